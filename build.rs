@@ -0,0 +1,49 @@
+//! Records the versions of the dependencies reported by `--version-verbose`,
+//! as locked in Cargo.lock when the binary is built
+
+use std::{env, fs, path::Path};
+
+/// The dependencies that parse crates.io and `cargo metadata` data
+const DEPENDENCIES: &[&str] = &["ureq", "cargo_metadata"];
+
+fn main() {
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").unwrap();
+    let lockfile = Path::new(&manifest_dir).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lockfile.display());
+    // Without a Cargo.lock, e.g. when built as part of another workspace, the versions are unknown
+    let lock = fs::read_to_string(&lockfile).unwrap_or_default();
+    for name in DEPENDENCIES {
+        let versions = locked_versions(&lock, name);
+        let versions = if versions.is_empty() {
+            "unknown".to_owned()
+        } else {
+            versions.join(", ")
+        };
+        println!(
+            "cargo:rustc-env=SUPPLY_CHAIN_{}_VERSION={}",
+            name.to_uppercase(),
+            versions
+        );
+    }
+}
+
+/// The versions of the package `name` in the lockfile, which can list several of them
+fn locked_versions(lock: &str, name: &str) -> Vec<String> {
+    let name_line = format!("name = \"{}\"", name);
+    let mut lines = lock.lines();
+    let mut versions = Vec::new();
+    while let Some(line) = lines.next() {
+        if line != name_line {
+            continue;
+        }
+        // Cargo always writes the version right after the name
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix("version = \""))
+            .and_then(|line| line.strip_suffix('"'));
+        if let Some(version) = version {
+            versions.push(version.to_owned());
+        }
+    }
+    versions
+}
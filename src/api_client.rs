@@ -1,8 +1,22 @@
-use std::time::{Duration, Instant};
+use crate::cli::TlsArgs;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::{
+    fmt, fs,
+    io::{self, ErrorKind},
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::warn;
 
 pub struct RateLimitedClient {
     last_request_time: Option<Instant>,
     agent: ureq::Agent,
+    token: Option<Redacted<String>>,
 }
 
 impl Default for RateLimitedClient {
@@ -10,25 +24,110 @@ impl Default for RateLimitedClient {
         RateLimitedClient {
             last_request_time: None,
             agent: ureq::agent(),
+            token: None,
         }
     }
 }
 
+/// A secret such as an API token, which is left out of `Debug` output so that it is never logged
+#[derive(Clone, PartialEq, Eq)]
+pub struct Redacted<T>(pub T);
+
+impl<T> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
 impl RateLimitedClient {
     pub fn new() -> Self {
         RateLimitedClient::default()
     }
 
+    /// Trusts the extra roots and skips verification as configured by `tls`
+    /// if any TLS option was given, otherwise the same client as [`new`](Self::new)
+    pub fn with_tls(tls: &TlsArgs) -> Result<Self, io::Error> {
+        if tls.ca_bundle.is_none() && !tls.allow_self_signed {
+            return Ok(RateLimitedClient::new());
+        }
+        // The same roots that ureq trusts by default
+        let mut roots = RootCertStore::empty();
+        let native = rustls_native_certs::load_native_certs().unwrap_or_else(|e| {
+            warn!("Could not load the root certificates of the system: {}", e);
+            Vec::new()
+        });
+        roots.add_parsable_certificates(native);
+        if let Some(path) = &tls.ca_bundle {
+            let bundle = fs::read(path).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("Could not read CA bundle {}: {}", path.display(), e),
+                )
+            })?;
+            for certificate in pem_certificates(&bundle, path)? {
+                roots.add(certificate).map_err(|e| {
+                    io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid certificate in CA bundle {}: {}", path.display(), e),
+                    )
+                })?;
+            }
+        }
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = rustls::ClientConfig::builder_with_provider(Arc::clone(&provider))
+            .with_safe_default_protocol_versions()
+            .map_err(io::Error::other)?;
+        let config = if tls.allow_self_signed {
+            warn!("TLS certificates are not verified because of `--allow-self-signed`.");
+            warn!("Anyone on the network path can tamper with the data about publishers!");
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(NoCertificateVerification(provider)))
+                .with_no_client_auth()
+        } else {
+            builder.with_root_certificates(roots).with_no_client_auth()
+        };
+        Ok(RateLimitedClient {
+            last_request_time: None,
+            agent: ureq::AgentBuilder::new()
+                .tls_config(Arc::new(config))
+                .build(),
+            token: None,
+        })
+    }
+
+    /// Authenticates the requests with the crates.io API token, if one is given
+    pub fn with_token(self, token: Option<Redacted<String>>) -> Self {
+        RateLimitedClient { token, ..self }
+    }
+
     pub fn get(&mut self, url: &str) -> ureq::Request {
+        self.request("GET", url)
+    }
+
+    pub fn head(&mut self, url: &str) -> ureq::Request {
+        self.request("HEAD", url)
+    }
+
+    pub fn post(&mut self, url: &str) -> ureq::Request {
+        self.request("POST", url)
+    }
+
+    fn request(&mut self, method: &str, url: &str) -> ureq::Request {
         self.wait_to_honor_rate_limit();
-        self.agent.get(url).set(
+        let request = self.agent.request(method, url).set(
             "User-Agent",
             "cargo supply-chain (https://github.com/rust-secure-code/cargo-supply-chain)",
-        )
+        );
+        match &self.token {
+            // crates.io takes the bare token, like cargo sends it
+            Some(Redacted(token)) => request.set("Authorization", token),
+            None => request,
+        }
     }
 
     /// Waits until at least 1 second has elapsed since last request,
-    /// as per <https://crates.io/data-access>
+    /// as per <https://crates.io/data-access>. The limit is the same with an API token.
     fn wait_to_honor_rate_limit(&mut self) {
         if let Some(prev_req_time) = self.last_request_time {
             let next_req_time = prev_req_time + Duration::from_secs(1);
@@ -39,3 +138,104 @@ impl RateLimitedClient {
         self.last_request_time = Some(Instant::now());
     }
 }
+
+/// The certificates in the PEM bundle read from `path`. Other PEM sections and text between
+/// them, such as comments, are skipped
+fn pem_certificates(bundle: &[u8], path: &Path) -> Result<Vec<CertificateDer<'static>>, io::Error> {
+    let certificates = CertificateDer::pem_slice_iter(bundle)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid certificate in CA bundle {}: {}", path.display(), e),
+            )
+        })?;
+    if certificates.is_empty() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("No certificates found in CA bundle {}", path.display()),
+        ));
+    }
+    Ok(certificates)
+}
+
+/// Accepts any certificate, for `--allow-self-signed`. The signatures of the handshake are still
+/// checked, so that the connection is at least with the owner of the certificate
+#[derive(Debug)]
+struct NoCertificateVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_pem_bundle() {
+        let path = Path::new("corporate.pem");
+        let bundle =
+            "# Corporate root\n-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n\
+            -----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----\n";
+        let certificates = pem_certificates(bundle.as_bytes(), path).unwrap();
+        let der: Vec<&[u8]> = certificates.iter().map(|cert| cert.as_ref()).collect();
+        assert_eq!(der, [&[0x00, 0x00, 0x00][..], &[0x04, 0x10, 0x41][..]]);
+
+        let truncated = "-----BEGIN CERTIFICATE-----\ntruncated\n";
+        assert!(pem_certificates(truncated.as_bytes(), path).is_err());
+        let error = pem_certificates(b"not a certificate", path).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No certificates found in CA bundle corporate.pem"
+        );
+    }
+
+    #[test]
+    fn redacted_token() {
+        let token = Redacted("cio_secret".to_owned());
+        assert_eq!(format!("{:?}", token), "[redacted]");
+        assert_eq!(format!("{:?}", Some(token)), "Some([redacted])");
+    }
+}
@@ -1,5 +1,12 @@
+use crate::api_client::Redacted;
+use crate::output::{
+    align::Column, fields::FieldList, ColorMode, ColorThreshold, ExportGraphFormat, GraphFormat,
+    JsonFormat, OutputFormat, ShellChoice, SortKey,
+};
+use crate::publishers::PublisherKind;
 use bpaf::*;
-use std::{path::PathBuf, time::Duration};
+use regex::Regex;
+use std::{num::NonZeroUsize, path::PathBuf, time::Duration};
 
 /// Arguments to be passed to `cargo metadata`
 #[derive(Clone, Debug, Bpaf)]
@@ -16,6 +23,29 @@ pub struct MetadataArgs {
     /// Ignore dev-only dependencies
     pub no_dev: bool,
 
+    /// Mark build-only dependencies with `[build]` in the output.
+    /// Their publishers' code runs during `cargo build`
+    #[bpaf(long("include-build-dependencies"), long("include-build-deps"))]
+    pub include_build_dependencies: bool,
+
+    /// Analyze the dependencies of all workspace members combined, listing shared ones once.
+    /// This is the default, the flag only spells it out
+    pub workspace: bool,
+
+    /// Only analyze the dependencies of the package in the current directory, or of the one
+    /// given by `--manifest-path`, instead of those of all workspace members.
+    /// All members of a virtual workspace are analyzed anyway. Conflicts with `--workspace`
+    pub current_package: bool,
+
+    /// Also report the publishers of the analyzed workspace members whose names are taken on
+    /// crates.io, i.e. only of the current package with `--current-package`. Members that are
+    /// not published are skipped silently
+    #[bpaf(long("include-published-members"), long("include-workspace-deps"))]
+    pub include_published_members: bool,
+
+    /// Only analyze direct dependencies, not the dependencies of dependencies
+    pub no_transitive: bool,
+
     // This is a `String` because we don't parse the value, just pass it on to `cargo metadata` blindly
     /// Space or comma separated list of features to activate
     #[bpaf(argument("FEATURES"))]
@@ -26,7 +56,7 @@ pub struct MetadataArgs {
     pub target: Option<String>,
 
     /// Path to Cargo.toml
-    #[bpaf(argument("PATH"))]
+    #[bpaf(argument("PATH"), complete_shell(ShellComp::File { mask: Some("*.toml") }))]
     pub manifest_path: Option<PathBuf>,
 }
 
@@ -34,28 +64,498 @@ pub struct MetadataArgs {
 #[derive(Clone, Debug, Bpaf)]
 #[bpaf(generate(args))]
 pub(crate) struct QueryCommandArgs {
+    // The flags that the config file can set are `None` unless given on the command line,
+    // see the methods of the same names for their values
+    #[bpaf(external(cache_max_age_option))]
+    pub cache_max_age: Option<Duration>,
+
     #[bpaf(external)]
-    pub cache_max_age: Duration,
+    pub max_age_warning: Option<Duration>,
 
     /// Make output more friendly towards tools such as `diff`
     #[bpaf(short, long)]
     pub diffable: bool,
+
+    /// Print a brief pass/fail summary; with the `json` subcommand, print only the summary
+    pub summary: bool,
+
+    #[bpaf(external)]
+    pub no_summary: Option<bool>,
+
+    #[bpaf(external)]
+    pub verbose: usize,
+
+    #[bpaf(external)]
+    pub offline: Option<bool>,
+
+    /// When the cache is used, skip the crates missing from it, e.g. ones published after
+    /// the last `update`, instead of fetching them from the live crates.io API.
+    /// The `json` subcommand lists them in `not_fetched`
+    pub no_default_registry_fallback: bool,
+
+    /// Include the email addresses of users. Only available from the live crates.io API
+    pub include_email: bool,
+
+    /// Replace the logins of publishers with opaque IDs such as `user_3f0c85a1d2b7e694`,
+    /// for reports that are shared publicly. The IDs are listed on stderr for reference.
+    /// They are keyed by `--anonymize-key`, or else by a secret generated once and kept in
+    /// the cache directory, so they only match between reports made with the same key.
+    /// The publisher filters see the real logins, `--publishers-file` sees the IDs
+    pub anonymize: bool,
+
+    #[bpaf(external)]
+    pub anonymize_key: Option<Redacted<String>>,
+
+    /// Check the arguments, the files they refer to and the cache, and print what would be
+    /// fetched, without contacting crates.io or printing a report
+    pub dry_run: bool,
+
+    /// Do not warn about dependencies on yanked versions. Those are only detected with a cache
+    /// downloaded by `cargo supply-chain update --with-yanked`
+    #[bpaf(long("ignore-yanked"), long("ignore-version-yanked"))]
+    pub ignore_yanked: bool,
+
+    /// Only report crates.io crates that are not in the Cargo.lock of the given git revision,
+    /// e.g. the base branch of a pull request
+    #[bpaf(argument("GIT_REF"))]
+    pub since_commit: Option<String>,
+
+    /// Only analyze the crates.io crates whose names match the regular expression PATTERN
+    #[bpaf(long("crate-filter-regex"), argument("PATTERN"))]
+    pub crate_filter: Option<Regex>,
+
+    /// Pause after fetching every N crates from the live crates.io API,
+    /// until Enter is pressed or for `--batch-pause-secs`
+    #[bpaf(argument("N"))]
+    pub batch_size: Option<NonZeroUsize>,
+
+    /// With `--batch-size`, sleep for S seconds between batches instead of waiting for Enter
+    #[bpaf(argument("S"))]
+    pub batch_pause_secs: Option<u64>,
+
+    /// Annotate publishers with `[VERIFIED]` or `[UNTRUSTED]` and the identities listed in
+    /// the JSON file at PATH, which maps logins to `verified_name`, `email`, `organization`
+    /// and `trusted`
+    #[bpaf(argument("PATH"))]
+    pub publishers_file: Option<PathBuf>,
+
+    #[bpaf(external)]
+    pub separator: Option<String>,
+
+    #[bpaf(external)]
+    pub config_file: Option<PathBuf>,
+
+    // Set from the `format` of the config file, the default below `CARGO_SUPPLY_CHAIN_FORMAT`
+    #[bpaf(pure(None))]
+    pub config_format: Option<OutputFormat>,
+
+    #[bpaf(external(tls_args))]
+    pub tls: TlsArgs,
+
+    #[bpaf(external)]
+    pub crates_io_token: Option<Redacted<String>>,
 }
 
+// Parsed once per run, so boxing `Info` to shrink the enum is not worth the noise
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Bpaf)]
 pub(crate) enum PrintJson {
     /// Print JSON schema and exit
     #[bpaf(long("print-schema"))]
     Schema,
 
+    /// Print the example template for `--output-template`, which shows what its context
+    /// provides, and exit
+    #[bpaf(long("print-template"))]
+    Template,
+
     Info {
         #[bpaf(external)]
         args: QueryCommandArgs,
+        #[bpaf(external, optional)]
+        save: Option<SaveOnSuccess>,
+        /// Output format: `json` or `ndjson`, which prints one line per crate as soon as it is
+        /// processed. Defaults to `json`.
+        #[bpaf(argument("FORMAT"))]
+        format: Option<JsonFormat>,
+        /// List available output formats and exit
+        help_formats: bool,
+        /// Print the graph of workspace packages, crates.io crates and publishers instead,
+        /// with dependency and ownership edges: `dot`, `gexf`, `graphml` or `json-graph`
+        #[bpaf(argument("FORMAT"))]
+        export_graph: Option<ExportGraphFormat>,
+        /// Print text rendered from a Tera template instead. The context contains the JSON output
+        /// along with `crates`, `users`, `teams`, `stats` and `metadata`,
+        /// see `--print-template` for an example
+        #[bpaf(argument("PATH"))]
+        output_template: Option<PathBuf>,
+        /// Also write the JSON schema of the output to the file at PATH, e.g. to validate
+        /// the output saved by CI
+        #[bpaf(argument("PATH"))]
+        output_schema: Option<PathBuf>,
         #[bpaf(external)]
         meta_args: MetadataArgs,
     },
 }
 
+impl QueryCommandArgs {
+    /// How long the cache is considered valid
+    pub fn cache_max_age(&self) -> Duration {
+        self.cache_max_age.unwrap_or(DEFAULT_CACHE_MAX_AGE)
+    }
+
+    /// Whether to only use the local cache
+    pub fn offline(&self) -> bool {
+        self.offline.unwrap_or(false)
+    }
+
+    /// Whether to leave out the informational notes after the list
+    pub fn no_summary(&self) -> bool {
+        self.no_summary.unwrap_or(false)
+    }
+
+    /// Delimiter of the crates and publishers listed on one line
+    pub fn separator(&self) -> &str {
+        self.separator.as_deref().unwrap_or(DEFAULT_SEPARATOR)
+    }
+}
+
+/// Arguments controlling the warnings about crates that cannot be audited,
+/// for projects where non-crates.io dependencies are expected and audited separately
+#[derive(Clone, Debug, Bpaf)]
+#[bpaf(generate(reporting))]
+pub(crate) struct ReportingArgs {
+    /// Do not list the crates that come from a local directory
+    pub ignore_local: bool,
+
+    /// Do not list the crates that come from git repositories or other registries
+    pub ignore_foreign: bool,
+
+    /// `None` unless given on the command line, see [`quiet`](Self::quiet)
+    #[bpaf(external)]
+    pub quiet: Option<bool>,
+}
+
+impl ReportingArgs {
+    /// Whether to print fewer notes. Off unless turned on by `--quiet` or the config file
+    pub fn quiet(&self) -> bool {
+        self.quiet.unwrap_or(false)
+    }
+
+    pub fn ignores_local(&self) -> bool {
+        self.ignore_local || self.quiet()
+    }
+
+    pub fn ignores_foreign(&self) -> bool {
+        self.ignore_foreign || self.quiet()
+    }
+}
+
+/// Arguments for `--align`, shared by `crates` and `publishers`
+#[derive(Clone, Debug, Bpaf)]
+#[bpaf(generate(align_args))]
+pub(crate) struct AlignArgs {
+    /// Pad names to a common width, so that the lists after them line up in a column.
+    /// Conflicts with `--diffable`
+    pub align: bool,
+
+    /// With `--align`, truncate names longer than N characters
+    #[bpaf(argument("N"))]
+    pub max_column_width: Option<usize>,
+}
+
+impl AlignArgs {
+    /// The column to pad the `labels` to, if `--align` was given
+    pub fn column<'a>(&self, labels: impl IntoIterator<Item = &'a str>) -> Option<Column> {
+        self.align
+            .then(|| Column::fitting(labels, self.max_column_width))
+    }
+}
+
+/// TLS settings of connections to crates.io, for networks that intercept TLS with a private CA
+#[derive(Clone, Debug, Bpaf)]
+#[bpaf(generate(tls_args))]
+pub(crate) struct TlsArgs {
+    /// Also trust the root certificates in the PEM file at PATH
+    #[bpaf(long, env("CARGO_SUPPLY_CHAIN_CA_BUNDLE"), argument("PATH"))]
+    pub ca_bundle: Option<PathBuf>,
+
+    /// Do not verify TLS certificates at all. Only use this as a last resort!
+    #[bpaf(long, env("CARGO_SUPPLY_CHAIN_ALLOW_SELF_SIGNED"))]
+    pub allow_self_signed: bool,
+}
+
+/// Arguments specific to the `crates` subcommand
+#[derive(Clone, Debug, Bpaf)]
+#[bpaf(generate(crates_args))]
+pub(crate) struct CratesArgs {
+    /// Link crate names and user logins to their crates.io pages. The links are terminal
+    /// hyperlinks when `--color` allows escape sequences, which it does by default on a
+    /// terminal, and URLs in parentheses otherwise
+    pub link_crates: bool,
+
+    /// Show how many dependency hops away from the workspace each crate is
+    pub show_depth: bool,
+
+    /// Shorten GitHub teams to their organization, e.g. `org:tokio-rs`. Ignored with `--diffable`
+    pub show_org: bool,
+
+    /// Append how many users and teams can publish each crate, e.g. `[2 users, 1 team]`
+    pub show_counts: bool,
+
+    /// Append the crates.io categories of each crate, e.g. `[authentication, cryptography]`.
+    /// They are read from the cache, so this needs a recent
+    /// `cargo supply-chain update --with-categories`
+    pub show_crate_categories: bool,
+
+    /// With `--show-crate-categories`, list the crates under a heading for each category
+    pub group_by_category: bool,
+
+    /// Append how often each crate was downloaded, e.g. `(2.5M total, 150K recent)`.
+    /// The counts are fetched from the live crates.io API and reused for 24 hours.
+    /// If fetching them fails, the crates are listed without them
+    pub show_download_count: bool,
+
+    /// Only list the crates that have no publishers on record, which may indicate
+    /// a supply chain risk. Otherwise they are marked with `[NO OWNER]`
+    pub show_missing: bool,
+
+    /// Only list the crates that are procedural macros, which run arbitrary code at compile time
+    /// like build scripts. Otherwise they are marked with `[proc-macro]`
+    pub only_proc_macros: bool,
+
+    /// Show `[NO OWNER]` in red: `auto`, `always` or `never`. Defaults to `never`,
+    /// except for the hyperlinks of `--link-crates`, which default to `auto`.
+    #[bpaf(argument("WHEN"))]
+    pub color: Option<ColorMode>,
+
+    /// Order of the crates: `name`; `publishers`, fewest first; `risk`, crates owned by teams
+    /// and then by the most users first; or `downloads`, most first, which fetches the download
+    /// counts like `--show-download-count`. Defaults to `risk`. Ignored with `--diffable`,
+    /// which always sorts by name
+    #[bpaf(argument("KEY"))]
+    pub sort_by: Option<SortKey>,
+
+    /// Browse the results in a terminal UI. Requires the `tui` feature
+    pub interactive: bool,
+
+    /// Output format: `text`, `json`, `github-actions` or `github-markdown`, a table to post
+    /// as a comment on a pull request. Defaults to `CARGO_SUPPLY_CHAIN_FORMAT`,
+    /// then to `github-actions` when running in GitHub Actions and to `text` otherwise.
+    #[bpaf(argument("FORMAT"))]
+    pub format: Option<OutputFormat>,
+
+    /// Print the JSON schema of `--format json` output and exit
+    pub print_schema: bool,
+
+    /// List available output formats and exit
+    pub help_formats: bool,
+
+    #[bpaf(external)]
+    pub fields: Option<FieldList>,
+
+    /// With `--fields`, start the output with `#` comment lines describing how it was generated
+    pub prepend_headers: bool,
+
+    #[bpaf(external(align_args))]
+    pub align: AlignArgs,
+}
+
+/// Arguments specific to the `publishers` subcommand
+#[derive(Clone, Debug, Bpaf)]
+#[bpaf(generate(publishers_args))]
+pub(crate) struct PublishersArgs {
+    /// Output format: `text` or `json`. Defaults to `CARGO_SUPPLY_CHAIN_FORMAT`, then to `text`.
+    #[bpaf(argument("FORMAT"))]
+    pub format: Option<OutputFormat>,
+
+    /// Print the JSON schema of `--format json` output and exit
+    pub print_schema: bool,
+
+    /// List available output formats and exit
+    pub help_formats: bool,
+
+    #[bpaf(external)]
+    pub fields: Option<FieldList>,
+
+    /// With `--fields`, start the output with `#` comment lines describing how it was generated
+    pub prepend_headers: bool,
+
+    #[bpaf(external(align_args))]
+    pub align: AlignArgs,
+
+    /// Mark publishers as `[HIGH]`, `[MEDIUM]` or `[LOW]` by how many of the crates they can publish
+    pub trust_level: bool,
+
+    /// With `--trust-level`, publishers of at least N crates are `[HIGH]`. Defaults to 50
+    #[bpaf(argument("N"), fallback(50))]
+    pub trust_high_min: usize,
+
+    /// With `--trust-level`, publishers of at least N crates are `[MEDIUM]`. Defaults to 10
+    #[bpaf(argument("N"), fallback(10))]
+    pub trust_medium_min: usize,
+
+    /// Color publishers by how many of the crates they can publish: `auto`, `always` or `never`.
+    /// Defaults to `never`.
+    #[bpaf(argument("WHEN"))]
+    pub color: Option<ColorMode>,
+
+    /// With `--color`, publishers of at least N1 crates are yellow and of at least N2 red.
+    /// Defaults to 3,10
+    #[bpaf(argument("N1,N2"), fallback(ColorThreshold::default()))]
+    pub color_threshold: ColorThreshold,
+
+    /// Only show the publishers whose logins match the regular expression PATTERN
+    #[bpaf(long("publisher-filter-regex"), argument("PATTERN"))]
+    pub publisher_filter: Option<Regex>,
+
+    /// Only show the publisher with the numeric crates.io ID, which stays the same when
+    /// the login is renamed. Can be given multiple times, and combined with
+    /// `--publisher-filter-regex` to show the publishers matching either
+    #[bpaf(argument("ID"), many)]
+    pub publisher_id: Vec<u64>,
+
+    /// Only show users, not teams. Conflicts with `--only-teams`
+    pub only_users: bool,
+
+    /// Only show teams, not users. Conflicts with `--only-users`
+    pub only_teams: bool,
+
+    /// Exit with code 2 if any publishers are listed, and 0 otherwise. Combine with
+    /// `--only-users` to assert in CI that only teams can publish the dependencies
+    pub check: bool,
+
+    /// With `--check`, POST the listed publishers and their crates as JSON to URL when it fails,
+    /// e.g. to a Slack or PagerDuty webhook. Only `publishers --check` reports violations,
+    /// so the other subcommands do not have this flag
+    #[bpaf(argument("URL"))]
+    pub webhook: Option<String>,
+
+    /// Show the versions of the crates in the dependency graph, e.g. `serde (1.0.197)`
+    pub show_versions: bool,
+}
+
+impl PublishersArgs {
+    /// The thresholds for `--color`, if colors are enabled
+    pub fn colors(&self) -> Option<ColorThreshold> {
+        self.color
+            .is_some_and(ColorMode::enabled)
+            .then_some(self.color_threshold)
+    }
+
+    /// Whether `--publisher-filter-regex` or `--publisher-id` was given
+    pub fn filters_publishers(&self) -> bool {
+        self.publisher_filter.is_some() || !self.publisher_id.is_empty()
+    }
+
+    /// Whether a publisher passes `--publisher-filter-regex` or `--publisher-id`
+    pub fn shows_publisher(&self, login: &str, id: u64) -> bool {
+        if !self.filters_publishers() {
+            return true;
+        }
+        self.publisher_filter
+            .as_ref()
+            .is_some_and(|filter| filter.is_match(login))
+            || self.publisher_id.contains(&id)
+    }
+
+    /// Whether `--only-users` or `--only-teams` was given
+    pub fn filters_kind(&self) -> bool {
+        self.only_users || self.only_teams
+    }
+
+    /// Whether publishers of this kind pass `--only-users` and `--only-teams`
+    pub fn shows_kind(&self, kind: PublisherKind) -> bool {
+        match kind {
+            PublisherKind::user => !self.only_teams,
+            PublisherKind::team => !self.only_users,
+        }
+    }
+
+    /// The thresholds for `--trust-level`, if it was requested
+    pub fn trust_tiers(&self) -> Option<TrustLevel> {
+        self.trust_level.then_some(TrustLevel {
+            high: self.trust_high_min,
+            medium: self.trust_medium_min,
+        })
+    }
+}
+
+/// Minimum crate counts for publishers to be considered high and medium trust
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TrustLevel {
+    pub high: usize,
+    pub medium: usize,
+}
+
+impl TrustLevel {
+    pub fn tier(&self, crate_count: usize) -> &'static str {
+        if crate_count >= self.high {
+            "[HIGH]"
+        } else if crate_count >= self.medium {
+            "[MEDIUM]"
+        } else {
+            "[LOW]"
+        }
+    }
+}
+
+/// Arguments for hiding crates based on how many users and teams can publish them
+#[derive(Clone, Debug, Bpaf)]
+#[bpaf(generate(owner_count))]
+pub(crate) struct OwnerCountArgs {
+    /// Hide crates with fewer than N owners, counting both users and teams
+    #[bpaf(argument("N"))]
+    pub min_owners: Option<usize>,
+
+    #[bpaf(external)]
+    pub max_owners: Option<usize>,
+}
+
+impl OwnerCountArgs {
+    /// Whether a crate with this many owners should be shown
+    pub fn matches(&self, owners: usize) -> bool {
+        self.min_owners.is_none_or(|min| owners >= min)
+            && self.max_owners.is_none_or(|max| owners <= max)
+    }
+}
+
+fn fields() -> impl Parser<Option<FieldList>> {
+    long("fields")
+        .help(
+            "\
+Only print the given comma separated fields, one line per crate and publisher.
+Valid fields: crate, publisher, kind, version, repository, owner-count.
+With `--format json`, print an array of objects with only these keys.",
+        )
+        .argument::<FieldList>("FIELD,...")
+        .optional()
+}
+
+fn max_owners() -> impl Parser<Option<usize>> {
+    let max_owners = long("max-owners")
+        .help("Hide crates with more than N owners, counting both users and teams")
+        .argument::<usize>("N");
+    let single_owner = long("single-owner")
+        .help("Only show crates with at most one owner, same as '--max-owners 1'")
+        .req_flag(1);
+    construct!([max_owners, single_owner]).optional()
+}
+
+/// Arguments controlling the snapshots written by `json --save-on-success`
+#[derive(Clone, Debug, Bpaf)]
+#[bpaf(generate(save))]
+pub(crate) struct SaveOnSuccess {
+    /// Save a pretty-printed copy of the output to a timestamped file in DIR
+    #[bpaf(argument("DIR"))]
+    pub save_on_success: PathBuf,
+
+    /// Delete all but the N most recent snapshots in DIR, which include the new one.
+    /// N must be at least 1
+    #[bpaf(argument("N"))]
+    pub keep_last: Option<NonZeroUsize>,
+}
+
 /// Gather author, contributor and publisher data on crates in your dependency graph
 ///
 ///
@@ -74,6 +574,15 @@ pub(crate) enum CliArgs {
         #[bpaf(external)]
         args: QueryCommandArgs,
         #[bpaf(external)]
+        owner_count: OwnerCountArgs,
+        #[bpaf(external)]
+        publishers_args: PublishersArgs,
+        #[bpaf(external)]
+        reporting: ReportingArgs,
+        /// Only print the number of crates and unique publishers.
+        /// Conflicts with `--diffable` and `--format`
+        count: bool,
+        #[bpaf(external)]
         meta_args: MetadataArgs,
     },
 
@@ -87,6 +596,15 @@ pub(crate) enum CliArgs {
         #[bpaf(external)]
         args: QueryCommandArgs,
         #[bpaf(external)]
+        owner_count: OwnerCountArgs,
+        #[bpaf(external)]
+        crates_args: CratesArgs,
+        #[bpaf(external)]
+        reporting: ReportingArgs,
+        /// Only print the number of crates and unique publishers.
+        /// Conflicts with `--diffable` and `--format`
+        count: bool,
+        #[bpaf(external)]
         meta_args: MetadataArgs,
     },
 
@@ -99,6 +617,71 @@ pub(crate) enum CliArgs {
     #[bpaf(command)]
     Json(#[bpaf(external(print_json))] PrintJson),
 
+    /// Detailed info on the publishers of a single crate, which need not be a dependency
+    ///
+    ///
+    /// If a local cache created by 'update' subcommand is present and up to date,
+    /// it will be used. Otherwise live data will be fetched from the crates.io API.
+    #[bpaf(command)]
+    Inspect {
+        #[bpaf(external)]
+        args: QueryCommandArgs,
+        /// Print the report as JSON
+        json: bool,
+        /// Name of the crate on crates.io
+        #[bpaf(positional("CRATE"))]
+        crate_name: String,
+    },
+
+    /// List all crates on crates.io that a user or team can publish
+    ///
+    ///
+    /// Requires an up to date local cache created by 'update' subcommand,
+    /// since the crates.io API cannot be queried by publisher efficiently.
+    #[bpaf(command("owned-by"))]
+    OwnedBy {
+        #[bpaf(external)]
+        args: QueryCommandArgs,
+        /// Only match a `user` or only a `team` with this login
+        #[bpaf(argument("KIND"))]
+        kind: Option<PublisherKind>,
+        /// Login of the user, or of the team such as `github:rust-lang:core`
+        #[bpaf(positional("LOGIN"))]
+        login: String,
+    },
+
+    /// Graph of the crates in the dependency graph and their crates.io publishers
+    ///
+    ///
+    /// Crates are colored by their number of publishers. The DOT output can be rendered
+    /// with Graphviz, e.g. `cargo supply-chain trust-graph | dot -Tsvg > graph.svg`
+    #[bpaf(command("trust-graph"))]
+    TrustGraph {
+        #[bpaf(external)]
+        args: QueryCommandArgs,
+        /// Output format: `dot` or `mermaid`, which GitHub renders in Markdown. Defaults to `dot`
+        #[bpaf(argument("FORMAT"), fallback(GraphFormat::Dot))]
+        format: GraphFormat,
+        /// List available output formats and exit
+        help_formats: bool,
+        #[bpaf(external)]
+        meta_args: MetadataArgs,
+    },
+
+    /// Delete stale files from the local cache without downloading anything
+    ///
+    ///
+    /// Always deletes leftovers of interrupted downloads and other files the cache does not use.
+    /// This includes the partially downloaded tables (`.part` files) and `progress.json`,
+    /// so an interrupted `update` starts over instead of resuming.
+    #[bpaf(command)]
+    Prune {
+        #[bpaf(external)]
+        older_than: Option<Duration>,
+        /// Only list the files that would be deleted
+        dry_run: bool,
+    },
+
     /// Download the latest daily dump from crates.io to speed up other commands
     ///
     ///
@@ -112,10 +695,92 @@ pub(crate) enum CliArgs {
     Update {
         #[bpaf(external)]
         cache_max_age: Duration,
+        /// Refuse to access the network. Fails unless only invalidating crates
+        offline: bool,
+        #[bpaf(external(tls_args))]
+        tls: TlsArgs,
+        /// Forget the cached owners of CRATE so that they are fetched from the live API instead,
+        /// without downloading the dump. Can be given multiple times
+        #[bpaf(argument("CRATE"), many)]
+        invalidate_crate: Vec<String>,
+        /// Also store the yanked versions, so that the querying commands warn about them.
+        /// Their table is the largest in the dump, so it is only downloaded when asked for
+        with_yanked: bool,
+        /// Also store the crates.io categories of the crates, for `crates --show-crate-categories`
+        with_categories: bool,
+        /// Only check whether a newer dump is available. Exits with code 2 if it is
+        update_check: bool,
+        /// Output format of `--update-check`: `text` or `json`.
+        /// Defaults to `CARGO_SUPPLY_CHAIN_FORMAT`, then to `text`.
+        #[bpaf(
+            argument("FORMAT"),
+            guard(
+                is_update_check_format,
+                "`--update-check` only supports the `text` and `json` formats"
+            )
+        )]
+        format: Option<OutputFormat>,
+        /// List available output formats of `--update-check` and exit
+        help_formats: bool,
+        /// Abort the download once the dump exceeds MB mebibytes, e.g. on a metered connection.
+        /// Defaults to 2048
+        #[bpaf(
+            long,
+            env("CARGO_SUPPLY_CHAIN_MAX_DOWNLOAD_SIZE"),
+            argument("MB"),
+            fallback(2048)
+        )]
+        max_download_size: u64,
+    },
+
+    /// Show the location, age and contents of the local cache
+    #[bpaf(command("cache-info"))]
+    CacheInfo {
+        #[bpaf(external)]
+        cache_max_age: Duration,
+        /// Print the information as JSON
+        json: bool,
+        /// List each file of the cache with its size and modification time instead,
+        /// to diagnose downloads that updated only some of them
+        list_cache_files: bool,
+    },
+
+    /// List the available subcommands and exit
+    #[bpaf(long("list"))]
+    List,
+
+    /// Print version information for bug reports, including the state of the cache
+    #[bpaf(long("version-verbose"))]
+    VersionVerbose,
+
+    /// Write a `supply-chain.toml` documenting all settings to the current directory and exit
+    #[bpaf(long("write-config"))]
+    WriteConfig,
+
+    /// Print a script that makes the shell complete subcommands and flags
+    ///
+    ///
+    /// The script completes the `cargo-supply-chain` binary. See the README for how to install it.
+    #[bpaf(command("generate-completions"))]
+    GenerateCompletions {
+        /// `bash`, `zsh`, `fish` or `elvish`
+        #[bpaf(positional("SHELL"))]
+        shell: ShellChoice,
     },
 }
 
+/// How long the cache is valid without `--cache-max-age`
+pub const DEFAULT_CACHE_MAX_AGE: Duration = Duration::from_secs(48 * 3600);
+
+/// Delimiter of lists on one line without `--separator`
+pub const DEFAULT_SEPARATOR: &str = ", ";
+
 fn cache_max_age() -> impl Parser<Duration> {
+    cache_max_age_option().map(|max_age| max_age.unwrap_or(DEFAULT_CACHE_MAX_AGE))
+}
+
+/// Without the default, so that the config file can fill it in
+fn cache_max_age_option() -> impl Parser<Option<Duration>> {
     long("cache-max-age")
         .help(
             "\
@@ -125,7 +790,146 @@ If not specified, the cache is considered valid for 48 hours.",
         )
         .argument::<String>("AGE")
         .parse(|text| humantime::parse_duration(&text))
-        .fallback(Duration::from_secs(48 * 3600))
+        .optional()
+}
+
+/// `Some(true)` with the flag `on`, `Some(false)` with `off` and `None` without either,
+/// so that a flag turned on by the config file can be turned off on the command line
+fn toggle(on: NamedArg, off: NamedArg) -> impl Parser<Option<bool>> {
+    let on = on.req_flag(true);
+    let off = off.req_flag(false);
+    construct!([on, off]).optional()
+}
+
+fn offline() -> impl Parser<Option<bool>> {
+    toggle(
+        long("offline").help("Only use the local cache, never fetch live data from crates.io"),
+        long("no-offline")
+            .help("Fetch live data if needed, even if the config file sets `offline`"),
+    )
+}
+
+fn no_summary() -> impl Parser<Option<bool>> {
+    toggle(
+        long("no-summary")
+            .help("Do not print the informational notes after the list of crates or publishers"),
+        long("notes").help(
+            "Print the informational notes after the list, even if the config file sets `no-summary`",
+        ),
+    )
+}
+
+fn quiet() -> impl Parser<Option<bool>> {
+    toggle(
+        short('q')
+            .long("quiet")
+            .help("Print fewer notes to stderr. Implies `--ignore-local` and `--ignore-foreign`"),
+        long("no-quiet").help("Print all notes, even if the config file sets `quiet`"),
+    )
+}
+
+fn verbose() -> impl Parser<usize> {
+    short('v')
+        .long("verbose")
+        .help("Log what is going on to stderr. Pass twice for debug output")
+        .req_flag(())
+        .many()
+        .map(|flags| flags.len())
+}
+
+fn older_than() -> impl Parser<Option<Duration>> {
+    long("older-than")
+        .help(
+            "\
+Delete the entire cache if it is older than specified,
+except the secret key of `--anonymize`. The format is the same as for `--cache-max-age`.",
+        )
+        .argument::<String>("AGE")
+        .parse(|text| humantime::parse_duration(&text))
+        .optional()
+}
+
+fn max_age_warning() -> impl Parser<Option<Duration>> {
+    long("max-age-warning")
+        .help(
+            "\
+Warn when using a cache older than specified, while it is still valid.
+The format is the same as for `--cache-max-age`.",
+        )
+        .argument::<String>("AGE")
+        .parse(|text| humantime::parse_duration(&text))
+        .optional()
+}
+
+fn is_update_check_format(format: &Option<OutputFormat>) -> bool {
+    matches!(format, None | Some(OutputFormat::Text | OutputFormat::Json))
+}
+
+fn separator() -> impl Parser<Option<String>> {
+    long("separator")
+        .help(
+            "\
+Separate the crates and publishers listed on one line with SEP,
+e.g. `;` for spreadsheets or `|` for `cut -d'|'`. Defaults to `, `.",
+        )
+        .argument::<String>("SEP")
+        .guard(
+            |sep| !sep.contains('\n'),
+            "the separator cannot contain a newline",
+        )
+        .optional()
+}
+
+fn config_file() -> impl Parser<Option<PathBuf>> {
+    long("config-file")
+        .help(
+            "\
+Read the defaults for flags from the TOML file at PATH instead of `supply-chain.toml`
+in the workspace or one of its parents. Flags on the command line take precedence.
+Run with `--write-config` for a template documenting all settings.",
+        )
+        .argument::<PathBuf>("PATH")
+        .complete_shell(ShellComp::File {
+            mask: Some("*.toml"),
+        })
+        .optional()
+}
+
+/// Read from the environment here rather than with `env`, because that would show the token
+/// in `--help`
+fn crates_io_token() -> impl Parser<Option<Redacted<String>>> {
+    long("crates-io-token")
+        .help(
+            "\
+Authenticate requests to the live crates.io API with the API token TOKEN,
+also read from the `CRATES_IO_TOKEN` environment variable",
+        )
+        .argument::<String>("TOKEN")
+        .optional()
+        .map(|token| {
+            token
+                .or_else(|| std::env::var("CRATES_IO_TOKEN").ok())
+                .filter(|token| !token.is_empty())
+                .map(Redacted)
+        })
+}
+
+/// Read from the environment here rather than with `env`, like `--crates-io-token`
+fn anonymize_key() -> impl Parser<Option<Redacted<String>>> {
+    long("anonymize-key")
+        .help(
+            "\
+Derive the IDs of `--anonymize` from the secret KEY instead of the key in the
+cache directory, e.g. to get the same IDs on every CI run. Also read from the
+`CARGO_SUPPLY_CHAIN_ANONYMIZE_KEY` environment variable",
+        )
+        .argument::<String>("KEY")
+        .optional()
+        .map(|key| {
+            key.or_else(|| std::env::var("CARGO_SUPPLY_CHAIN_ANONYMIZE_KEY").ok())
+                .filter(|key| !key.is_empty())
+                .map(Redacted)
+        })
 }
 
 #[cfg(test)]
@@ -149,15 +953,163 @@ mod tests {
     }
 
     #[test]
-    fn test_accepted_query_options() {
+    fn test_max_age_warning_parser() {
         for command in ["crates", "publishers", "json"] {
-            let _ = args_parser().run_inner(&[command][..]).unwrap();
-            let _ = args_parser().run_inner(&[command, "-d"][..]).unwrap();
-            let _ = args_parser()
-                .run_inner(&[command, "--diffable"][..])
-                .unwrap();
-            let _ = args_parser()
-                .run_inner(&[command, "--cache-max-age=7d"][..])
+            let _ = parse_args(&[command, "--max-age-warning", "3d"]).unwrap();
+            let _ = parse_args(&[command, "--cache-max-age=1w", "--max-age-warning=3d"]).unwrap();
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--max-age-warning"]).is_err());
+        assert!(parse_args(&["crates", "--max-age-warning=3"]).is_err());
+        assert!(parse_args(&["update", "--max-age-warning=3d"]).is_err());
+    }
+
+    #[test]
+    fn test_verbose_option() {
+        for command in ["crates", "publishers", "json"] {
+            let verbosity = |args: &[&str]| match parse_args(args).unwrap() {
+                CliArgs::Publishers { args, .. } | CliArgs::Crates { args, .. } => args.verbose,
+                CliArgs::Json(PrintJson::Info { args, .. }) => args.verbose,
+                _ => unreachable!(),
+            };
+            assert_eq!(verbosity(&[command]), 0);
+            assert_eq!(verbosity(&[command, "-v"]), 1);
+            assert_eq!(verbosity(&[command, "--verbose"]), 1);
+            assert_eq!(verbosity(&[command, "-v", "-v"]), 2);
+            assert_eq!(verbosity(&[command, "-vv"]), 2);
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "-v"]).is_err());
+    }
+
+    #[test]
+    fn test_offline_option() {
+        for command in ["crates", "publishers", "json", "update"] {
+            let _ = parse_args(&[command, "--offline"]).unwrap();
+            let _ = parse_args(&[command, "--offline", "--cache-max-age=7d"]).unwrap();
+        }
+        let offline = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Crates { args, .. } => args.offline,
+            _ => unreachable!(),
+        };
+        assert_eq!(offline(&["crates"]), None);
+        assert_eq!(offline(&["crates", "--offline"]), Some(true));
+        assert_eq!(offline(&["crates", "--no-offline"]), Some(false));
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--offline", "--no-offline"]).is_err());
+        assert!(parse_args(&["update", "--no-offline"]).is_err());
+        assert!(parse_args(&["json", "--print-schema", "--offline"]).is_err());
+    }
+
+    #[test]
+    fn test_no_default_registry_fallback_option() {
+        for command in ["crates", "publishers", "json"] {
+            let _ = parse_args(&[command, "--no-default-registry-fallback"]).unwrap();
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "--no-default-registry-fallback"]).is_err());
+        assert!(parse_args(&["json", "--print-schema", "--no-default-registry-fallback"]).is_err());
+    }
+
+    #[test]
+    fn test_include_email_option() {
+        for command in ["crates", "publishers", "json", "inspect serde"] {
+            let mut args: Vec<&str> = command.split(' ').collect();
+            args.push("--include-email");
+            let _ = parse_args(&args).unwrap();
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "--include-email"]).is_err());
+    }
+
+    #[test]
+    fn test_anonymize_option() {
+        for command in ["crates", "publishers", "json", "inspect serde"] {
+            let mut args: Vec<&str> = command.split(' ').collect();
+            args.push("--anonymize");
+            let _ = parse_args(&args).unwrap();
+        }
+        let CliArgs::Publishers { args, .. } =
+            parse_args(&["publishers", "--anonymize", "--anonymize-key", "secret"]).unwrap()
+        else {
+            unreachable!()
+        };
+        assert_eq!(args.anonymize_key, Some(Redacted("secret".to_owned())));
+        assert!(!format!("{:?}", args).contains("secret"));
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "--anonymize"]).is_err());
+        assert!(parse_args(&["crates", "--anonymize=yes"]).is_err());
+        assert!(parse_args(&["crates", "--anonymize-key"]).is_err());
+        assert!(parse_args(&["update", "--anonymize-key", "secret"]).is_err());
+    }
+
+    #[test]
+    fn test_dry_run_option() {
+        let dry_run = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Crates { args, .. }
+            | CliArgs::Publishers { args, .. }
+            | CliArgs::Json(PrintJson::Info { args, .. })
+            | CliArgs::TrustGraph { args, .. }
+            | CliArgs::Inspect { args, .. }
+            | CliArgs::OwnedBy { args, .. } => args.dry_run,
+            _ => unreachable!(),
+        };
+        assert!(!dry_run(&["crates"]));
+        for command in [
+            "crates",
+            "publishers",
+            "json",
+            "trust-graph",
+            "inspect serde",
+            "owned-by dtolnay",
+        ] {
+            let mut args: Vec<&str> = command.split(' ').collect();
+            args.push("--dry-run");
+            assert!(dry_run(&args));
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "--dry-run"]).is_err());
+        assert!(parse_args(&["json", "--print-schema", "--dry-run"]).is_err());
+    }
+
+    #[test]
+    fn test_ignore_yanked_option() {
+        let ignore_yanked = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Crates { args, .. }
+            | CliArgs::Publishers { args, .. }
+            | CliArgs::Json(PrintJson::Info { args, .. }) => args.ignore_yanked,
+            _ => unreachable!(),
+        };
+        assert!(!ignore_yanked(&["crates"]));
+        for command in ["crates", "publishers", "json"] {
+            assert!(ignore_yanked(&[command, "--ignore-yanked"]));
+            assert!(ignore_yanked(&[command, "--ignore-version-yanked"]));
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "--ignore-yanked"]).is_err());
+    }
+
+    #[test]
+    fn test_since_commit_option() {
+        for command in ["crates", "publishers", "json"] {
+            let _ = parse_args(&[command, "--since-commit", "origin/main"]).unwrap();
+            let _ = parse_args(&[command, "--since-commit=HEAD~1", "-d"]).unwrap();
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--since-commit"]).is_err());
+        assert!(parse_args(&["update", "--since-commit=HEAD"]).is_err());
+    }
+
+    #[test]
+    fn test_accepted_query_options() {
+        for command in ["crates", "publishers", "json"] {
+            let _ = args_parser().run_inner(&[command][..]).unwrap();
+            let _ = args_parser().run_inner(&[command, "-d"][..]).unwrap();
+            let _ = args_parser()
+                .run_inner(&[command, "--diffable"][..])
+                .unwrap();
+            let _ = args_parser()
+                .run_inner(&[command, "--cache-max-age=7d"][..])
                 .unwrap();
             let _ = args_parser()
                 .run_inner(&[command, "-d", "--cache-max-age=7d"][..])
@@ -168,17 +1120,495 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_include_build_dependencies_option() {
+        for command in ["crates", "publishers", "json"] {
+            let _ = parse_args(&[command, "--include-build-dependencies"]).unwrap();
+            let _ = parse_args(&[command, "--no-dev", "--include-build-dependencies"]).unwrap();
+        }
+        let CliArgs::Crates { meta_args, .. } =
+            parse_args(&["crates", "--include-build-deps"]).unwrap()
+        else {
+            unreachable!()
+        };
+        assert!(meta_args.include_build_dependencies);
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "--include-build-dependencies"]).is_err());
+    }
+
+    #[test]
+    fn test_include_published_members_option() {
+        for command in ["crates", "publishers", "json", "trust-graph"] {
+            let _ = parse_args(&[command, "--include-published-members"]).unwrap();
+            let _ = parse_args(&[command, "--workspace", "--include-published-members"]).unwrap();
+            let _ =
+                parse_args(&[command, "--current-package", "--include-published-members"]).unwrap();
+        }
+        let CliArgs::Crates { meta_args, .. } =
+            parse_args(&["crates", "--include-workspace-deps"]).unwrap()
+        else {
+            unreachable!()
+        };
+        assert!(meta_args.include_published_members);
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["inspect", "serde", "--include-published-members"]).is_err());
+    }
+
+    #[test]
+    fn test_batch_options() {
+        let batch = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Crates { args, .. } => (args.batch_size, args.batch_pause_secs),
+            _ => unreachable!(),
+        };
+        assert_eq!(batch(&["crates"]), (None, None));
+        assert_eq!(
+            batch(&["crates", "--batch-size", "50", "--batch-pause-secs=30"]),
+            (NonZeroUsize::new(50), Some(30))
+        );
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--batch-size", "0"]).is_err());
+        assert!(parse_args(&["crates", "--batch-pause-secs", "-1"]).is_err());
+    }
+
+    #[test]
+    fn test_no_transitive_option() {
+        for command in ["crates", "publishers", "json"] {
+            let _ = parse_args(&[command, "--no-transitive"]).unwrap();
+            let _ = parse_args(&[command, "--no-transitive", "--no-dev", "--workspace"]).unwrap();
+            let _ = parse_args(&[command, "--no-transitive", "--current-package"]).unwrap();
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "--no-transitive"]).is_err());
+    }
+
+    #[test]
+    fn test_summary_option() {
+        for command in ["crates", "publishers", "json"] {
+            let _ = parse_args(&[command, "--summary"]).unwrap();
+            let _ = parse_args(&[command, "-d", "--summary"]).unwrap();
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "--summary"]).is_err());
+        assert!(parse_args(&["json", "--print-schema", "--summary"]).is_err());
+    }
+
+    #[test]
+    fn test_no_summary_option() {
+        for command in ["crates", "publishers", "json"] {
+            let _ = parse_args(&[command, "--no-summary"]).unwrap();
+            let _ = parse_args(&[command, "--no-summary", "-d"]).unwrap();
+            let _ = parse_args(&[command, "--notes"]).unwrap();
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "--no-summary"]).is_err());
+        assert!(parse_args(&["crates", "--no-summary", "--notes"]).is_err());
+    }
+
+    #[test]
+    fn test_owner_count_options() {
+        for command in ["crates", "publishers"] {
+            let _ = parse_args(&[command, "--min-owners", "2"]).unwrap();
+            let _ = parse_args(&[command, "--max-owners=5"]).unwrap();
+            let _ = parse_args(&[command, "--min-owners=2", "--max-owners=5"]).unwrap();
+            let _ = parse_args(&[command, "--single-owner"]).unwrap();
+            // erroneous invocations that must be rejected
+            assert!(parse_args(&[command, "--min-owners"]).is_err());
+            assert!(parse_args(&[command, "--max-owners=-1"]).is_err());
+            assert!(parse_args(&[command, "--single-owner", "--max-owners=5"]).is_err());
+        }
+        assert!(parse_args(&["json", "--single-owner"]).is_err());
+
+        let args = parse_args(&["crates", "--single-owner"]).unwrap();
+        let CliArgs::Crates { owner_count, .. } = args else {
+            panic!("expected the crates subcommand");
+        };
+        assert_eq!(owner_count.max_owners, Some(1));
+        assert!(owner_count.matches(0));
+        assert!(owner_count.matches(1));
+        assert!(!owner_count.matches(2));
+    }
+
+    #[test]
+    fn test_count_option() {
+        for command in ["crates", "publishers"] {
+            let _ = parse_args(&[command, "--count"]).unwrap();
+            let _ = parse_args(&[command, "--count", "--min-owners", "1"]).unwrap();
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["json", "--count"]).is_err());
+        assert!(parse_args(&["update", "--count"]).is_err());
+    }
+
+    #[test]
+    fn test_json_format_option() {
+        let format = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Json(PrintJson::Info { format, .. }) => format,
+            _ => unreachable!(),
+        };
+        assert_eq!(format(&["json"]), None);
+        assert_eq!(
+            format(&["json", "--format", "json"]),
+            Some(JsonFormat::Json)
+        );
+        assert_eq!(
+            format(&["json", "--format=ndjson"]),
+            Some(JsonFormat::Ndjson)
+        );
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["json", "--format", "text"]).is_err());
+        assert!(parse_args(&["json", "--format"]).is_err());
+    }
+
+    #[test]
+    fn test_link_crates_option() {
+        let _ = parse_args(&["crates", "--link-crates"]).unwrap();
+        let _ = parse_args(&["crates", "-d", "--link-crates"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--link-crates"]).is_err());
+        assert!(parse_args(&["json", "--link-crates"]).is_err());
+    }
+
+    #[test]
+    fn test_show_depth_option() {
+        let _ = parse_args(&["crates", "--show-depth"]).unwrap();
+        let _ = parse_args(&["crates", "-d", "--show-depth", "--link-crates"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--show-depth"]).is_err());
+    }
+
+    #[test]
+    fn test_show_missing_option() {
+        let _ = parse_args(&["crates", "--show-missing"]).unwrap();
+        let _ = parse_args(&["crates", "--show-missing", "--color", "always"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--show-missing"]).is_err());
+        assert!(parse_args(&["crates", "--color", "red"]).is_err());
+    }
+
+    #[test]
+    fn test_only_proc_macros_option() {
+        let _ = parse_args(&["crates", "--only-proc-macros"]).unwrap();
+        let _ = parse_args(&["crates", "--only-proc-macros", "--show-missing"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--only-proc-macros"]).is_err());
+    }
+
+    #[test]
+    fn test_show_counts_option() {
+        let _ = parse_args(&["crates", "--show-counts"]).unwrap();
+        let _ = parse_args(&["crates", "--show-counts", "--format", "json"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--show-counts"]).is_err());
+    }
+
+    #[test]
+    fn test_show_versions_option() {
+        let _ = parse_args(&["publishers", "--show-versions"]).unwrap();
+        let _ = parse_args(&["publishers", "--show-versions", "--format", "json"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--show-versions"]).is_err());
+    }
+
+    #[test]
+    fn test_show_crate_categories_option() {
+        let _ = parse_args(&["crates", "--show-crate-categories"]).unwrap();
+        let _ = parse_args(&["crates", "--show-crate-categories", "--group-by-category"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--show-crate-categories"]).is_err());
+        assert!(parse_args(&["publishers", "--group-by-category"]).is_err());
+    }
+
+    #[test]
+    fn test_show_download_count_option() {
+        let _ = parse_args(&["crates", "--show-download-count"]).unwrap();
+        let _ = parse_args(&[
+            "crates",
+            "--show-download-count",
+            "--format",
+            "github-markdown",
+        ])
+        .unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--show-download-count"]).is_err());
+    }
+
+    #[test]
+    fn test_sort_by_option() {
+        let sort_by = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Crates { crates_args, .. } => crates_args.sort_by,
+            _ => unreachable!(),
+        };
+        assert_eq!(sort_by(&["crates"]), None);
+        assert_eq!(
+            sort_by(&["crates", "--sort-by", "publishers"]),
+            Some(SortKey::Publishers)
+        );
+        assert_eq!(
+            sort_by(&["crates", "--sort-by=downloads", "--diffable"]),
+            Some(SortKey::Downloads)
+        );
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--sort-by", "age"]).is_err());
+        assert!(parse_args(&["publishers", "--sort-by", "name"]).is_err());
+    }
+
+    #[test]
+    fn test_show_org_option() {
+        let _ = parse_args(&["crates", "--show-org"]).unwrap();
+        let _ = parse_args(&["crates", "--show-org", "--link-crates"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--show-org"]).is_err());
+    }
+
+    #[test]
+    fn test_interactive_option() {
+        let _ = parse_args(&["crates", "--interactive"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--interactive"]).is_err());
+        assert!(parse_args(&["json", "--interactive"]).is_err());
+    }
+
+    #[test]
+    fn test_format_option() {
+        let _ = parse_args(&["crates", "--format", "text"]).unwrap();
+        let _ = parse_args(&["crates", "--format=github-actions"]).unwrap();
+        let _ = parse_args(&["crates", "--format=github-markdown"]).unwrap();
+        let _ = parse_args(&["publishers", "--format", "github-markdown"]).unwrap();
+        let _ = parse_args(&["update", "--update-check", "--format=json"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--format"]).is_err());
+        assert!(parse_args(&["crates", "--format=sarif"]).is_err());
+        assert!(parse_args(&["update", "--format=github-actions"]).is_err());
+        assert!(parse_args(&["update", "--update-check", "--format=github-markdown"]).is_err());
+    }
+
+    #[test]
+    fn test_help_formats_option() {
+        for command in ["crates", "publishers", "json", "trust-graph", "update"] {
+            let _ = parse_args(&[command, "--help-formats"]).unwrap();
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["inspect", "serde", "--help-formats"]).is_err());
+        assert!(parse_args(&["json", "--print-schema", "--help-formats"]).is_err());
+    }
+
+    #[test]
+    fn test_print_schema_option() {
+        for command in ["crates", "publishers"] {
+            let _ = parse_args(&[command, "--format", "json"]).unwrap();
+            let _ = parse_args(&[command, "--format=json", "--print-schema"]).unwrap();
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "--print-schema"]).is_err());
+    }
+
+    #[test]
+    fn test_inspect_options() {
+        let _ = parse_args(&["inspect", "serde"]).unwrap();
+        let _ = parse_args(&["inspect", "--json", "serde"]).unwrap();
+        let _ = parse_args(&["inspect", "serde", "--json", "--offline"]).unwrap();
+        let _ = parse_args(&["inspect", "-d", "--cache-max-age=7d", "serde"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["inspect"]).is_err());
+        assert!(parse_args(&["inspect", "serde", "tokio"]).is_err());
+        assert!(parse_args(&["inspect", "--no-dev", "serde"]).is_err());
+    }
+
+    #[test]
+    fn test_trust_level_options() {
+        let tiers = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Publishers {
+                publishers_args, ..
+            } => publishers_args.trust_tiers(),
+            _ => unreachable!(),
+        };
+        assert!(tiers(&["publishers"]).is_none());
+        let default = tiers(&["publishers", "--trust-level"]).unwrap();
+        assert_eq!((default.high, default.medium), (50, 10));
+        let custom = tiers(&[
+            "publishers",
+            "--trust-level",
+            "--trust-high-min=20",
+            "--trust-medium-min",
+            "5",
+        ])
+        .unwrap();
+        assert_eq!(custom.tier(20), "[HIGH]");
+        assert_eq!(custom.tier(19), "[MEDIUM]");
+        assert_eq!(custom.tier(5), "[MEDIUM]");
+        assert_eq!(custom.tier(4), "[LOW]");
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--trust-high-min"]).is_err());
+        assert!(parse_args(&["publishers", "--trust-high-min=many"]).is_err());
+        assert!(parse_args(&["crates", "--trust-level"]).is_err());
+    }
+
+    #[test]
+    fn test_fields_option() {
+        for command in ["crates", "publishers"] {
+            let _ = parse_args(&[command, "--fields", "crate"]).unwrap();
+            let _ = parse_args(&[command, "--fields=crate,publisher,owner-count"]).unwrap();
+            let _ = parse_args(&[command, "--fields=repository", "--format=json"]).unwrap();
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--fields"]).is_err());
+        assert!(parse_args(&["crates", "--fields=crate,owners"]).is_err());
+        assert!(parse_args(&["json", "--fields=crate"]).is_err());
+    }
+
+    #[test]
+    fn test_align_options() {
+        for command in ["crates", "publishers"] {
+            let _ = parse_args(&[command, "--align"]).unwrap();
+            let _ = parse_args(&[command, "--align", "--max-column-width", "20"]).unwrap();
+        }
+        let column = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Crates { crates_args, .. } => crates_args.align.column(["serde_json"]),
+            _ => unreachable!(),
+        };
+        assert_eq!(column(&["crates"]), None);
+        assert_eq!(
+            column(&["crates", "--align"]),
+            Some(Column::fitting(["serde_json"], None))
+        );
+        assert_eq!(
+            column(&["crates", "--align", "--max-column-width=5"]),
+            Some(Column::fitting(["serde"], None))
+        );
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["json", "--align"]).is_err());
+        assert!(parse_args(&["crates", "--max-column-width", "wide"]).is_err());
+    }
+
+    #[test]
+    fn test_prepend_headers_option() {
+        for command in ["crates", "publishers"] {
+            let _ = parse_args(&[command, "--fields=crate", "--prepend-headers"]).unwrap();
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["json", "--prepend-headers"]).is_err());
+    }
+
+    #[test]
+    fn test_owned_by_options() {
+        let kind = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::OwnedBy { kind, .. } => kind,
+            _ => unreachable!(),
+        };
+        assert_eq!(kind(&["owned-by", "dtolnay"]), None);
+        assert_eq!(
+            kind(&["owned-by", "--kind", "user", "dtolnay"]),
+            Some(PublisherKind::user)
+        );
+        assert_eq!(
+            kind(&["owned-by", "--kind=team", "github:serde-rs:owners"]),
+            Some(PublisherKind::team)
+        );
+        let _ = parse_args(&["owned-by", "-d", "--offline", "dtolnay"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["owned-by"]).is_err());
+        assert!(parse_args(&["owned-by", "--kind=org", "dtolnay"]).is_err());
+    }
+
+    #[test]
+    fn test_prune_options() {
+        let _ = parse_args(&["prune"]).unwrap();
+        let _ = parse_args(&["prune", "--dry-run"]).unwrap();
+        let _ = parse_args(&["prune", "--older-than", "30d", "--dry-run"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["prune", "--older-than"]).is_err());
+        assert!(parse_args(&["prune", "--older-than=30"]).is_err());
+        assert!(parse_args(&["prune", "--cache-max-age=7d"]).is_err());
+    }
+
+    #[test]
+    fn test_color_options() {
+        let colors = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Publishers {
+                publishers_args, ..
+            } => publishers_args.colors(),
+            _ => unreachable!(),
+        };
+        assert_eq!(colors(&["publishers"]), None);
+        assert_eq!(colors(&["publishers", "--color=never"]), None);
+        assert_eq!(
+            colors(&["publishers", "--color", "always"]),
+            Some(ColorThreshold::default())
+        );
+        assert_eq!(
+            colors(&["publishers", "--color=always", "--color-threshold", "2,5"]),
+            Some(ColorThreshold { yellow: 2, red: 5 })
+        );
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--color=rainbow"]).is_err());
+        assert!(parse_args(&["publishers", "--color-threshold=2"]).is_err());
+        assert!(parse_args(&["publishers", "--color-threshold=5,2"]).is_err());
+        assert!(parse_args(&["publishers", "--color-threshold=a,b"]).is_err());
+    }
+
+    #[test]
+    fn test_reporting_options() {
+        for command in ["crates", "publishers"] {
+            let reporting = |args: &[&str]| match parse_args(args).unwrap() {
+                CliArgs::Publishers { reporting, .. } | CliArgs::Crates { reporting, .. } => {
+                    (reporting.ignores_local(), reporting.ignores_foreign())
+                }
+                _ => unreachable!(),
+            };
+            assert_eq!(reporting(&[command]), (false, false));
+            assert_eq!(reporting(&[command, "--ignore-local"]), (true, false));
+            assert_eq!(reporting(&[command, "--ignore-foreign"]), (false, true));
+            assert_eq!(reporting(&[command, "--quiet"]), (true, true));
+            assert_eq!(reporting(&[command, "-q"]), (true, true));
+            assert_eq!(reporting(&[command, "--no-quiet"]), (false, false));
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["json", "--ignore-local"]).is_err());
+    }
+
     #[test]
     fn test_accepted_update_options() {
         let _ = args_parser().run_inner(Args::from(&["update"])).unwrap();
         let _ = parse_args(&["update", "--cache-max-age=7d"]).unwrap();
+        let _ = parse_args(&["update", "--invalidate-crate", "serde"]).unwrap();
+        let _ = parse_args(&[
+            "update",
+            "--invalidate-crate=serde",
+            "--invalidate-crate=rand",
+        ])
+        .unwrap();
+        let _ = parse_args(&["update", "--with-yanked"]).unwrap();
+        let _ = parse_args(&["update", "--with-yanked", "--with-categories"]).unwrap();
+        let _ = parse_args(&["update", "--update-check"]).unwrap();
+        let _ = parse_args(&["update", "--update-check", "--format=json"]).unwrap();
         // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "--invalidate-crate"]).is_err());
+        assert!(parse_args(&["update", "--quiet"]).is_err());
+        assert!(parse_args(&["crates", "--with-yanked"]).is_err());
+        assert!(parse_args(&["update", "--update-check", "--format=yaml"]).is_err());
         assert!(parse_args(&["update", "-d"]).is_err());
         assert!(parse_args(&["update", "--diffable"]).is_err());
         assert!(parse_args(&["update", "-d", "--cache-max-age=7d"]).is_err());
         assert!(parse_args(&["update", "--diffable", "--cache-max-age=7d"]).is_err());
     }
 
+    #[test]
+    fn test_max_download_size_option() {
+        let max_download_size = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Update {
+                max_download_size, ..
+            } => max_download_size,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            max_download_size(&["update", "--max-download-size", "512"]),
+            512
+        );
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["update", "--max-download-size", "lots"]).is_err());
+        assert!(parse_args(&["crates", "--max-download-size", "512"]).is_err());
+    }
+
     #[test]
     fn test_json_schema_option() {
         let _ = parse_args(&["json", "--print-schema"]).unwrap();
@@ -191,6 +1621,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_json_save_on_success_options() {
+        let _ = parse_args(&["json", "--save-on-success", "snapshots"]).unwrap();
+        let _ = parse_args(&["json", "--save-on-success=snapshots", "-d"]).unwrap();
+        let _ = parse_args(&["json", "--save-on-success=snapshots", "--keep-last=5"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["json", "--keep-last=5"]).is_err());
+        assert!(parse_args(&["json", "--save-on-success"]).is_err());
+        assert!(parse_args(&["json", "--save-on-success=snapshots", "--keep-last=x"]).is_err());
+        assert!(parse_args(&["json", "--save-on-success=snapshots", "--keep-last=0"]).is_err());
+        assert!(parse_args(&["crates", "--save-on-success=snapshots"]).is_err());
+    }
+
     #[test]
     fn test_invocation_through_cargo() {
         let _ = parse_args(&["supply-chain", "update"]).unwrap();
@@ -202,4 +1645,379 @@ mod tests {
         assert!(parse_args(&["supply-chain", "supply-chain", "json", "--print-schema"]).is_err());
         assert!(parse_args(&["supply-chain", "supply-chain", "crates", "-d"]).is_err());
     }
+
+    #[test]
+    fn test_direct_invocation() {
+        // As `cargo-supply-chain` rather than through cargo, without the `supply-chain` prefix
+        let _ = parse_args(&["update"]).unwrap();
+        let _ = parse_args(&["publishers", "-d"]).unwrap();
+        let _ = parse_args(&["crates", "-d", "--cache-max-age=5h"]).unwrap();
+        let _ = parse_args(&["json", "--print-schema"]).unwrap();
+        let _ = parse_args(&["inspect", "serde"]).unwrap();
+        let _ = parse_args(&["--list"]).unwrap();
+    }
+
+    #[test]
+    fn test_filter_regex_options() {
+        let filters = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Publishers {
+                args,
+                publishers_args,
+                ..
+            } => (
+                args.crate_filter.map(|r| r.as_str().to_owned()),
+                publishers_args
+                    .publisher_filter
+                    .map(|r| r.as_str().to_owned()),
+            ),
+            _ => unreachable!(),
+        };
+        assert_eq!(filters(&["publishers"]), (None, None));
+        assert_eq!(
+            filters(&[
+                "publishers",
+                "--crate-filter-regex",
+                "^tokio-",
+                "--publisher-filter-regex=^github:"
+            ]),
+            (Some("^tokio-".to_owned()), Some("^github:".to_owned()))
+        );
+        let _ = parse_args(&["crates", "--crate-filter-regex", "^aws-"]).unwrap();
+        let _ = parse_args(&["json", "--crate-filter-regex", "^aws-"]).unwrap();
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--crate-filter-regex", "("]).is_err());
+        assert!(parse_args(&["crates", "--publisher-filter-regex", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_publisher_id_option() {
+        let publishers_args = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Publishers {
+                publishers_args, ..
+            } => publishers_args,
+            _ => unreachable!(),
+        };
+        let all = publishers_args(&["publishers"]);
+        assert!(all.shows_publisher("dtolnay", 3618));
+
+        let by_id = publishers_args(&["publishers", "--publisher-id", "3618", "--publisher-id=1"]);
+        assert_eq!(by_id.publisher_id, vec![3618, 1]);
+        assert!(by_id.shows_publisher("renamed", 3618));
+        assert!(!by_id.shows_publisher("dtolnay", 2));
+
+        let either = publishers_args(&[
+            "publishers",
+            "--publisher-id=3618",
+            "--publisher-filter-regex=^github:",
+        ]);
+        assert!(either.shows_publisher("dtolnay", 3618));
+        assert!(either.shows_publisher("github:serde-rs:owners", 5));
+        assert!(!either.shows_publisher("alexcrichton", 1));
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--publisher-id", "dtolnay"]).is_err());
+    }
+
+    #[test]
+    fn test_only_kind_options() {
+        let publishers_args = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Publishers {
+                publishers_args, ..
+            } => publishers_args,
+            _ => unreachable!(),
+        };
+        let all = publishers_args(&["publishers"]);
+        assert!(!all.filters_kind());
+        assert!(all.shows_kind(PublisherKind::user) && all.shows_kind(PublisherKind::team));
+
+        let users = publishers_args(&["publishers", "--only-users"]);
+        assert!(users.filters_kind());
+        assert!(users.shows_kind(PublisherKind::user) && !users.shows_kind(PublisherKind::team));
+
+        let teams = publishers_args(&["publishers", "--only-teams"]);
+        assert!(!teams.shows_kind(PublisherKind::user) && teams.shows_kind(PublisherKind::team));
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--only-users"]).is_err());
+    }
+
+    #[test]
+    fn test_check_option() {
+        let check = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Publishers {
+                publishers_args, ..
+            } => publishers_args.check,
+            _ => unreachable!(),
+        };
+        assert!(!check(&["publishers"]));
+        assert!(check(&["publishers", "--check"]));
+        assert!(check(&["publishers", "--only-users", "--check"]));
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--check"]).is_err());
+    }
+
+    #[test]
+    fn test_webhook_option() {
+        let webhook = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Publishers {
+                publishers_args, ..
+            } => publishers_args.webhook,
+            _ => unreachable!(),
+        };
+        assert_eq!(webhook(&["publishers", "--check"]), None);
+        assert_eq!(
+            webhook(&[
+                "publishers",
+                "--check",
+                "--webhook",
+                "https://example.com/hook"
+            ])
+            .as_deref(),
+            Some("https://example.com/hook")
+        );
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["publishers", "--webhook"]).is_err());
+        assert!(parse_args(&["crates", "--webhook", "https://example.com/hook"]).is_err());
+    }
+
+    #[test]
+    fn test_export_graph_option() {
+        let export_graph = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Json(PrintJson::Info { export_graph, .. }) => export_graph,
+            _ => unreachable!(),
+        };
+        assert_eq!(export_graph(&["json"]), None);
+        assert_eq!(
+            export_graph(&["json", "--export-graph", "gexf"]),
+            Some(ExportGraphFormat::Gexf)
+        );
+        assert_eq!(
+            export_graph(&["json", "--export-graph=json-graph", "-d"]),
+            Some(ExportGraphFormat::JsonGraph)
+        );
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["json", "--export-graph=svg"]).is_err());
+        assert!(parse_args(&["crates", "--export-graph=dot"]).is_err());
+    }
+
+    #[test]
+    fn test_publishers_file_option() {
+        let publishers_file = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Crates { args, .. }
+            | CliArgs::Publishers { args, .. }
+            | CliArgs::Json(PrintJson::Info { args, .. }) => args.publishers_file,
+            _ => unreachable!(),
+        };
+        assert_eq!(publishers_file(&["crates"]), None);
+        for subcommand in ["crates", "publishers", "json"] {
+            assert_eq!(
+                publishers_file(&[subcommand, "--publishers-file", "people.json"]),
+                Some(PathBuf::from("people.json"))
+            );
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--publishers-file"]).is_err());
+    }
+
+    #[test]
+    fn test_separator_option() {
+        let separator = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Crates { args, .. } | CliArgs::Publishers { args, .. } => {
+                args.separator().to_owned()
+            }
+            _ => unreachable!(),
+        };
+        assert_eq!(separator(&["crates"]), ", ");
+        assert_eq!(separator(&["crates", "--separator", ";"]), ";");
+        assert_eq!(separator(&["publishers", "--separator=|"]), "|");
+        assert_eq!(separator(&["publishers", "--separator", "\t"]), "\t");
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--separator"]).is_err());
+        assert!(parse_args(&["crates", "--separator", "\n"]).is_err());
+        assert!(parse_args(&["crates", "--separator", ";\n"]).is_err());
+    }
+
+    #[test]
+    fn test_tls_options() {
+        let tls = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Crates { args, .. } => args.tls,
+            CliArgs::Update { tls, .. } => tls,
+            _ => unreachable!(),
+        };
+        let default = tls(&["crates"]);
+        assert_eq!(default.ca_bundle, None);
+        assert!(!default.allow_self_signed);
+        let custom = tls(&["crates", "--ca-bundle", "corp.pem", "--allow-self-signed"]);
+        assert_eq!(custom.ca_bundle, Some(PathBuf::from("corp.pem")));
+        assert!(custom.allow_self_signed);
+        assert!(tls(&["update", "--allow-self-signed"]).allow_self_signed);
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--ca-bundle"]).is_err());
+    }
+
+    #[test]
+    fn test_crates_io_token_option() {
+        let args = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Crates { args, .. } | CliArgs::Inspect { args, .. } => args,
+            _ => unreachable!(),
+        };
+        for command in ["crates", "inspect serde"] {
+            let mut command: Vec<&str> = command.split(' ').collect();
+            command.extend(["--crates-io-token", "cio_secret"]);
+            let args = args(&command);
+            assert_eq!(
+                args.crates_io_token,
+                Some(Redacted("cio_secret".to_owned()))
+            );
+            assert!(!format!("{:?}", args).contains("cio_secret"));
+        }
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["crates", "--crates-io-token"]).is_err());
+        assert!(parse_args(&["update", "--crates-io-token", "cio_secret"]).is_err());
+    }
+
+    #[test]
+    fn test_output_template_option() {
+        let output_template = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Json(PrintJson::Info {
+                output_template, ..
+            }) => output_template,
+            _ => unreachable!(),
+        };
+        assert_eq!(output_template(&["json"]), None);
+        assert_eq!(
+            output_template(&["json", "--output-template", "report.txt"]),
+            Some(PathBuf::from("report.txt"))
+        );
+        assert!(matches!(
+            parse_args(&["json", "--print-template"]).unwrap(),
+            CliArgs::Json(PrintJson::Template)
+        ));
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["json", "--output-template"]).is_err());
+        assert!(parse_args(&["json", "--print-template", "--offline"]).is_err());
+        assert!(parse_args(&["crates", "--print-template"]).is_err());
+        assert!(parse_args(&["crates", "--output-template=report.txt"]).is_err());
+    }
+
+    #[test]
+    fn test_output_schema_option() {
+        let output_schema = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Json(PrintJson::Info { output_schema, .. }) => output_schema,
+            _ => unreachable!(),
+        };
+        assert_eq!(output_schema(&["json"]), None);
+        assert_eq!(
+            output_schema(&["json", "--diffable", "--output-schema", "schema.json"]),
+            Some(PathBuf::from("schema.json"))
+        );
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["json", "--output-schema"]).is_err());
+        assert!(parse_args(&["json", "--print-schema", "--output-schema=schema.json"]).is_err());
+        assert!(parse_args(&["crates", "--output-schema=schema.json"]).is_err());
+    }
+
+    #[test]
+    fn test_trust_graph_options() {
+        let format = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::TrustGraph { format, .. } => format,
+            _ => unreachable!(),
+        };
+        assert_eq!(format(&["trust-graph"]), GraphFormat::Dot);
+        assert_eq!(
+            format(&["trust-graph", "--format", "dot"]),
+            GraphFormat::Dot
+        );
+        assert_eq!(
+            format(&["trust-graph", "--format=mermaid", "--no-dev"]),
+            GraphFormat::Mermaid
+        );
+        // erroneous invocations that must be rejected
+        assert!(parse_args(&["trust-graph", "--format=svg"]).is_err());
+        assert!(parse_args(&["trust-graph", "serde"]).is_err());
+    }
+
+    #[test]
+    fn test_generate_completions_subcommand() {
+        assert!(matches!(
+            parse_args(&["generate-completions", "zsh"]).unwrap(),
+            CliArgs::GenerateCompletions {
+                shell: ShellChoice::Zsh
+            }
+        ));
+        // erroneous invocations to be rejected
+        assert!(parse_args(&["generate-completions"]).is_err());
+        assert!(parse_args(&["generate-completions", "powershell"]).is_err());
+    }
+
+    #[test]
+    fn test_config_file_options() {
+        let config_file = |args: &[&str]| match parse_args(args).unwrap() {
+            CliArgs::Crates { args, .. }
+            | CliArgs::Inspect { args, .. }
+            | CliArgs::Json(PrintJson::Info { args, .. }) => args.config_file,
+            _ => unreachable!(),
+        };
+        assert_eq!(config_file(&["crates"]), None);
+        for args in [
+            &["crates", "--config-file", "ci.toml"][..],
+            &["inspect", "serde", "--config-file=ci.toml"],
+            &["json", "--config-file", "ci.toml"],
+        ] {
+            assert_eq!(config_file(args), Some(PathBuf::from("ci.toml")));
+        }
+        assert!(matches!(
+            parse_args(&["--write-config"]).unwrap(),
+            CliArgs::WriteConfig
+        ));
+        // erroneous invocations to be rejected
+        assert!(parse_args(&["crates", "--config-file"]).is_err());
+        assert!(parse_args(&["update", "--config-file", "ci.toml"]).is_err());
+        assert!(parse_args(&["crates", "--write-config"]).is_err());
+    }
+
+    #[test]
+    fn test_cache_info_subcommand() {
+        let _ = parse_args(&["cache-info"]).unwrap();
+        let CliArgs::CacheInfo {
+            cache_max_age,
+            json,
+            list_cache_files,
+        } = parse_args(&["cache-info", "--json", "--cache-max-age=1w"]).unwrap()
+        else {
+            unreachable!()
+        };
+        assert_eq!(cache_max_age, Duration::from_secs(7 * 24 * 3600));
+        assert!(json);
+        assert!(!list_cache_files);
+        assert!(matches!(
+            parse_args(&["cache-info", "--list-cache-files"]).unwrap(),
+            CliArgs::CacheInfo {
+                list_cache_files: true,
+                ..
+            }
+        ));
+        // erroneous invocations to be rejected
+        assert!(parse_args(&["cache-info", "--offline"]).is_err());
+    }
+
+    #[test]
+    fn test_version_verbose_option() {
+        assert!(matches!(
+            parse_args(&["--version-verbose"]).unwrap(),
+            CliArgs::VersionVerbose
+        ));
+        // erroneous invocations to be rejected
+        assert!(parse_args(&["crates", "--version-verbose"]).is_err());
+    }
+
+    #[test]
+    fn test_list_option() {
+        assert!(matches!(parse_args(&["--list"]).unwrap(), CliArgs::List));
+        assert!(matches!(
+            parse_args(&["supply-chain", "--list"]).unwrap(),
+            CliArgs::List
+        ));
+        // erroneous invocations to be rejected
+        assert!(parse_args(&["--list", "crates"]).is_err());
+        assert!(parse_args(&["crates", "--list"]).is_err());
+    }
 }
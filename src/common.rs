@@ -1,25 +1,92 @@
 use anyhow::bail;
 use cargo_metadata::{
-    semver::VersionReq, CargoOpt::AllFeatures, CargoOpt::NoDefaultFeatures, Dependency,
-    DependencyKind, Metadata, MetadataCommand, Package, PackageId,
+    semver::{Version, VersionReq},
+    CargoOpt::AllFeatures,
+    CargoOpt::NoDefaultFeatures,
+    Dependency, DependencyKind, Metadata, MetadataCommand, Node, Package, PackageId, Resolve,
+    Source,
+};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    hash::{Hash, Hasher},
+    path::PathBuf,
 };
-use std::collections::{HashMap, HashSet};
 
 pub use crate::cli::MetadataArgs;
+use crate::cli::ReportingArgs;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(test, derive(serde::Deserialize, serde::Serialize))]
 pub enum PkgSource {
     Local,
     CratesIo,
+    /// A git repository, along with the exact commit that was used
+    Git {
+        url: String,
+        rev: String,
+    },
+    /// A path dependency outside of the workspace, along with the directory it is in
+    Path {
+        location: PathBuf,
+    },
     Foreign,
 }
 
+/// The sparse protocol index of crates.io, used by default since Rust 1.70
+const CRATES_IO_SPARSE_INDEX: &str = "sparse+https://index.crates.io/";
+
+impl PkgSource {
+    /// Classifies the source of a package in `cargo metadata` output.
+    /// Path dependencies and workspace members have no source, so they are classified by the caller.
+    pub fn from_cargo_source(source: &Source) -> Self {
+        if source.is_crates_io() || source.repr == CRATES_IO_SPARSE_INDEX {
+            PkgSource::CratesIo
+        } else if let Some(git) = git_source(&source.repr) {
+            git
+        } else {
+            PkgSource::Foreign
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
-#[cfg_attr(test, derive(Eq, PartialEq, serde::Deserialize, serde::Serialize))]
+#[cfg_attr(test, derive(serde::Deserialize, serde::Serialize))]
 pub struct SourcedPackage {
     pub source: PkgSource,
     pub package: Package,
+    /// Length of the shortest dependency path from a workspace member to this package.
+    /// `None` if the dependency graph was not resolved.
+    // Like `build_only`, this is left out of the files in `deps_tests`, which only record
+    // which packages are found, and tested against the metadata instead
+    #[cfg_attr(test, serde(skip))]
+    pub depth: Option<usize>,
+    /// Whether the package is only needed to run build scripts, i.e. it is a build dependency
+    /// or a dependency of one, but not a normal dependency of any package in the graph
+    #[cfg_attr(test, serde(skip))]
+    pub build_only: bool,
+}
+
+impl SourcedPackage {
+    /// What Cargo identifies a package by
+    fn key(&self) -> (&str, &Version, &PkgSource) {
+        (&self.package.name, &self.package.version, &self.source)
+    }
+}
+
+/// Packages are equal if they are the same release from the same source,
+/// regardless of where they are found in the dependency graph
+impl PartialEq for SourcedPackage {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for SourcedPackage {}
+
+impl Hash for SourcedPackage {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
 }
 
 fn metadata_command(args: MetadataArgs) -> MetadataCommand {
@@ -49,20 +116,52 @@ fn metadata_command(args: MetadataArgs) -> MetadataCommand {
 pub fn sourced_dependencies(
     metadata_args: MetadataArgs,
 ) -> Result<Vec<SourcedPackage>, anyhow::Error> {
+    sourced_dependencies_and_workspace_members(metadata_args).map(|(deps, _)| deps)
+}
+
+/// Like [`sourced_dependencies`], but also returns the names of all workspace members,
+/// including the ones that are not part of the analyzed dependency graph
+pub fn sourced_dependencies_and_workspace_members(
+    metadata_args: MetadataArgs,
+) -> Result<(Vec<SourcedPackage>, Vec<String>), anyhow::Error> {
     let no_dev = metadata_args.no_dev;
-    let command = metadata_command(metadata_args);
-    let meta = match command.exec() {
-        Ok(v) => v,
+    if metadata_args.workspace && metadata_args.current_package {
+        bail!("`--workspace` cannot be combined with `--current-package`");
+    }
+    let current_package = metadata_args.current_package;
+    let no_transitive = metadata_args.no_transitive;
+    let meta = exec_metadata(metadata_command(metadata_args))?;
+
+    let mut members: Vec<String> = meta
+        .workspace_members
+        .iter()
+        .map(|id| meta[id].name.clone())
+        .collect();
+    members.sort_unstable();
+    let deps = sourced_dependencies_from_metadata(meta, no_dev, current_package, no_transitive)?;
+    Ok((deps, members))
+}
+
+/// The root directory of the workspace, without resolving any dependencies
+pub fn workspace_root(metadata_args: MetadataArgs) -> Result<PathBuf, anyhow::Error> {
+    let mut command = metadata_command(metadata_args);
+    command.no_deps();
+    Ok(exec_metadata(command)?.workspace_root.into())
+}
+
+fn exec_metadata(command: MetadataCommand) -> Result<Metadata, anyhow::Error> {
+    match command.exec() {
+        Ok(v) => Ok(v),
         Err(cargo_metadata::Error::CargoMetadata { stderr: e }) => bail!(e),
         Err(err) => bail!("Failed to fetch crate metadata!\n  {}", err),
-    };
-
-    sourced_dependencies_from_metadata(meta, no_dev)
+    }
 }
 
 fn sourced_dependencies_from_metadata(
     meta: Metadata,
     no_dev: bool,
+    current_package: bool,
+    no_transitive: bool,
 ) -> Result<Vec<SourcedPackage>, anyhow::Error> {
     let mut how: HashMap<PackageId, PkgSource> = HashMap::new();
     let mut what: HashMap<PackageId, Package> = meta
@@ -72,25 +171,57 @@ fn sourced_dependencies_from_metadata(
         .collect();
 
     for pkg in &meta.packages {
-        // Suppose every package is foreign, until proven otherwise..
-        how.insert(pkg.id.clone(), PkgSource::Foreign);
+        let source = match pkg.source.as_ref() {
+            Some(source) => PkgSource::from_cargo_source(source),
+            // Workspace members have no source either, they are marked as local below
+            None => {
+                let manifest_dir = pkg.manifest_path.parent().unwrap_or(&pkg.manifest_path);
+                let location = manifest_dir.as_std_path().to_owned();
+                PkgSource::Path { location }
+            }
+        };
+        how.insert(pkg.id.clone(), source);
     }
 
-    // Find the crates.io dependencies..
-    for pkg in &meta.packages {
-        if let Some(source) = pkg.source.as_ref() {
-            if source.is_crates_io() {
-                how.insert(pkg.id.clone(), PkgSource::CratesIo);
-            }
-        }
+    for pkg in &meta.workspace_members {
+        *how.get_mut(pkg).unwrap() = PkgSource::Local;
     }
 
-    for pkg in meta.workspace_members {
-        *how.get_mut(&pkg).unwrap() = PkgSource::Local;
+    // All workspace members are audited unless only the current package is asked for.
+    // In a virtual workspace there is no current package, so all members are audited anyway.
+    let roots = match &meta.resolve {
+        Some(Resolve {
+            root: Some(root), ..
+        }) if current_package => vec![root.clone()],
+        _ => meta.workspace_members.clone(),
+    };
+
+    // Without `resolve` (e.g. with `--no-deps`) we cannot tell which packages are reachable
+    let depths = meta
+        .resolve
+        .as_ref()
+        .map(|resolve| dependency_depths(resolve, &roots));
+    if let Some(depths) = &depths {
+        how.retain(|id, _| depths.contains_key(id));
+        what.retain(|id, _| depths.contains_key(id));
     }
 
+    let build_only = meta
+        .resolve
+        .as_ref()
+        .map(|resolve| build_only_packages(resolve, &roots))
+        .unwrap_or_default();
+
     if no_dev {
-        (how, what) = extract_non_dev_dependencies(&mut how, &mut what);
+        (how, what) = extract_non_dev_dependencies(&mut how, &mut what, roots);
+    }
+
+    // Direct dependencies are one hop away from the analyzed workspace members
+    if no_transitive {
+        if let Some(depths) = &depths {
+            how.retain(|id, _| depths[id] <= 1);
+            what.retain(|id, _| depths[id] <= 1);
+        }
     }
 
     let dependencies: Vec<_> = how
@@ -98,8 +229,10 @@ fn sourced_dependencies_from_metadata(
         .map(|(id, kind)| {
             let dep = what.get(id).cloned().unwrap();
             SourcedPackage {
-                source: *kind,
+                source: kind.clone(),
                 package: dep,
+                depth: depths.as_ref().and_then(|depths| depths.get(id).copied()),
+                build_only: build_only.contains(id),
             }
         })
         .collect();
@@ -107,6 +240,105 @@ fn sourced_dependencies_from_metadata(
     Ok(dependencies)
 }
 
+/// Parses a source such as `git+https://github.com/foo/bar?branch=main#0123abcd`.
+/// The query is dropped: the revision after `#` is what pins down the code.
+fn git_source(repr: &str) -> Option<PkgSource> {
+    let location = repr.strip_prefix("git+")?;
+    let (url, rev) = location.split_once('#')?;
+    let url = url.split_once('?').map_or(url, |(url, _query)| url);
+    Some(PkgSource::Git {
+        url: url.to_owned(),
+        rev: rev.to_owned(),
+    })
+}
+
+/// Returns the packages that `roots` depend on, directly or transitively, including `roots` themselves,
+/// along with the length of the shortest path to each of them. Dependencies of all kinds are followed.
+fn dependency_depths(resolve: &Resolve, roots: &[PackageId]) -> HashMap<PackageId, usize> {
+    let nodes: HashMap<&PackageId, &Node> =
+        resolve.nodes.iter().map(|node| (&node.id, node)).collect();
+    let mut depths: HashMap<PackageId, usize> = roots.iter().map(|id| (id.clone(), 0)).collect();
+    // Breadth-first, so the first path found to a package is also the shortest one
+    let mut queue: VecDeque<PackageId> = roots.iter().cloned().collect();
+    while let Some(id) = queue.pop_front() {
+        let depth = depths[&id];
+        if let Some(node) = nodes.get(&id) {
+            for dep in &node.dependencies {
+                if !depths.contains_key(dep) {
+                    depths.insert(dep.clone(), depth + 1);
+                    queue.push_back(dep.clone());
+                }
+            }
+        }
+    }
+    depths
+}
+
+/// Returns the packages that are reachable from `roots` through build dependencies,
+/// but not through normal dependencies alone.
+fn build_only_packages(resolve: &Resolve, roots: &[PackageId]) -> HashSet<PackageId> {
+    let normal = reachable_through(resolve, roots, &[DependencyKind::Normal]);
+    let normal_or_build = reachable_through(
+        resolve,
+        roots,
+        &[DependencyKind::Normal, DependencyKind::Build],
+    );
+    normal_or_build.difference(&normal).cloned().collect()
+}
+
+/// Returns the packages reachable from `roots`, including themselves,
+/// following only the dependencies of the given kinds.
+fn reachable_through(
+    resolve: &Resolve,
+    roots: &[PackageId],
+    kinds: &[DependencyKind],
+) -> HashSet<PackageId> {
+    let nodes: HashMap<&PackageId, &Node> =
+        resolve.nodes.iter().map(|node| (&node.id, node)).collect();
+    let mut reachable: HashSet<PackageId> = roots.iter().cloned().collect();
+    let mut queue = roots.to_vec();
+    while let Some(id) = queue.pop() {
+        let Some(node) = nodes.get(&id) else {
+            continue;
+        };
+        for dep in &node.deps {
+            let followed = dep.dep_kinds.iter().any(|info| kinds.contains(&info.kind));
+            if followed && reachable.insert(dep.pkg.clone()) {
+                queue.push(dep.pkg.clone());
+            }
+        }
+    }
+    reachable
+}
+
+/// Names of the crates of which every version in the graph is [`build_only`](SourcedPackage::build_only)
+pub fn build_only_crate_names(dependencies: &[SourcedPackage]) -> HashSet<String> {
+    let mut build_only: HashMap<&str, bool> = HashMap::new();
+    for dep in dependencies {
+        *build_only.entry(dep.package.name.as_str()).or_insert(true) &= dep.build_only;
+    }
+    build_only
+        .into_iter()
+        .filter(|(_, build_only)| *build_only)
+        .map(|(name, _)| name.to_owned())
+        .collect()
+}
+
+/// Names of the crates of which any version in the graph is a procedural macro.
+/// Like build scripts, proc-macros run arbitrary code at compile time.
+pub fn proc_macro_crate_names(dependencies: &[SourcedPackage]) -> HashSet<String> {
+    dependencies
+        .iter()
+        .filter(|dep| {
+            dep.package
+                .targets
+                .iter()
+                .any(|target| target.kind.iter().any(|kind| kind == "proc-macro"))
+        })
+        .map(|dep| dep.package.name.clone())
+        .collect()
+}
+
 #[derive(Eq, Hash, PartialEq)]
 struct Dep {
     name: String,
@@ -126,7 +358,16 @@ impl Dep {
     }
 }
 
-/// Start with the `PkgSource::Local` packages, then iteratively add non-dev-dependencies until no more
+/// Whether `package` declares a dependency of any kind that `dependency` can satisfy.
+/// Matching is "best effort", see [`extract_non_dev_dependencies`].
+pub fn declares_dependency_on(package: &Package, dependency: &Package) -> bool {
+    package
+        .dependencies
+        .iter()
+        .any(|dep| Dep::from_cargo_metadata_dependency(dep).matches(dependency))
+}
+
+/// Start with the `roots` packages, then iteratively add non-dev-dependencies until no more
 /// packages can be added, and return the results.
 ///
 /// Note that matching dependencies to packages is "best effort." The fields that Cargo uses to
@@ -139,20 +380,12 @@ impl Dep {
 fn extract_non_dev_dependencies(
     how: &mut HashMap<PackageId, PkgSource>,
     what: &mut HashMap<PackageId, Package>,
+    roots: Vec<PackageId>,
 ) -> (HashMap<PackageId, PkgSource>, HashMap<PackageId, Package>) {
     let mut how_new = HashMap::new();
     let mut what_new = HashMap::new();
 
-    let mut ids = how
-        .iter()
-        .filter_map(|(id, source)| {
-            if matches!(source, PkgSource::Local) {
-                Some(id.clone())
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
+    let mut ids = roots;
 
     while !ids.is_empty() {
         let mut deps = HashSet::new();
@@ -179,21 +412,28 @@ fn extract_non_dev_dependencies(
 }
 
 pub fn crate_names_from_source(crates: &[SourcedPackage], source: PkgSource) -> Vec<String> {
-    let mut filtered_crate_names: Vec<String> = crates
+    let unique_names: HashSet<&str> = crates
         .iter()
         .filter(|p| p.source == source)
-        .map(|p| p.package.name.clone())
+        .map(|p| p.package.name.as_str())
         .collect();
-    // Collecting into a HashSet is less user-friendly because order varies between runs
+    let mut filtered_crate_names: Vec<String> =
+        unique_names.into_iter().map(str::to_owned).collect();
+    // The order of a HashSet varies between runs, which is not user-friendly
     filtered_crate_names.sort_unstable();
-    filtered_crate_names.dedup();
     filtered_crate_names
 }
 
-pub fn complain_about_non_crates_io_crates(dependencies: &[SourcedPackage]) {
-    {
+pub fn complain_about_non_crates_io_crates(
+    dependencies: &[SourcedPackage],
+    published_members: &BTreeSet<String>,
+    reporting: &ReportingArgs,
+) {
+    if !reporting.ignores_local() {
         // scope bound to avoid accidentally referencing local crates when working with foreign ones
-        let local_crate_names = crate_names_from_source(dependencies, PkgSource::Local);
+        let mut local_crate_names = crate_names_from_source(dependencies, PkgSource::Local);
+        // Their publishers are looked up with `--include-published-members`
+        local_crate_names.retain(|name| !published_members.contains(name));
         if !local_crate_names.is_empty() {
             eprintln!(
                 "\nThe following crates will be ignored because they come from a local directory:"
@@ -202,6 +442,50 @@ pub fn complain_about_non_crates_io_crates(dependencies: &[SourcedPackage]) {
                 eprintln!(" - {}", crate_name);
             }
         }
+
+        let mut path_crates: Vec<String> = dependencies
+            .iter()
+            .filter_map(|p| match &p.source {
+                PkgSource::Path { location } => {
+                    Some(format!("{} ({})", p.package.name, location.display()))
+                }
+                _ => None,
+            })
+            .collect();
+        path_crates.sort_unstable();
+        path_crates.dedup();
+        if !path_crates.is_empty() {
+            eprintln!("\nThe following crates will be ignored because they are path dependencies outside the workspace:");
+            for crate_name in &path_crates {
+                eprintln!(" - {}", crate_name);
+            }
+        }
+    }
+
+    if reporting.ignores_foreign() {
+        return;
+    }
+
+    {
+        let mut git_crates: Vec<String> = dependencies
+            .iter()
+            .filter_map(|p| match &p.source {
+                PkgSource::Git { url, rev } => {
+                    Some(format!("{} ({}#{})", p.package.name, url, rev))
+                }
+                _ => None,
+            })
+            .collect();
+        git_crates.sort_unstable();
+        git_crates.dedup();
+        if !git_crates.is_empty() {
+            eprintln!(
+                "\nCannot audit the following crates because they come from git repositories:"
+            );
+            for crate_name in &git_crates {
+                eprintln!(" - {}", crate_name);
+            }
+        }
     }
 
     {
@@ -215,12 +499,22 @@ pub fn complain_about_non_crates_io_crates(dependencies: &[SourcedPackage]) {
     }
 }
 
-pub fn comma_separated_list(list: &[String]) -> String {
+/// Wraps `text` in an OSC 8 hyperlink to `url` if `escapes` are enabled,
+/// otherwise appends the bare URL in parentheses.
+pub fn hyperlink(text: &str, url: &str, escapes: bool) -> String {
+    if escapes {
+        format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+    } else {
+        format!("{} ({})", text, url)
+    }
+}
+
+pub fn separated_list(list: &[String], separator: &str) -> String {
     let mut result = String::new();
     let mut first_loop = true;
     for crate_name in list {
         if !first_loop {
-            result.push_str(", ");
+            result.push_str(separator);
         }
         first_loop = false;
         result.push_str(crate_name.as_str());
@@ -230,10 +524,14 @@ pub fn comma_separated_list(list: &[String]) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{sourced_dependencies_from_metadata, SourcedPackage};
-    use cargo_metadata::Metadata;
+    use super::{
+        git_source, hyperlink, proc_macro_crate_names, sourced_dependencies_from_metadata,
+        PkgSource, SourcedPackage,
+    };
+    use cargo_metadata::{Metadata, PackageId, Source};
     use std::{
         cmp::Ordering,
+        collections::{HashMap, HashSet},
         env::var,
         fs::{read_dir, read_to_string, write},
         path::Path,
@@ -264,7 +562,8 @@ mod tests {
                 let path = prefix.clone() + ".deps" + if no_dev { "_no_dev" } else { "" } + ".json";
 
                 let mut deps_from_metadata =
-                    sourced_dependencies_from_metadata(metadata.clone(), no_dev).unwrap();
+                    sourced_dependencies_from_metadata(metadata.clone(), no_dev, false, false)
+                        .unwrap();
                 deps_from_metadata.sort_by(cmp_dep);
 
                 if enabled("BLESS") {
@@ -276,7 +575,12 @@ mod tests {
                 let mut deps_from_file = sourced_dependencies_from_file(&path);
                 deps_from_file.sort_by(cmp_dep);
 
-                assert_eq!(deps_from_file, deps_from_metadata);
+                // `SourcedPackage` equality ignores where the package is in the graph,
+                // so compare everything through the serialized form instead
+                assert_eq!(
+                    serde_json::to_value(&deps_from_file).unwrap(),
+                    serde_json::to_value(&deps_from_metadata).unwrap()
+                );
             }
         }
     }
@@ -291,6 +595,17 @@ mod tests {
         assert!(deps.iter().any(|dep| dep.package.name == "snapbox-macros"));
     }
 
+    #[test]
+    fn cargo_proc_macros() {
+        let deps = sourced_dependencies_from_file("deps_tests/cargo_0.70.1.deps.json");
+        let proc_macros = proc_macro_crate_names(&deps);
+
+        assert!(proc_macros.contains("serde_derive"));
+        assert!(proc_macros.contains("cargo-test-macro"));
+        assert!(!proc_macros.contains("serde"));
+        assert_eq!(proc_macros.len(), 6);
+    }
+
     #[test]
     fn cargo_no_dev() {
         let deps = sourced_dependencies_from_file("deps_tests/cargo_0.70.1.deps_no_dev.json");
@@ -299,6 +614,112 @@ mod tests {
         assert!(deps.iter().all(|dep| dep.package.name != "snapbox-macros"));
     }
 
+    #[test]
+    fn cargo_depth() {
+        let contents = read_to_string("deps_tests/cargo_0.70.1.metadata.json").unwrap();
+        let metadata = serde_json::from_str::<Metadata>(&contents).unwrap();
+        let deps =
+            sourced_dependencies_from_metadata(metadata.clone(), false, false, false).unwrap();
+
+        let depths: HashMap<&PackageId, usize> = deps
+            .iter()
+            .map(|dep| (&dep.package.id, dep.depth.unwrap()))
+            .collect();
+        let resolve = metadata.resolve.as_ref().unwrap();
+        for dep in &deps {
+            let depth = depths[&dep.package.id];
+            if metadata.workspace_members.contains(&dep.package.id) {
+                assert_eq!(depth, 0);
+                continue;
+            }
+            // One more than the closest of the packages that depend on it
+            let closest_dependent = resolve
+                .nodes
+                .iter()
+                .filter(|node| node.dependencies.contains(&dep.package.id))
+                .filter_map(|node| depths.get(&node.id))
+                .min();
+            assert_eq!(
+                closest_dependent.map(|depth| depth + 1),
+                Some(depth),
+                "{}",
+                dep.package.id
+            );
+        }
+
+        let depth_of = |name| {
+            deps.iter()
+                .find(|dep| dep.package.name == name)
+                .unwrap()
+                .depth
+        };
+        assert_eq!(depth_of("cargo"), Some(0));
+        assert_eq!(depth_of("snapbox"), Some(1));
+        assert_eq!(depth_of("snapbox-macros"), Some(2));
+        // Also a dependency of dependencies of `cargo`, but the shortest path counts
+        assert_eq!(depth_of("serde"), Some(1));
+    }
+
+    // `cc` is only a build dependency of `cargo`'s dependencies, such as `libgit2-sys`.
+    // `flate2` is both a build dependency of `cargo` and a normal one.
+
+    #[test]
+    fn cargo_build_dependencies() {
+        let contents = read_to_string("deps_tests/cargo_0.70.1.metadata.json").unwrap();
+        let metadata = serde_json::from_str::<Metadata>(&contents).unwrap();
+
+        let deps =
+            sourced_dependencies_from_metadata(metadata.clone(), false, false, false).unwrap();
+        let cc = deps.iter().find(|dep| dep.package.name == "cc").unwrap();
+        assert!(cc.build_only);
+        let flate2 = deps
+            .iter()
+            .find(|dep| dep.package.name == "flate2")
+            .unwrap();
+        assert!(!flate2.build_only);
+
+        // `--no-dev` leaves build dependencies alone
+        let deps = sourced_dependencies_from_metadata(metadata, true, false, false).unwrap();
+        let cc = deps.iter().find(|dep| dep.package.name == "cc").unwrap();
+        assert!(cc.build_only);
+    }
+
+    #[test]
+    fn cargo_no_transitive() {
+        let contents = read_to_string("deps_tests/cargo_0.70.1.metadata.json").unwrap();
+        let metadata = serde_json::from_str::<Metadata>(&contents).unwrap();
+
+        let deps =
+            sourced_dependencies_from_metadata(metadata.clone(), false, false, true).unwrap();
+        assert!(deps.iter().all(|dep| dep.depth <= Some(1)));
+        assert!(deps.iter().any(|dep| dep.package.name == "cargo"));
+        assert!(deps.iter().any(|dep| dep.package.name == "snapbox"));
+        assert!(deps.iter().all(|dep| dep.package.name != "snapbox-macros"));
+
+        let deps = sourced_dependencies_from_metadata(metadata, true, false, true).unwrap();
+        assert!(deps.iter().all(|dep| dep.package.name != "snapbox"));
+    }
+
+    // `cargo-util` and other crates of the `cargo` repository are path dependencies,
+    // but not members of its workspace.
+
+    #[test]
+    fn cargo_path_dependencies() {
+        let deps = sourced_dependencies_from_file("deps_tests/cargo_0.70.1.deps.json");
+
+        let cargo_util = deps
+            .iter()
+            .find(|dep| dep.package.name == "cargo-util")
+            .unwrap();
+        assert_eq!(
+            cargo_util.source,
+            PkgSource::Path {
+                location: "$CARGO_MANIFEST_DIR/crates/cargo-util".into()
+            }
+        );
+        assert!(deps.iter().all(|dep| dep.source != PkgSource::Foreign));
+    }
+
     #[test]
     fn snapbox() {
         let deps = sourced_dependencies_from_file("deps_tests/snapbox_0.4.11.deps.json");
@@ -306,11 +727,134 @@ mod tests {
         assert!(deps.iter().any(|dep| dep.package.name == "snapbox-macros"));
     }
 
+    #[test]
+    fn git() {
+        assert_eq!(
+            git_source("git+https://github.com/rust-lang/cargo?branch=master#0123abcd"),
+            Some(PkgSource::Git {
+                url: "https://github.com/rust-lang/cargo".to_owned(),
+                rev: "0123abcd".to_owned(),
+            })
+        );
+        assert_eq!(
+            git_source("git+https://github.com/rust-lang/cargo#0123abcd"),
+            Some(PkgSource::Git {
+                url: "https://github.com/rust-lang/cargo".to_owned(),
+                rev: "0123abcd".to_owned(),
+            })
+        );
+        assert_eq!(
+            git_source("registry+https://github.com/rust-lang/crates.io-index"),
+            None
+        );
+    }
+
+    #[test]
+    fn from_cargo_source() {
+        let classify = |repr: &str| {
+            let source: Source = serde_json::from_value(repr.into()).unwrap();
+            PkgSource::from_cargo_source(&source)
+        };
+        assert_eq!(
+            classify("registry+https://github.com/rust-lang/crates.io-index"),
+            PkgSource::CratesIo
+        );
+        assert_eq!(
+            classify("sparse+https://index.crates.io/"),
+            PkgSource::CratesIo
+        );
+        assert_eq!(
+            classify("git+https://github.com/rust-lang/cargo?rev=0123abcd#0123abcd"),
+            PkgSource::Git {
+                url: "https://github.com/rust-lang/cargo".to_owned(),
+                rev: "0123abcd".to_owned(),
+            }
+        );
+        assert_eq!(
+            classify("registry+https://my-intranet:8080/git/index"),
+            PkgSource::Foreign
+        );
+        assert_eq!(
+            classify("sparse+https://my-intranet:8080/index/"),
+            PkgSource::Foreign
+        );
+    }
+
+    // `snapbox`, `snapbox-macros` and `trycmd` are members of the same workspace and share dependencies.
+    // `trycmd` depends on `snapbox`, but not the other way around.
+
+    #[test]
+    fn snapbox_workspace() {
+        let contents = read_to_string("deps_tests/snapbox_0.4.11.metadata.json").unwrap();
+        let metadata = serde_json::from_str::<Metadata>(&contents).unwrap();
+
+        let package_deps =
+            sourced_dependencies_from_metadata(metadata.clone(), false, true, false).unwrap();
+        let workspace_deps =
+            sourced_dependencies_from_metadata(metadata.clone(), false, false, false).unwrap();
+
+        assert!(package_deps.iter().any(|dep| dep.package.name == "snapbox"));
+        assert!(package_deps.iter().all(|dep| dep.package.name != "trycmd"));
+        assert!(workspace_deps
+            .iter()
+            .any(|dep| dep.package.name == "trycmd"));
+
+        // Every package is listed at most once
+        let workspace_ids = package_ids(&workspace_deps);
+        assert_eq!(workspace_ids.len(), workspace_deps.len());
+
+        // The workspace dependencies are the union of the dependencies of each member
+        let mut union = HashSet::new();
+        for member in &metadata.workspace_members {
+            let mut metadata = metadata.clone();
+            metadata.resolve.as_mut().unwrap().root = Some(member.clone());
+            let member_deps =
+                sourced_dependencies_from_metadata(metadata, false, true, false).unwrap();
+            union.extend(package_ids(&member_deps));
+        }
+        assert!(package_ids(&package_deps).is_subset(&workspace_ids));
+        assert_eq!(union, workspace_ids);
+    }
+
+    #[test]
+    fn hyperlinks() {
+        let url = "https://crates.io/crates/serde";
+        assert_eq!(
+            hyperlink("serde", url, true),
+            "\x1b]8;;https://crates.io/crates/serde\x1b\\serde\x1b]8;;\x1b\\"
+        );
+        assert_eq!(
+            hyperlink("serde", url, false),
+            "serde (https://crates.io/crates/serde)"
+        );
+    }
+
+    fn package_ids(deps: &[SourcedPackage]) -> HashSet<PackageId> {
+        deps.iter().map(|dep| dep.package.id.clone()).collect()
+    }
+
     fn sourced_dependencies_from_file(path: impl AsRef<Path>) -> Vec<SourcedPackage> {
         let contents = read_to_string(path).unwrap();
         serde_json::from_str::<Vec<SourcedPackage>>(&contents).unwrap()
     }
 
+    #[test]
+    fn sourced_package_equality() {
+        let deps = sourced_dependencies_from_file("deps_tests/cargo_0.70.1.deps.json");
+        let unique: HashSet<&SourcedPackage> = deps.iter().collect();
+        assert_eq!(unique.len(), deps.len());
+
+        let mut moved = deps[0].clone();
+        moved.depth = Some(7);
+        assert_eq!(moved, deps[0]);
+        assert!(unique.contains(&moved));
+        moved.source = PkgSource::Git {
+            url: "https://example.com/fork".to_owned(),
+            rev: "0123abcd".to_owned(),
+        };
+        assert_ne!(moved, deps[0]);
+    }
+
     fn cmp_dep(left: &SourcedPackage, right: &SourcedPackage) -> Ordering {
         left.package.id.cmp(&right.package.id)
     }
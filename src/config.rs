@@ -0,0 +1,238 @@
+//! Project-local defaults for command-line flags, read from `supply-chain.toml`
+//! or from the file given with `--config-file`
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::cli::{CliArgs, PrintJson, QueryCommandArgs, ReportingArgs};
+use crate::output::OutputFormat;
+
+/// Looked up in the directory of `--manifest-path` or the current directory, then their parents
+pub const CONFIG_FILE_NAME: &str = "supply-chain.toml";
+
+/// Written by `--write-config`, documents every supported setting
+pub const TEMPLATE: &str = include_str!("config_template.toml");
+
+/// The settings of a config file. Every one of them defaults to the default of its flag.
+#[derive(Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SupplyChainConfig {
+    #[serde(with = "humantime_serde")]
+    pub cache_max_age: Option<Duration>,
+    #[serde(with = "humantime_serde")]
+    pub max_age_warning: Option<Duration>,
+    pub offline: Option<bool>,
+    pub quiet: Option<bool>,
+    pub no_summary: Option<bool>,
+    pub format: Option<String>,
+    pub separator: Option<String>,
+    pub publishers_file: Option<PathBuf>,
+}
+
+impl SupplyChainConfig {
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Could not read config file {}", path.display()))?;
+        let mut config = Self::parse(&contents)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        // Like the other paths in the file, so that it works from any directory
+        if let (Some(file), Some(dir)) = (&mut config.publishers_file, path.parent()) {
+            *file = dir.join(&*file);
+        }
+        Ok(config)
+    }
+
+    fn parse(contents: &str) -> Result<Self, anyhow::Error> {
+        let config: Self = toml::from_str(contents)?;
+        if let Some(format) = &config.format {
+            if let Err(error) = format.parse::<OutputFormat>() {
+                bail!("{} in `format`", error);
+            }
+        }
+        if config
+            .separator
+            .as_deref()
+            .is_some_and(|s| s.contains('\n'))
+        {
+            bail!("the `separator` cannot contain a newline");
+        }
+        Ok(config)
+    }
+
+    /// `supply-chain.toml` in `dir` or the closest of its parents that has one
+    fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    /// Fills in the flags that were not given on the command line
+    fn apply(&self, args: &mut QueryCommandArgs, reporting: Option<&mut ReportingArgs>) {
+        args.cache_max_age = args.cache_max_age.or(self.cache_max_age);
+        args.max_age_warning = args.max_age_warning.or(self.max_age_warning);
+        args.offline = args.offline.or(self.offline);
+        args.no_summary = args.no_summary.or(self.no_summary);
+        if args.separator.is_none() {
+            args.separator = self.separator.clone();
+        }
+        if args.publishers_file.is_none() {
+            args.publishers_file = self.publishers_file.clone();
+        }
+        if let Some(reporting) = reporting {
+            reporting.quiet = reporting.quiet.or(self.quiet);
+        }
+        // Validated while parsing. Like `CARGO_SUPPLY_CHAIN_FORMAT`, which takes precedence,
+        // it is ignored by the commands that do not support it
+        args.config_format = self
+            .format
+            .as_deref()
+            .and_then(|format| format.parse().ok());
+    }
+}
+
+/// Applies `--config-file`, or else the `supply-chain.toml` found for the command,
+/// to the arguments of the querying commands
+pub fn apply_config(args: &mut CliArgs) -> Result<(), anyhow::Error> {
+    let (args, reporting, manifest_path) = match args {
+        CliArgs::Publishers {
+            args,
+            reporting,
+            meta_args,
+            ..
+        }
+        | CliArgs::Crates {
+            args,
+            reporting,
+            meta_args,
+            ..
+        } => (args, Some(reporting), meta_args.manifest_path.clone()),
+        CliArgs::Json(PrintJson::Info {
+            args, meta_args, ..
+        })
+        | CliArgs::TrustGraph {
+            args, meta_args, ..
+        } => (args, None, meta_args.manifest_path.clone()),
+        CliArgs::Inspect { args, .. } | CliArgs::OwnedBy { args, .. } => (args, None, None),
+        CliArgs::Prune { .. }
+        | CliArgs::CacheInfo { .. }
+        | CliArgs::Update { .. }
+        | CliArgs::List
+        | CliArgs::VersionVerbose
+        | CliArgs::WriteConfig
+        | CliArgs::GenerateCompletions { .. }
+        | CliArgs::Json(PrintJson::Schema)
+        | CliArgs::Json(PrintJson::Template) => return Ok(()),
+    };
+    let path = match &args.config_file {
+        Some(path) => path.clone(),
+        None => {
+            let dir = match manifest_path.as_deref().and_then(Path::parent) {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.to_owned(),
+                _ => env::current_dir()?,
+            };
+            match SupplyChainConfig::find(&dir) {
+                Some(path) => path,
+                None => return Ok(()),
+            }
+        }
+    };
+    tracing::info!("Reading defaults from {}", path.display());
+    SupplyChainConfig::load(&path)?.apply(args, reporting);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{args_parser, DEFAULT_CACHE_MAX_AGE};
+    use bpaf::Args;
+
+    #[test]
+    fn template_sets_nothing() {
+        assert_eq!(
+            SupplyChainConfig::parse(TEMPLATE).unwrap(),
+            SupplyChainConfig::default()
+        );
+        // Every setting in the template is valid once uncommented
+        let settings: String = TEMPLATE
+            .lines()
+            .filter_map(|line| line.strip_prefix("# "))
+            .filter(|line| line.contains(" = "))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let config = SupplyChainConfig::parse(&settings).unwrap();
+        assert_eq!(config.cache_max_age, Some(DEFAULT_CACHE_MAX_AGE));
+        assert_eq!(config.format.as_deref(), Some("text"));
+    }
+
+    #[test]
+    fn invalid_configs() {
+        assert!(SupplyChainConfig::parse("exclude = [\"serde\"]").is_err());
+        assert!(SupplyChainConfig::parse("format = \"markdown\"").is_err());
+        assert!(SupplyChainConfig::parse("cache-max-age = \"5\"").is_err());
+        assert!(SupplyChainConfig::parse("separator = \"\\n\"").is_err());
+    }
+
+    #[test]
+    fn command_line_takes_precedence() {
+        let config = SupplyChainConfig::parse(
+            r#"
+            cache-max-age = "1w"
+            offline = true
+            quiet = true
+            separator = ";"
+            publishers-file = "people.json"
+            format = "json"
+            "#,
+        )
+        .unwrap();
+        let apply = |args: &[&str]| {
+            let mut cli = args_parser().run_inner(Args::from(args)).unwrap();
+            match &mut cli {
+                CliArgs::Crates {
+                    args, reporting, ..
+                } => config.apply(args, Some(reporting)),
+                _ => unreachable!(),
+            }
+            cli
+        };
+
+        let CliArgs::Crates {
+            args, reporting, ..
+        } = apply(&["crates"])
+        else {
+            unreachable!()
+        };
+        assert_eq!(args.cache_max_age(), Duration::from_secs(7 * 24 * 3600));
+        assert!(args.offline());
+        assert_eq!(args.separator(), ";");
+        assert_eq!(args.publishers_file, Some(PathBuf::from("people.json")));
+        assert_eq!(args.config_format, Some(OutputFormat::Json));
+        assert!(reporting.quiet());
+
+        // Even when the command line repeats a default, or turns off what the file turns on
+        let args = [
+            "crates",
+            "--cache-max-age=48h",
+            "--separator",
+            ", ",
+            "--no-offline",
+            "--no-quiet",
+        ];
+        let CliArgs::Crates {
+            args, reporting, ..
+        } = apply(&args)
+        else {
+            unreachable!()
+        };
+        assert_eq!(args.cache_max_age(), DEFAULT_CACHE_MAX_AGE);
+        assert!(!args.offline());
+        assert_eq!(args.separator(), ", ");
+        assert!(!reporting.quiet());
+    }
+}
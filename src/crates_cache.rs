@@ -1,14 +1,18 @@
 use crate::api_client::RateLimitedClient;
+use crate::downloads::CachedDownloadCount;
 use crate::publishers::{PublisherData, PublisherKind};
+use cargo_metadata::semver::Version;
 use flate2::read::GzDecoder;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeSet, HashMap},
+    cell::Cell,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs,
-    io::{self, ErrorKind},
-    path::PathBuf,
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
     time::Duration,
-    time::SystemTimeError,
+    time::{SystemTime, SystemTimeError},
 };
 
 pub struct CratesCache {
@@ -18,13 +22,88 @@ pub struct CratesCache {
     crate_owners: Option<HashMap<u64, Vec<CrateOwner>>>,
     users: Option<HashMap<u64, User>>,
     teams: Option<HashMap<u64, Team>>,
-    versions: Option<HashMap<(u64, String), Publisher>>,
+    /// Crate IDs mapped to their yanked versions. Versions that are not yanked are not stored.
+    versions: Option<HashMap<u64, Vec<String>>>,
+    /// Category IDs mapped to their slugs, e.g. `development-tools::build-utils`
+    categories: Option<HashMap<u64, String>>,
+    /// Crate IDs mapped to the IDs of their categories
+    crate_categories: Option<HashMap<u64, Vec<u64>>>,
+    publisher_index: Option<PublisherIndex>,
 }
 
 pub enum CacheState {
     Fresh,
+    /// Still fresh, but older than the age at which the user wants to be warned.
+    FreshButAging(Duration),
     Expired,
     Unknown,
+    /// Created by a version of this tool with a different cache format, given here
+    Incompatible(String),
+}
+
+impl CacheState {
+    /// Short description of the state, as shown by `cache-info`
+    pub fn name(&self) -> &'static str {
+        match self {
+            CacheState::Fresh | CacheState::FreshButAging(_) => "fresh",
+            CacheState::Expired => "expired",
+            CacheState::Incompatible(_) => "incompatible",
+            CacheState::Unknown => "missing",
+        }
+    }
+
+    /// Explains an [`Incompatible`](CacheState::Incompatible) cache, `None` for other states
+    pub fn incompatibility(&self) -> Option<String> {
+        let CacheState::Incompatible(created_by) = self else {
+            return None;
+        };
+        let created_by = if created_by.is_empty() {
+            "an older version"
+        } else {
+            created_by.as_str()
+        };
+        Some(format!(
+            "Cache was created by version {} but you are running {}. Please run 'cargo supply-chain update'.",
+            created_by,
+            env!("CARGO_PKG_VERSION")
+        ))
+    }
+}
+
+/// A summary of the cache for `cache-info`. Every field is `None` if it could not be determined.
+#[derive(Debug, Serialize)]
+pub struct CacheStats {
+    /// Where the cache is stored
+    pub location: Option<PathBuf>,
+    /// Names of the files in the cache directory mapped to their sizes in bytes
+    pub files: BTreeMap<String, u64>,
+    /// When crates.io created the cached dump
+    #[serde(with = "humantime_serde")]
+    pub timestamp: Option<SystemTime>,
+    /// When the dump was downloaded
+    #[serde(with = "humantime_serde")]
+    pub downloaded: Option<SystemTime>,
+    /// How old the cached data is, which decides whether the cache is still valid
+    #[serde(with = "humantime_serde")]
+    pub age: Option<Duration>,
+    /// Version of this tool that wrote the cache
+    pub tool_version: Option<String>,
+    pub crate_count: Option<usize>,
+    pub user_count: Option<usize>,
+    pub team_count: Option<usize>,
+    /// `fresh`, `expired`, `incompatible` or `missing`
+    pub state: &'static str,
+}
+
+/// A file of the cache for `cache-info --list-cache-files`
+#[derive(Debug, Serialize)]
+pub struct CacheFile {
+    pub name: &'static str,
+    /// Size in bytes, `None` if the file is missing
+    pub size: Option<u64>,
+    /// When the file was last written, which can differ between files after a partial download
+    #[serde(with = "humantime_serde")]
+    pub modified: Option<SystemTime>,
 }
 
 pub enum DownloadState {
@@ -34,10 +113,78 @@ pub enum DownloadState {
     Expired,
     /// We forced the download of an update.
     Stale,
+    /// The resource was not stale, but tables missing from the cache were downloaded.
+    Completed,
+}
+
+/// The tables from the crates.io database dump that are stored in the cache
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum CacheTable {
+    Metadata,
+    Crates,
+    CrateOwners,
+    Users,
+    Teams,
+    Versions,
+    Categories,
+    CrateCategories,
+}
+
+impl CacheTable {
+    pub const ALL: &'static [CacheTable] = &[
+        CacheTable::Metadata,
+        CacheTable::Crates,
+        CacheTable::CrateOwners,
+        CacheTable::Users,
+        CacheTable::Teams,
+        CacheTable::Versions,
+        CacheTable::Categories,
+        CacheTable::CrateCategories,
+    ];
+
+    /// The table stored from the dump entry at `path`, if any
+    fn from_dump_path(path: &[u8]) -> Option<CacheTable> {
+        // Compare whole file names, since e.g. `crates_categories.csv` ends with `categories.csv`
+        // and `default_versions.csv` with `versions.csv`
+        let file_name = path.rsplit(|&byte| byte == b'/').next()?;
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|table| file_name == table.dump_file_name().as_bytes())
+    }
+
+    fn dump_file_name(self) -> &'static str {
+        match self {
+            CacheTable::Metadata => "metadata.json",
+            CacheTable::Crates => "crates.csv",
+            CacheTable::CrateOwners => "crate_owners.csv",
+            CacheTable::Users => "users.csv",
+            CacheTable::Teams => "teams.csv",
+            CacheTable::Versions => "versions.csv",
+            CacheTable::Categories => "categories.csv",
+            CacheTable::CrateCategories => "crates_categories.csv",
+        }
+    }
+
+    fn file_name(self) -> &'static str {
+        match self {
+            CacheTable::Metadata => CratesCache::METADATA_FS,
+            CacheTable::Crates => CratesCache::CRATES_FS,
+            CacheTable::CrateOwners => CratesCache::CRATE_OWNERS_FS,
+            CacheTable::Users => CratesCache::USERS_FS,
+            CacheTable::Teams => CratesCache::TEAMS_FS,
+            CacheTable::Versions => CratesCache::VERSIONS_FS,
+            CacheTable::Categories => CratesCache::CATEGORIES_FS,
+            CacheTable::CrateCategories => CratesCache::CRATE_CATEGORIES_FS,
+        }
+    }
 }
 
 struct CacheDir(PathBuf);
 
+/// Written during `download` and removed once the download is committed
+const PROGRESS_FS: &str = "progress.json";
+
 #[derive(Clone, Deserialize, Serialize)]
 struct Metadata {
     #[serde(with = "humantime_serde")]
@@ -46,10 +193,64 @@ struct Metadata {
 
 #[derive(Clone, Deserialize, Serialize)]
 struct MetadataStored {
+    /// When crates.io generated the dump, taken from its `metadata.json`
     #[serde(with = "humantime_serde")]
     timestamp: std::time::SystemTime,
+    /// When the dump was downloaded. Missing in caches created by older versions
+    #[serde(default, with = "humantime_serde")]
+    downloaded: Option<std::time::SystemTime>,
     #[serde(default)]
     etag: Option<String>,
+    /// Version of this tool that wrote the cache. Empty in caches created before it was recorded
+    #[serde(default)]
+    tool_version: String,
+}
+
+/// Maps publishers to their crates, so that looking up the crates of a publisher
+/// does not require loading and scanning all the tables
+#[derive(Clone, Deserialize, Serialize)]
+struct PublisherIndex {
+    /// Timestamp of the dump that the index was computed from.
+    /// An index of another dump, e.g. left behind by an interrupted download, is not used.
+    #[serde(with = "humantime_serde")]
+    timestamp: SystemTime,
+    /// Lowercase logins of users and teams mapped to the sorted names of their crates
+    crates: HashMap<String, Vec<String>>,
+}
+
+impl PublisherIndex {
+    fn new(
+        timestamp: SystemTime,
+        crates: &HashMap<String, Crate>,
+        crate_owners: &HashMap<u64, Vec<CrateOwner>>,
+        users: &HashMap<u64, User>,
+        teams: &HashMap<u64, Team>,
+    ) -> Self {
+        let mut index: HashMap<String, Vec<String>> = HashMap::new();
+        for crate_ in crates.values() {
+            for owner in crate_owners.get(&crate_.id).into_iter().flatten() {
+                let login = match owner.owner_kind {
+                    0 => users.get(&owner.owner_id).map(|user| &user.gh_login),
+                    1 => teams.get(&owner.owner_id).map(|team| &team.login),
+                    _ => None,
+                };
+                if let Some(login) = login {
+                    index
+                        .entry(login.to_ascii_lowercase())
+                        .or_default()
+                        .push(crate_.name.clone());
+                }
+            }
+        }
+        for names in index.values_mut() {
+            names.sort_unstable();
+            names.dedup();
+        }
+        PublisherIndex {
+            timestamp,
+            crates: index,
+        }
+    }
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -66,10 +267,27 @@ struct CrateOwner {
     owner_kind: i32,
 }
 
-#[derive(Clone, Deserialize, Serialize)]
-struct Publisher {
+/// A row of `versions.csv`, reduced to the columns needed to tell whether it is yanked
+#[derive(Deserialize)]
+struct CrateVersion {
     crate_id: u64,
-    published_by: u64,
+    num: String,
+    /// `t` or `f`, as exported by PostgreSQL
+    yanked: String,
+}
+
+/// A row of `categories.csv`, reduced to the columns that identify the category
+#[derive(Deserialize)]
+struct Category {
+    id: u64,
+    slug: String,
+}
+
+/// A row of `crates_categories.csv`
+#[derive(Deserialize)]
+struct CrateCategory {
+    crate_id: u64,
+    category_id: u64,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -96,6 +314,13 @@ impl CratesCache {
     const USERS_FS: &'static str = "users.json";
     const TEAMS_FS: &'static str = "teams.json";
     const VERSIONS_FS: &'static str = "versions.json";
+    const CATEGORIES_FS: &'static str = "categories.json";
+    const CRATE_CATEGORIES_FS: &'static str = "crate_categories.json";
+    const PUBLISHER_INDEX_FS: &'static str = "publisher_index.json";
+    /// Fetched from the live API rather than taken from the dump, see [`crate::downloads`]
+    const DOWNLOAD_COUNTS_FS: &'static str = "download_counts.json";
+    /// The secret key of `--anonymize`, see [`anonymize_key`](Self::anonymize_key)
+    const ANONYMIZE_KEY_FS: &'static str = "anonymize.key";
 
     const DUMP_URL: &'static str = "https://static.crates.io/db-dump.tar.gz";
 
@@ -109,20 +334,293 @@ impl CratesCache {
             users: None,
             teams: None,
             versions: None,
+            categories: None,
+            crate_categories: None,
+            publisher_index: None,
         }
     }
 
+    /// A cache in `dir` whose data is `age` old, for the tests of the subcommands
+    #[cfg(test)]
+    pub(crate) fn in_dir(dir: PathBuf, age: Duration) -> Self {
+        let mut cache = CratesCache::new();
+        cache.cache_dir = Some(CacheDir(dir));
+        cache.metadata = Some(MetadataStored {
+            timestamp: SystemTime::now() - age,
+            downloaded: None,
+            etag: None,
+            tool_version: env!("CARGO_PKG_VERSION").to_owned(),
+        });
+        cache
+    }
+
     fn cache_dir() -> Option<PathBuf> {
         xdg::BaseDirectories::with_prefix("cargo-supply-chain")
             .ok()
             .map(|base_directories| base_directories.get_cache_home())
     }
 
+    /// Load the given tables into memory upfront instead of on first access.
+    ///
+    /// Returns an error if any of them is missing from the cache.
+    pub fn prefetch(&mut self, tables: &[CacheTable]) -> Result<(), io::Error> {
+        let cache_dir = self.cache_dir.as_ref().ok_or(ErrorKind::NotFound)?;
+        for &table in tables {
+            let file = table.file_name();
+            match table {
+                CacheTable::Metadata => {
+                    cache_dir.load_cached(&mut self.metadata, file)?;
+                }
+                CacheTable::Crates => {
+                    cache_dir.load_cached(&mut self.crates, file)?;
+                }
+                CacheTable::CrateOwners => {
+                    cache_dir.load_cached(&mut self.crate_owners, file)?;
+                }
+                CacheTable::Users => {
+                    cache_dir.load_cached(&mut self.users, file)?;
+                }
+                CacheTable::Teams => {
+                    cache_dir.load_cached(&mut self.teams, file)?;
+                }
+                CacheTable::Versions => {
+                    cache_dir.load_cached(&mut self.versions, file)?;
+                }
+                CacheTable::Categories => {
+                    cache_dir.load_cached(&mut self.categories, file)?;
+                }
+                CacheTable::CrateCategories => {
+                    cache_dir.load_cached(&mut self.crate_categories, file)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists the files in the cache directory that are not part of the cache, such as leftovers
+    /// of interrupted downloads. If the cache is older than `older_than`, lists all of its files
+    /// except the key of `--anonymize`, which is not derived from the dump.
+    pub fn stale_files(&mut self, older_than: Option<Duration>) -> Result<Vec<PathBuf>, io::Error> {
+        let dir = match &self.cache_dir {
+            Some(CacheDir(dir)) if dir.is_dir() => dir.clone(),
+            _ => return Ok(Vec::new()),
+        };
+        // Without valid metadata the age is unknown, so consider the whole cache stale
+        let expired = older_than.is_some_and(|max_age| self.age().is_none_or(|age| age > max_age));
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let name = entry.file_name();
+            if name.to_str() == Some(Self::ANONYMIZE_KEY_FS) {
+                continue;
+            }
+            let in_use = CacheTable::ALL
+                .iter()
+                .map(|table| table.file_name())
+                .chain([Self::PUBLISHER_INDEX_FS, Self::DOWNLOAD_COUNTS_FS])
+                .any(|file| name.to_str() == Some(file));
+            if expired || !in_use {
+                files.push(entry.path());
+            }
+        }
+        files.sort_unstable();
+        Ok(files)
+    }
+
+    /// Collects the [`CacheStats`]. The cache is considered valid while younger than `max_age`.
+    ///
+    /// Loads all the tables to count their entries, so this is slow.
+    pub fn stats(&mut self, max_age: Duration) -> Result<CacheStats, io::Error> {
+        let mut files = BTreeMap::new();
+        if let Some(CacheDir(dir)) = &self.cache_dir {
+            match fs::read_dir(dir) {
+                Ok(entries) => {
+                    for entry in entries {
+                        let entry = entry?;
+                        let metadata = entry.metadata()?;
+                        if metadata.is_file() {
+                            let name = entry.file_name().to_string_lossy().into_owned();
+                            files.insert(name, metadata.len());
+                        }
+                    }
+                }
+                Err(error) if error.kind() == ErrorKind::NotFound => {}
+                Err(error) => return Err(error),
+            }
+        }
+        let metadata = self.load_metadata().cloned();
+        // The tables of another cache format may not even parse
+        let compatible = metadata.as_ref().is_some_and(MetadataStored::is_compatible);
+        let mut stats = CacheStats {
+            location: self.location().map(Path::to_owned),
+            files,
+            timestamp: metadata.as_ref().map(|meta| meta.timestamp),
+            downloaded: metadata.as_ref().and_then(|meta| meta.downloaded),
+            age: metadata.as_ref().and_then(|meta| meta.age().ok()),
+            tool_version: metadata
+                .map(|meta| meta.tool_version)
+                .filter(|version| !version.is_empty()),
+            crate_count: compatible.then(|| self.total_crate_count()).flatten(),
+            user_count: compatible.then(|| self.total_user_count()).flatten(),
+            team_count: compatible.then(|| self.total_team_count()).flatten(),
+            state: "missing",
+        };
+        // Last, because an expired cache is no longer read from
+        stats.state = self.expire(max_age, None).name();
+        Ok(stats)
+    }
+
+    /// The files that make up a complete cache, whether they exist or not
+    pub fn files(&self) -> Result<Vec<CacheFile>, io::Error> {
+        let mut files = Vec::new();
+        for name in CacheTable::ALL.iter().map(|table| table.file_name()) {
+            let metadata = match &self.cache_dir {
+                Some(CacheDir(dir)) => match fs::metadata(dir.join(name)) {
+                    Ok(metadata) => Some(metadata),
+                    Err(error) if error.kind() == ErrorKind::NotFound => None,
+                    Err(error) => return Err(error),
+                },
+                None => None,
+            };
+            files.push(CacheFile {
+                name,
+                size: metadata.as_ref().map(fs::Metadata::len),
+                modified: metadata.and_then(|metadata| metadata.modified().ok()),
+            });
+        }
+        Ok(files)
+    }
+
+    /// The download counts fetched from the live API earlier, with when each was fetched.
+    /// Unlike the tables of the dump they are read even if the dump is expired or missing.
+    pub fn live_download_counts(&self) -> HashMap<String, CachedDownloadCount> {
+        let Some(dir) = Self::cache_dir() else {
+            return HashMap::new();
+        };
+        fs::File::open(dir.join(Self::DOWNLOAD_COUNTS_FS))
+            .ok()
+            .and_then(|file| serde_json::from_reader(io::BufReader::new(file)).ok())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the download counts in the live cache
+    pub fn store_live_download_counts(
+        &self,
+        counts: &HashMap<String, CachedDownloadCount>,
+    ) -> Result<(), io::Error> {
+        let dir = Self::cache_dir().ok_or(ErrorKind::NotFound)?;
+        fs::create_dir_all(&dir)?;
+        // Written to a temporary file first, so that concurrent runs never read half of it
+        let path = dir.join(Self::DOWNLOAD_COUNTS_FS);
+        let part = path.with_extension("part");
+        let mut writer = io::BufWriter::new(fs::File::create(&part)?);
+        serde_json::to_writer(&mut writer, counts)?;
+        writer.flush()?;
+        drop(writer);
+        fs::rename(part, path)
+    }
+
+    /// The secret key of `--anonymize` in the cache directory, generated on first use,
+    /// so that the IDs stay the same for as long as the file is kept
+    pub fn anonymize_key(&self) -> Result<Vec<u8>, io::Error> {
+        let CacheDir(dir) = self.cache_dir.as_ref().ok_or(ErrorKind::NotFound)?;
+        let path = dir.join(Self::ANONYMIZE_KEY_FS);
+        match fs::read(&path) {
+            Ok(key) => return Ok(key),
+            Err(error) if error.kind() == ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+        let mut random = [0; 32];
+        getrandom::getrandom(&mut random).map_err(|e| io::Error::other(e.to_string()))?;
+        let key: String = random.iter().map(|byte| format!("{:02x}", byte)).collect();
+        fs::create_dir_all(dir)?;
+        // Never replaces the key of a concurrent run, whose IDs would then differ from ours
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                file.write_all(key.as_bytes())?;
+                Ok(key.into_bytes())
+            }
+            Err(error) if error.kind() == ErrorKind::AlreadyExists => fs::read(path),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Forget the owners of the given crates, so that they are fetched from the live API
+    /// on next use. The cache is written to disk only once for all of them.
+    ///
+    /// The crates are removed from the crates table as well, because a crate that is present
+    /// there but has no owners on record is reported as having no owners rather than looked up.
+    /// Returns how many of them were in the cache.
+    pub fn invalidate_crates(&mut self, crate_names: &[String]) -> Result<usize, io::Error> {
+        self.prefetch(&[CacheTable::Crates, CacheTable::CrateOwners])?;
+        let mut crates = self.crates.take().unwrap();
+        let mut crate_owners = self.crate_owners.take().unwrap();
+        let mut removed = 0;
+        for crate_name in crate_names {
+            if let Some(crate_) = crates.remove(crate_name) {
+                crate_owners.remove(&crate_.id);
+                removed += 1;
+            }
+        }
+
+        let cache_dir = CratesCache::cache_dir().ok_or(ErrorKind::NotFound)?;
+        let mut cache_updater = CacheUpdater::new(cache_dir)?;
+        cache_updater.store(&mut self.crates, Self::CRATES_FS, crates)?;
+        cache_updater.store(&mut self.crate_owners, Self::CRATE_OWNERS_FS, crate_owners)?;
+        // Caches downloaded by older versions have no index to keep in sync
+        if let Some(index) = self.load_publisher_index() {
+            let mut index = index.clone();
+            for names in index.crates.values_mut() {
+                names.retain(|name| !crate_names.contains(name));
+            }
+            cache_updater.store(&mut self.publisher_index, Self::PUBLISHER_INDEX_FS, index)?;
+        }
+        cache_updater.commit()?;
+        // Reload from disk on next access
+        self.crates = None;
+        self.crate_owners = None;
+        self.publisher_index = None;
+        Ok(removed)
+    }
+
+    /// Asks crates.io whether the cached dump is still the latest one, without downloading it.
+    /// Without a cached ETag there is no way to tell, so an update is assumed to be available.
+    pub fn is_up_to_date(&mut self, client: &mut RateLimitedClient) -> Result<bool, io::Error> {
+        let etag = self.etag();
+        let mut request = client.head(Self::DUMP_URL);
+        if let Some(etag) = &etag {
+            request = request.set("if-none-match", etag);
+        }
+        let response = request.call().map_err(io::Error::other)?;
+        Ok(etag.is_some()
+            && (response.status() == 304 || response.header("etag") == etag.as_deref()))
+    }
+
+    /// ETag of the cached dump, if known
+    pub fn etag(&mut self) -> Option<String> {
+        self.load_metadata()?.etag.clone()
+    }
+
     /// Re-download the list from the data dumps.
+    ///
+    /// Only the `required` tables are stored. The metadata is always stored,
+    /// since it is what determines whether the cache is fresh.
+    ///
+    /// The download is aborted once it exceeds `max_size` bytes, and the files staged
+    /// so far are removed.
     pub fn download(
         &mut self,
         client: &mut RateLimitedClient,
         max_age: Duration,
+        required: &[CacheTable],
+        max_size: u64,
     ) -> Result<DownloadState, io::Error> {
         let bar = indicatif::ProgressBar::new(!0)
             .with_prefix("Downloading")
@@ -133,13 +631,18 @@ impl CratesCache {
             )
             .with_message("preparing");
 
+        let complete = self.has_tables(required);
+        let mut fresh = false;
         let remembered_etag;
         let response = {
             let mut request = client.get(Self::DUMP_URL);
             if let Some(meta) = self.load_metadata() {
                 remembered_etag = meta.etag.clone();
                 // See if we can consider the resource not-yet-stale.
-                if meta.validate(max_age) == Some(true) {
+                fresh = meta.is_compatible() && meta.validate(max_age) == Some(true);
+                // Unless tables are missing, e.g. the categories after a plain `update`,
+                // which would otherwise not be added before the next dump
+                if fresh && complete {
                     if let Some(etag) = meta.etag.as_ref() {
                         request = request.set("if-none-match", etag);
                     }
@@ -161,6 +664,10 @@ impl CratesCache {
             .header("content-length")
             .and_then(|l| l.parse().ok())
         {
+            if length > max_size {
+                bar.finish_and_clear();
+                return Err(download_too_large(max_size));
+            }
             bar.set_style(
                 indicatif::ProgressStyle::default_bar()
                     .template("{prefix:>12.bright.cyan} [{bar:27}] {bytes:>9}/{total_bytes:9}  {bytes_per_sec}  ETA {eta:4} - {msg:.cyan}").unwrap()
@@ -174,98 +681,225 @@ impl CratesCache {
         }
 
         let etag = response.header("etag").map(String::from);
-        let reader = bar.wrap_read(response.into_reader());
+        let exceeded = Rc::new(Cell::new(false));
+        let reader = SizeLimitedReader {
+            inner: bar.wrap_read(response.into_reader()),
+            remaining: max_size,
+            max_size,
+            exceeded: Rc::clone(&exceeded),
+        };
         let ungzip = GzDecoder::new(reader);
         let mut archive = tar::Archive::new(ungzip);
 
         let cache_dir = CratesCache::cache_dir().ok_or(ErrorKind::NotFound)?;
         let mut cache_updater = CacheUpdater::new(cache_dir)?;
-        let required_files = [
-            Self::CRATE_OWNERS_FS,
-            Self::CRATES_FS,
-            Self::USERS_FS,
-            Self::TEAMS_FS,
-            Self::METADATA_FS,
-        ]
-        .iter()
-        .map(ToString::to_string)
-        .collect::<BTreeSet<_>>();
+        let resumed = cache_updater.resume(etag.clone());
+        if !resumed.is_empty() {
+            bar.println(format!(
+                "Resuming an interrupted download, already have: {}",
+                resumed.iter().cloned().collect::<Vec<_>>().join(", ")
+            ));
+            // The resumed tables are read back from disk once committed
+            self.metadata = None;
+            self.crates = None;
+            self.crate_owners = None;
+            self.users = None;
+            self.teams = None;
+            self.versions = None;
+            self.categories = None;
+            self.crate_categories = None;
+            self.publisher_index = None;
+        }
+        let required = required
+            .iter()
+            .copied()
+            .chain(std::iter::once(CacheTable::Metadata))
+            .collect::<BTreeSet<_>>();
 
-        for entry in (archive.entries()?).flatten() {
-            if let Ok(path) = entry.path() {
-                if let Some(name) = path.file_name().and_then(std::ffi::OsStr::to_str) {
-                    bar.set_message(name.to_string());
+        let mut store_entries = || -> Result<(), io::Error> {
+            for entry in (archive.entries()?).flatten() {
+                if let Ok(path) = entry.path() {
+                    if let Some(name) = path.file_name().and_then(std::ffi::OsStr::to_str) {
+                        bar.set_message(name.to_string());
+                    }
                 }
-            }
-            if entry.path_bytes().ends_with(b"crate_owners.csv") {
-                let owners: Vec<CrateOwner> = read_csv_data(entry)?;
-                cache_updater.store_multi_map(
-                    &mut self.crate_owners,
-                    Self::CRATE_OWNERS_FS,
-                    owners.as_slice(),
-                    &|owner| owner.crate_id,
-                )?;
-            } else if entry.path_bytes().ends_with(b"crates.csv") {
-                let crates: Vec<Crate> = read_csv_data(entry)?;
-                cache_updater.store_map(
-                    &mut self.crates,
-                    Self::CRATES_FS,
-                    crates.as_slice(),
-                    &|crate_| crate_.name.clone(),
-                )?;
-            } else if entry.path_bytes().ends_with(b"users.csv") {
-                let users: Vec<User> = read_csv_data(entry)?;
-                cache_updater.store_map(
-                    &mut self.users,
-                    Self::USERS_FS,
-                    users.as_slice(),
-                    &|user| user.id,
-                )?;
-            } else if entry.path_bytes().ends_with(b"teams.csv") {
-                let teams: Vec<Team> = read_csv_data(entry)?;
-                cache_updater.store_map(
-                    &mut self.teams,
-                    Self::TEAMS_FS,
-                    teams.as_slice(),
-                    &|team| team.id,
-                )?;
-            } else if entry.path_bytes().ends_with(b"metadata.json") {
-                let meta: Metadata = serde_json::from_reader(entry)?;
-                cache_updater.store(
-                    &mut self.metadata,
-                    Self::METADATA_FS,
-                    MetadataStored {
-                        timestamp: meta.timestamp,
-                        etag: etag.clone(),
-                    },
-                )?;
-            } else {
-                // This was not a file with a filename we actually use.
-                // Check if we've obtained all the files we need.
-                // If yes, we can end the download early.
-                // This saves hundreds of megabytes of traffic.
-                if required_files.is_subset(&cache_updater.staged_files) {
-                    break;
+                // Whole file names are compared, since e.g. `default_versions.csv` is not
+                // the table of versions
+                let table = CacheTable::from_dump_path(&entry.path_bytes())
+                    .filter(|table| required.contains(table));
+                // The compressed stream cannot be seeked, but already staged files need not be parsed
+                if table.is_some_and(|table| resumed.contains(table.file_name())) {
+                    continue;
+                }
+                match table {
+                    Some(CacheTable::CrateOwners) => {
+                        let owners: Vec<CrateOwner> = read_csv_data(entry)?;
+                        cache_updater.store_multi_map(
+                            &mut self.crate_owners,
+                            Self::CRATE_OWNERS_FS,
+                            owners.as_slice(),
+                            &|owner| owner.crate_id,
+                        )?;
+                    }
+                    Some(CacheTable::Crates) => {
+                        let crates: Vec<Crate> = read_csv_data(entry)?;
+                        cache_updater.store_map(
+                            &mut self.crates,
+                            Self::CRATES_FS,
+                            crates.as_slice(),
+                            &|crate_| crate_.name.clone(),
+                        )?;
+                    }
+                    Some(CacheTable::Users) => {
+                        let users: Vec<User> = read_csv_data(entry)?;
+                        cache_updater.store_map(
+                            &mut self.users,
+                            Self::USERS_FS,
+                            users.as_slice(),
+                            &|user| user.id,
+                        )?;
+                    }
+                    Some(CacheTable::Teams) => {
+                        let teams: Vec<Team> = read_csv_data(entry)?;
+                        cache_updater.store_map(
+                            &mut self.teams,
+                            Self::TEAMS_FS,
+                            teams.as_slice(),
+                            &|team| team.id,
+                        )?;
+                    }
+                    Some(CacheTable::Versions) => {
+                        // By far the largest table, so it is filtered while reading
+                        let mut yanked: HashMap<u64, Vec<String>> = HashMap::new();
+                        for version in csv_reader(entry).deserialize() {
+                            let version: CrateVersion = version?;
+                            if version.yanked == "t" {
+                                yanked
+                                    .entry(version.crate_id)
+                                    .or_default()
+                                    .push(version.num);
+                            }
+                        }
+                        cache_updater.store(&mut self.versions, Self::VERSIONS_FS, yanked)?;
+                    }
+                    Some(CacheTable::CrateCategories) => {
+                        let crate_categories: Vec<CrateCategory> = read_csv_data(entry)?;
+                        let mut by_crate: HashMap<u64, Vec<u64>> = HashMap::new();
+                        for row in crate_categories {
+                            by_crate
+                                .entry(row.crate_id)
+                                .or_default()
+                                .push(row.category_id);
+                        }
+                        cache_updater.store(
+                            &mut self.crate_categories,
+                            Self::CRATE_CATEGORIES_FS,
+                            by_crate,
+                        )?;
+                    }
+                    Some(CacheTable::Categories) => {
+                        let categories = csv_reader(entry)
+                            .deserialize()
+                            .map(|row| row.map(|category: Category| (category.id, category.slug)))
+                            .collect::<Result<HashMap<_, _>, _>>()?;
+                        cache_updater.store(
+                            &mut self.categories,
+                            Self::CATEGORIES_FS,
+                            categories,
+                        )?;
+                    }
+                    Some(CacheTable::Metadata) => {
+                        let meta: Metadata = serde_json::from_reader(entry)?;
+                        cache_updater.store(
+                            &mut self.metadata,
+                            Self::METADATA_FS,
+                            MetadataStored {
+                                timestamp: meta.timestamp,
+                                downloaded: Some(std::time::SystemTime::now()),
+                                etag: etag.clone(),
+                                tool_version: env!("CARGO_PKG_VERSION").to_owned(),
+                            },
+                        )?;
+                    }
+                    None => {
+                        // This was not a file with a filename we actually use.
+                        // Check if we've obtained all the files we need.
+                        // If yes, we can end the download early.
+                        // This saves hundreds of megabytes of traffic.
+                        if required
+                            .iter()
+                            .all(|table| cache_updater.staged_files.contains(table.file_name()))
+                        {
+                            break;
+                        }
+                    }
                 }
             }
+            Ok(())
+        };
+        let stored = store_entries();
+        // Checked even if storing succeeded, since errors reading the headers of entries are
+        // skipped. A dump too large for the limit cannot be resumed under it either.
+        if exceeded.get() {
+            cache_updater.rollback()?;
+            return Err(download_too_large(max_size));
+        }
+        stored?;
+        if [
+            CacheTable::Crates,
+            CacheTable::CrateOwners,
+            CacheTable::Users,
+            CacheTable::Teams,
+        ]
+        .iter()
+        .all(|table| required.contains(table))
+        {
+            bar.set_message("indexing publishers");
+            let index = self.staged_publisher_index(&cache_updater.dir)?;
+            cache_updater.store(&mut self.publisher_index, Self::PUBLISHER_INDEX_FS, index)?;
         }
         // Now that we've successfully downloaded and stored everything,
         // replace the old cache contents with the new one.
         cache_updater.commit()?;
 
         // If we get here, we had no etag or the etag mismatched or we forced a download due to
-        // stale data. Catch the last as it means the crates.io daily dumps were not updated.
-        if remembered_etag == etag {
-            Ok(DownloadState::Stale)
-        } else {
+        // stale data or missing tables. Catch stale data, as it means the crates.io daily dumps
+        // were not updated.
+        if remembered_etag != etag {
             Ok(DownloadState::Expired)
+        } else if fresh {
+            Ok(DownloadState::Completed)
+        } else {
+            Ok(DownloadState::Stale)
         }
     }
 
-    pub fn expire(&mut self, max_age: Duration) -> CacheState {
+    /// Whether the files of all the `tables` are in the cache directory
+    fn has_tables(&self, tables: &[CacheTable]) -> bool {
+        match &self.cache_dir {
+            Some(CacheDir(dir)) => tables
+                .iter()
+                .all(|table| dir.join(table.file_name()).is_file()),
+            None => false,
+        }
+    }
+
+    pub fn expire(&mut self, max_age: Duration, age_warning: Option<Duration>) -> CacheState {
+        if let Some(meta) = self.load_metadata() {
+            if !meta.is_compatible() {
+                let created_by = meta.tool_version.clone();
+                self.cache_dir = None;
+                return CacheState::Incompatible(created_by);
+            }
+        }
         match self.validate(max_age) {
-            // Still fresh.
-            Some(true) => CacheState::Fresh,
+            // Still fresh, but maybe not for long.
+            Some(true) => match (self.age(), age_warning) {
+                (Some(age), Some(age_warning)) if age >= age_warning => {
+                    CacheState::FreshButAging(age)
+                }
+                _ => CacheState::Fresh,
+            },
             // There was no valid meta data. Consider expired for safety.
             None => {
                 self.cache_dir = None;
@@ -278,6 +912,21 @@ impl CratesCache {
         }
     }
 
+    /// When the cached dump was created by crates.io
+    pub fn timestamp(&mut self) -> Option<std::time::SystemTime> {
+        Some(self.load_metadata()?.timestamp)
+    }
+
+    /// The version of this tool that wrote the cache, if it is known
+    pub fn tool_version(&mut self) -> Option<String> {
+        Some(self.load_metadata()?.tool_version.clone()).filter(|version| !version.is_empty())
+    }
+
+    /// Where the cache is stored, if a location could be determined
+    pub fn location(&self) -> Option<&std::path::Path> {
+        self.cache_dir.as_ref().map(|CacheDir(dir)| dir.as_path())
+    }
+
     pub fn age(&mut self) -> Option<Duration> {
         match self.load_metadata() {
             Some(meta) => meta.age().ok(),
@@ -285,23 +934,67 @@ impl CratesCache {
         }
     }
 
+    /// Whether the crate is present in the cached DB dump, or `None` if the cache is unavailable.
+    /// Tells apart crates missing from the dump, e.g. very new ones, from crates with no owners.
+    pub fn crate_exists(&mut self, crate_name: &str) -> Option<bool> {
+        Some(self.load_crates()?.contains_key(crate_name))
+    }
+
+    /// Number of crates in the cached DB dump, or `None` if the cache is unavailable
+    pub fn total_crate_count(&mut self) -> Option<usize> {
+        Some(self.load_crates()?.len())
+    }
+
+    /// Number of users in the cached DB dump, or `None` if the cache is unavailable
+    pub fn total_user_count(&mut self) -> Option<usize> {
+        Some(self.load_users()?.len())
+    }
+
+    /// Number of teams in the cached DB dump, or `None` if the cache is unavailable
+    pub fn total_team_count(&mut self) -> Option<usize> {
+        Some(self.load_teams()?.len())
+    }
+
+    /// The repository URL declared by the crate, if the crate is in the cache and declares one
+    pub fn crate_repository(&mut self, crate_name: &str) -> Option<String> {
+        self.load_crates()?.get(crate_name)?.repository.clone()
+    }
+
+    /// The slugs of the crates.io categories of the crate, e.g. `cryptography`, in alphabetical
+    /// order. `None` if the crate is not in the cache or the cache has no categories,
+    /// e.g. because an older version downloaded it
+    pub fn crate_categories(&mut self, crate_name: &str) -> Option<Vec<String>> {
+        let id = self.load_crates()?.get(crate_name)?.id;
+        let category_ids = self
+            .load_crate_categories()?
+            .get(&id)
+            .cloned()
+            .unwrap_or_default();
+        let categories = self.load_categories()?;
+        let mut slugs: Vec<String> = category_ids
+            .iter()
+            .filter_map(|id| categories.get(id).cloned())
+            .collect();
+        slugs.sort_unstable();
+        Some(slugs)
+    }
+
+    /// Whether this version of the crate is yanked, or `None` if the crate is not in the cache
+    /// or the cache has no versions, e.g. because an older version downloaded it
+    pub fn is_version_yanked(&mut self, crate_name: &str, version: &str) -> Option<bool> {
+        let id = self.load_crates()?.get(crate_name)?.id;
+        let yanked = self.load_versions()?.get(&id);
+        Some(yanked.is_some_and(|yanked| yanked.iter().any(|num| num == version)))
+    }
+
     pub fn publisher_users(&mut self, crate_name: &str) -> Option<Vec<PublisherData>> {
         let id = self.load_crates()?.get(crate_name)?.id;
         let owners = self.load_crate_owners()?.get(&id)?.clone();
-        let users = self.load_users()?;
+        self.load_users()?;
         let publisher = owners
             .into_iter()
             .filter(|owner| owner.owner_kind == 0)
-            .filter_map(|owner: CrateOwner| {
-                let user = users.get(&owner.owner_id)?;
-                Some(PublisherData {
-                    id: user.id,
-                    avatar: user.gh_avatar.clone(),
-                    login: user.gh_login.clone(),
-                    name: user.name.clone(),
-                    kind: PublisherKind::user,
-                })
-            })
+            .filter_map(|owner: CrateOwner| self.user_by_id(owner.owner_id))
             .collect();
         Some(publisher)
     }
@@ -309,24 +1002,132 @@ impl CratesCache {
     pub fn publisher_teams(&mut self, crate_name: &str) -> Option<Vec<PublisherData>> {
         let id = self.load_crates()?.get(crate_name)?.id;
         let owners = self.load_crate_owners()?.get(&id)?.clone();
-        let teams = self.load_teams()?;
+        self.load_teams()?;
         let publisher = owners
             .into_iter()
             .filter(|owner| owner.owner_kind == 1)
-            .filter_map(|owner: CrateOwner| {
-                let team = teams.get(&owner.owner_id)?;
-                Some(PublisherData {
-                    id: team.id,
-                    avatar: team.avatar.clone(),
-                    login: team.login.clone(),
-                    name: team.name.clone(),
-                    kind: PublisherKind::team,
-                })
-            })
+            .filter_map(|owner: CrateOwner| self.team_by_id(owner.owner_id))
             .collect();
         Some(publisher)
     }
 
+    /// Looks up a user by the ID that crates.io assigned to it
+    pub fn user_by_id(&mut self, id: u64) -> Option<PublisherData> {
+        let user = self.load_users()?.get(&id)?;
+        Some(PublisherData {
+            id: user.id,
+            avatar: user.gh_avatar.clone(),
+            login: user.gh_login.clone(),
+            name: user.name.clone(),
+            kind: PublisherKind::user,
+            email: None,
+        })
+    }
+
+    /// Looks up a team by the ID that crates.io assigned to it.
+    /// Team IDs are independent from user IDs, so the same ID may refer to a user too.
+    pub fn team_by_id(&mut self, id: u64) -> Option<PublisherData> {
+        let team = self.load_teams()?.get(&id)?;
+        Some(PublisherData {
+            id: team.id,
+            avatar: team.avatar.clone(),
+            login: team.login.clone(),
+            name: team.name.clone(),
+            kind: PublisherKind::team,
+            email: None,
+        })
+    }
+
+    /// Computes the [`PublisherIndex`] from the tables staged in `dir` by a download,
+    /// which are already in memory unless the download was resumed
+    fn staged_publisher_index(&mut self, dir: &Path) -> Result<PublisherIndex, io::Error> {
+        let staged = CacheDir(dir.to_owned());
+        let part = |table: CacheTable| {
+            Path::new(table.file_name())
+                .with_extension("part")
+                .display()
+                .to_string()
+        };
+        let timestamp = staged
+            .load_cached(&mut self.metadata, &part(CacheTable::Metadata))?
+            .timestamp;
+        Ok(PublisherIndex::new(
+            timestamp,
+            staged.load_cached(&mut self.crates, &part(CacheTable::Crates))?,
+            staged.load_cached(&mut self.crate_owners, &part(CacheTable::CrateOwners))?,
+            staged.load_cached(&mut self.users, &part(CacheTable::Users))?,
+            staged.load_cached(&mut self.teams, &part(CacheTable::Teams))?,
+        ))
+    }
+
+    /// Names of all crates that the user or team with this login can publish, sorted,
+    /// looked up in the index written along with the cache.
+    /// `None` if the cache has no up to date index, e.g. because an older version downloaded it.
+    pub fn crates_owned_by_login(&mut self, login: &str) -> Option<Vec<String>> {
+        let timestamp = self.load_metadata()?.timestamp;
+        let index = self.load_publisher_index()?;
+        if index.timestamp != timestamp {
+            return None;
+        }
+        let crates = index.crates.get(&login.to_ascii_lowercase());
+        Some(crates.cloned().unwrap_or_default())
+    }
+
+    /// Names of all crates that the user or team with this login can publish, sorted.
+    /// Logins are compared case-insensitively, like on GitHub.
+    pub fn crates_owned_by(
+        &mut self,
+        login: &str,
+        kind: Option<PublisherKind>,
+    ) -> Option<Vec<String>> {
+        // The index does not tell users and teams apart
+        if kind.is_none() {
+            if let Some(names) = self.crates_owned_by_login(login) {
+                return Some(names);
+            }
+        }
+        self.prefetch(&[
+            CacheTable::Crates,
+            CacheTable::CrateOwners,
+            CacheTable::Users,
+            CacheTable::Teams,
+        ])
+        .ok()?;
+        let crates = self.crates.as_ref()?;
+        let crate_owners = self.crate_owners.as_ref()?;
+        // Owner IDs are only unique together with the owner kind: 0 for users, 1 for teams
+        let mut owners: HashSet<(i32, u64)> = HashSet::new();
+        if kind != Some(PublisherKind::team) {
+            let users = self.users.as_ref()?.values();
+            owners.extend(
+                users
+                    .filter(|user| user.gh_login.eq_ignore_ascii_case(login))
+                    .map(|user| (0, user.id)),
+            );
+        }
+        if kind != Some(PublisherKind::user) {
+            let teams = self.teams.as_ref()?.values();
+            owners.extend(
+                teams
+                    .filter(|team| team.login.eq_ignore_ascii_case(login))
+                    .map(|team| (1, team.id)),
+            );
+        }
+        let crate_ids: HashSet<u64> = crate_owners
+            .values()
+            .flatten()
+            .filter(|owner| owners.contains(&(owner.owner_kind, owner.owner_id)))
+            .map(|owner| owner.crate_id)
+            .collect();
+        let mut names: Vec<String> = crates
+            .values()
+            .filter(|crate_| crate_ids.contains(&crate_.id))
+            .map(|crate_| crate_.name.clone())
+            .collect();
+        names.sort_unstable();
+        Some(names)
+    }
+
     fn validate(&mut self, max_age: Duration) -> Option<bool> {
         let meta = self.load_metadata()?;
         meta.validate(max_age)
@@ -367,26 +1168,105 @@ impl CratesCache {
             .ok()
     }
 
-    fn load_versions(&mut self) -> Option<&HashMap<(u64, String), Publisher>> {
+    fn load_publisher_index(&mut self) -> Option<&PublisherIndex> {
+        self.cache_dir
+            .as_ref()?
+            .load_cached(&mut self.publisher_index, Self::PUBLISHER_INDEX_FS)
+            .ok()
+    }
+
+    fn load_versions(&mut self) -> Option<&HashMap<u64, Vec<String>>> {
         self.cache_dir
             .as_ref()?
             .load_cached(&mut self.versions, Self::VERSIONS_FS)
             .ok()
     }
+
+    fn load_categories(&mut self) -> Option<&HashMap<u64, String>> {
+        self.cache_dir
+            .as_ref()?
+            .load_cached(&mut self.categories, Self::CATEGORIES_FS)
+            .ok()
+    }
+
+    fn load_crate_categories(&mut self) -> Option<&HashMap<u64, Vec<u64>>> {
+        self.cache_dir
+            .as_ref()?
+            .load_cached(&mut self.crate_categories, Self::CRATE_CATEGORIES_FS)
+            .ok()
+    }
+}
+
+/// The cache format that `version` of this tool reads and writes, `None` if it is not a valid
+/// version. The format only changes in releases that are semver-incompatible, so it is e.g.
+/// `0.3` for every 0.3.x release and `1` for every 1.x release
+pub fn cache_format(version: &str) -> Option<String> {
+    let version = Version::parse(version).ok()?;
+    Some(if version.major == 0 {
+        format!("0.{}", version.minor)
+    } else {
+        version.major.to_string()
+    })
+}
+
+/// Caches of e.g. 0.3.4 are fine for 0.3.5, but not for 0.4.0, see [`cache_format`]
+fn compatible_versions(stored: &str, current: &str) -> bool {
+    match (cache_format(stored), cache_format(current)) {
+        (Some(stored), Some(current)) => stored == current,
+        _ => false,
+    }
+}
+
+/// Fails reading once more than `max_size` bytes were read, and records that in `exceeded`
+struct SizeLimitedReader<R> {
+    inner: R,
+    remaining: u64,
+    max_size: u64,
+    exceeded: Rc<Cell<bool>>,
+}
+
+impl<R: io::Read> io::Read for SizeLimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        match self.remaining.checked_sub(read as u64) {
+            Some(remaining) => {
+                self.remaining = remaining;
+                Ok(read)
+            }
+            None => {
+                self.exceeded.set(true);
+                Err(download_too_large(self.max_size))
+            }
+        }
+    }
+}
+
+fn download_too_large(max_size: u64) -> io::Error {
+    io::Error::other(format!(
+        "The data dump is larger than the limit of {}. Raise it with `--max-download-size`.",
+        indicatif::HumanBytes(max_size)
+    ))
 }
 
 fn read_csv_data<T: serde::de::DeserializeOwned>(
     from: impl io::Read,
 ) -> Result<Vec<T>, csv::Error> {
-    let mut reader = csv::ReaderBuilder::new()
+    csv_reader(from).deserialize().collect()
+}
+
+fn csv_reader<R: io::Read>(from: R) -> csv::Reader<R> {
+    csv::ReaderBuilder::new()
         .delimiter(b',')
         .double_quote(true)
         .quoting(true)
-        .from_reader(from);
-    reader.deserialize().collect()
+        .from_reader(from)
 }
 
 impl MetadataStored {
+    fn is_compatible(&self) -> bool {
+        compatible_versions(&self.tool_version, env!("CARGO_PKG_VERSION"))
+    }
+
     fn validate(&self, max_age: Duration) -> Option<bool> {
         match self.age() {
             Ok(duration) => Some(duration < max_age),
@@ -394,8 +1274,14 @@ impl MetadataStored {
         }
     }
 
+    /// The age of the data, not of the download: a dump that was 3 days old when downloaded
+    /// yesterday is 4 days old. The older of the two timestamps is used in case
+    /// the local clock disagrees with the one of crates.io.
     pub fn age(&self) -> Result<Duration, SystemTimeError> {
-        self.timestamp.elapsed()
+        match self.downloaded {
+            Some(downloaded) => self.timestamp.min(downloaded).elapsed(),
+            None => self.timestamp.elapsed(),
+        }
     }
 }
 
@@ -425,6 +1311,16 @@ impl CacheDir {
 struct CacheUpdater {
     dir: PathBuf,
     staged_files: BTreeSet<String>,
+    /// ETag of the dump being staged, recorded in the progress file
+    etag: Option<String>,
+}
+
+/// Records which files of a download are already staged, so an interrupted download
+/// of the same dump can skip them
+#[derive(Deserialize, Serialize)]
+struct DownloadProgress {
+    etag: String,
+    staged: BTreeSet<String>,
 }
 
 /// Creates the cache directory if it doesn't exist.
@@ -443,9 +1339,60 @@ impl CacheUpdater {
         Ok(Self {
             dir,
             staged_files: BTreeSet::new(),
+            etag: None,
         })
     }
 
+    /// Picks up the files staged by an interrupted download of the dump with this ETag
+    /// and returns their names. If the dump has changed since, the old progress is discarded.
+    fn resume(&mut self, etag: Option<String>) -> BTreeSet<String> {
+        self.etag = etag;
+        let progress: Option<DownloadProgress> = fs::File::open(self.dir.join(PROGRESS_FS))
+            .ok()
+            .and_then(|file| serde_json::from_reader(io::BufReader::new(file)).ok());
+        match (progress, &self.etag) {
+            (Some(progress), Some(etag)) if &progress.etag == etag => {
+                // A staged file may have been cleaned up since, e.g. by `prune`
+                let resumed: BTreeSet<String> = progress
+                    .staged
+                    .into_iter()
+                    .filter(|file| self.dir.join(file).with_extension("part").is_file())
+                    .collect();
+                self.staged_files.extend(resumed.iter().cloned());
+                resumed
+            }
+            _ => BTreeSet::new(),
+        }
+    }
+
+    /// Without an ETag there is no way to tell whether a later download is of the same dump
+    fn save_progress(&self) -> Result<(), io::Error> {
+        if let Some(etag) = &self.etag {
+            let progress = DownloadProgress {
+                etag: etag.clone(),
+                staged: self.staged_files.clone(),
+            };
+            let out = fs::File::create(self.dir.join(PROGRESS_FS))?;
+            serde_json::to_writer(out, &progress)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the files staged so far, along with the progress of the download
+    fn rollback(&mut self) -> io::Result<()> {
+        let staged = std::mem::take(&mut self.staged_files);
+        let parts = staged
+            .iter()
+            .map(|file| self.dir.join(file).with_extension("part"));
+        for path in parts.chain(std::iter::once(self.dir.join(PROGRESS_FS))) {
+            match fs::remove_file(path) {
+                Err(error) if error.kind() != ErrorKind::NotFound => return Err(error),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
     /// Commits to disk any changes that you have staged via the `store()` function.
     fn commit(&mut self) -> io::Result<()> {
         let mut uncommitted_files = std::mem::take(&mut self.staged_files);
@@ -463,7 +1410,11 @@ impl CacheUpdater {
             let destination = self.dir.join(&file);
             fs::rename(source, destination)?;
         }
-        Ok(())
+        // Nothing is left to resume
+        match fs::remove_file(self.dir.join(PROGRESS_FS)) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
     }
 
     /// Does not overwrite existing data until `commit()` is called.
@@ -475,12 +1426,14 @@ impl CacheUpdater {
         *cache = None;
         let value = cache.get_or_insert(value);
 
-        self.staged_files.insert(file.to_owned());
         let out_path = self.dir.join(file).with_extension("part");
         let out_file = fs::File::create(out_path)?;
-        let out = io::BufWriter::new(out_file);
-        serde_json::to_writer(out, value)?;
-        Ok(())
+        let mut out = io::BufWriter::new(out_file);
+        serde_json::to_writer(&mut out, value)?;
+        // Only record the file as staged once it is completely written
+        out.flush()?;
+        self.staged_files.insert(file.to_owned());
+        self.save_progress()
     }
 
     fn store_map<T, K>(
@@ -523,3 +1476,422 @@ impl CacheUpdater {
         self.store(cache, file, hashed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A cache with the given users and teams already loaded, so nothing is read from disk
+    fn mock_cache(users: Vec<User>, teams: Vec<Team>) -> CratesCache {
+        let mut cache = CratesCache::new();
+        cache.cache_dir = Some(CacheDir(PathBuf::new()));
+        cache.users = Some(users.into_iter().map(|user| (user.id, user)).collect());
+        cache.teams = Some(teams.into_iter().map(|team| (team.id, team)).collect());
+        cache
+    }
+
+    #[test]
+    fn publisher_data_by_id() {
+        let mut cache = mock_cache(
+            vec![User {
+                id: 7,
+                gh_avatar: None,
+                gh_id: Some("1234".to_owned()),
+                gh_login: "dtolnay".to_owned(),
+                name: Some("David Tolnay".to_owned()),
+            }],
+            vec![Team {
+                id: 7,
+                avatar: Some("https://example.com/avatar.png".to_owned()),
+                login: "github:serde-rs:owners".to_owned(),
+                name: Some("owners".to_owned()),
+            }],
+        );
+
+        let user = cache.user_by_id(7).unwrap();
+        assert_eq!(user.login, "dtolnay");
+        assert_eq!(user.name.as_deref(), Some("David Tolnay"));
+        assert_eq!(user.kind, PublisherKind::user);
+
+        let team = cache.team_by_id(7).unwrap();
+        assert_eq!(team.login, "github:serde-rs:owners");
+        assert_eq!(
+            team.avatar.as_deref(),
+            Some("https://example.com/avatar.png")
+        );
+        assert_eq!(team.kind, PublisherKind::team);
+
+        assert!(cache.user_by_id(8).is_none());
+        assert!(cache.team_by_id(8).is_none());
+
+        assert_eq!(cache.total_user_count(), Some(1));
+        assert_eq!(cache.total_team_count(), Some(1));
+    }
+
+    #[test]
+    fn publisher_index() {
+        let timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let crate_ = |id, name: &str| {
+            let crate_ = Crate {
+                name: name.to_owned(),
+                id,
+                repository: None,
+            };
+            (crate_.name.clone(), crate_)
+        };
+        let owner = |crate_id, owner_id, owner_kind| CrateOwner {
+            crate_id,
+            owner_id,
+            owner_kind,
+        };
+        let crates = [crate_(1, "serde"), crate_(2, "syn"), crate_(3, "abandoned")].into();
+        let crate_owners = [
+            (1, vec![owner(1, 7, 0), owner(1, 7, 1)]),
+            (2, vec![owner(2, 7, 0)]),
+        ]
+        .into();
+        let mut cache = mock_cache(
+            vec![User {
+                id: 7,
+                gh_avatar: None,
+                gh_id: None,
+                gh_login: "dtolnay".to_owned(),
+                name: None,
+            }],
+            vec![Team {
+                id: 7,
+                avatar: None,
+                login: "github:serde-rs:owners".to_owned(),
+                name: None,
+            }],
+        );
+        let index = PublisherIndex::new(
+            timestamp,
+            &crates,
+            &crate_owners,
+            cache.users.as_ref().unwrap(),
+            cache.teams.as_ref().unwrap(),
+        );
+        cache.publisher_index = Some(index);
+        cache.metadata = Some(MetadataStored {
+            timestamp,
+            downloaded: None,
+            etag: None,
+            tool_version: env!("CARGO_PKG_VERSION").to_owned(),
+        });
+
+        assert_eq!(
+            cache.crates_owned_by_login("DTolnay"),
+            Some(vec!["serde".to_owned(), "syn".to_owned()])
+        );
+        assert_eq!(
+            cache.crates_owned_by_login("github:serde-rs:owners"),
+            Some(vec!["serde".to_owned()])
+        );
+        assert_eq!(cache.crates_owned_by_login("nobody"), Some(Vec::new()));
+
+        // an index of another dump is not used
+        cache.metadata.as_mut().unwrap().timestamp = timestamp + Duration::from_secs(86400);
+        assert_eq!(cache.crates_owned_by_login("dtolnay"), None);
+    }
+
+    #[test]
+    fn yanked_versions() {
+        let mut cache = mock_cache(Vec::new(), Vec::new());
+        let crate_ = |id, name: &str| {
+            let crate_ = Crate {
+                name: name.to_owned(),
+                id,
+                repository: None,
+            };
+            (crate_.name.clone(), crate_)
+        };
+        cache.crates = Some([crate_(1, "serde"), crate_(2, "syn")].into());
+        cache.versions = Some([(1, vec!["1.0.100".to_owned()])].into());
+
+        assert_eq!(cache.is_version_yanked("serde", "1.0.100"), Some(true));
+        assert_eq!(cache.is_version_yanked("serde", "1.0.197"), Some(false));
+        assert_eq!(cache.is_version_yanked("syn", "2.0.48"), Some(false));
+        assert_eq!(cache.is_version_yanked("unknown", "1.0.0"), None);
+
+        let csv = "crate_id,num,yanked,license\n1,1.0.100,t,MIT\n1,1.0.197,f,MIT\n";
+        let rows: Vec<CrateVersion> = read_csv_data(csv.as_bytes()).unwrap();
+        let yanked: Vec<_> = rows.iter().map(|row| row.yanked == "t").collect();
+        assert_eq!(yanked, [true, false]);
+    }
+
+    #[test]
+    fn crate_categories() {
+        let mut cache = mock_cache(Vec::new(), Vec::new());
+        let crate_ = |id, name: &str| {
+            let crate_ = Crate {
+                name: name.to_owned(),
+                id,
+                repository: None,
+            };
+            (crate_.name.clone(), crate_)
+        };
+        cache.crates = Some([crate_(1, "rustls"), crate_(2, "syn")].into());
+        cache.categories = Some(
+            [
+                (10, "cryptography".to_owned()),
+                (11, "network-programming".to_owned()),
+            ]
+            .into(),
+        );
+        cache.crate_categories = Some([(1, vec![11, 10])].into());
+
+        assert_eq!(
+            cache.crate_categories("rustls"),
+            Some(vec![
+                "cryptography".to_owned(),
+                "network-programming".to_owned()
+            ])
+        );
+        assert_eq!(cache.crate_categories("syn"), Some(Vec::new()));
+        assert_eq!(cache.crate_categories("unknown"), None);
+
+        let csv = "category,crates_cnt,created_at,description,id,path,slug\nCryptography,100,2017-01-17,,10,root.cryptography,cryptography\n";
+        let rows: Vec<Category> = read_csv_data(csv.as_bytes()).unwrap();
+        assert_eq!((rows[0].id, rows[0].slug.as_str()), (10, "cryptography"));
+    }
+
+    #[test]
+    fn tables_from_dump_paths() {
+        let table = |path: &str| CacheTable::from_dump_path(path.as_bytes());
+        assert_eq!(
+            table("2024-01-01/data/crates.csv"),
+            Some(CacheTable::Crates)
+        );
+        assert_eq!(
+            table("2024-01-01/data/categories.csv"),
+            Some(CacheTable::Categories)
+        );
+        assert_eq!(
+            table("2024-01-01/data/crates_categories.csv"),
+            Some(CacheTable::CrateCategories)
+        );
+        assert_eq!(
+            table("2024-01-01/metadata.json"),
+            Some(CacheTable::Metadata)
+        );
+        assert_eq!(
+            table("2024-01-01/data/versions.csv"),
+            Some(CacheTable::Versions)
+        );
+        // Sorts before `versions.csv` in the dump, but has none of its columns
+        assert_eq!(table("2024-01-01/data/default_versions.csv"), None);
+        assert_eq!(table("2024-01-01/data/keywords.csv"), None);
+    }
+
+    /// An empty directory of its own for each test
+    fn temp_cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "supply-chain-cache-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn stale_files() {
+        let dir = temp_cache_dir("stale");
+        let in_use = CacheTable::ALL
+            .iter()
+            .map(|table| table.file_name())
+            .chain([
+                CratesCache::PUBLISHER_INDEX_FS,
+                CratesCache::ANONYMIZE_KEY_FS,
+            ]);
+        for file in in_use.chain([PROGRESS_FS, "crates.json.part", "leftover.tmp"]) {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        // Only files are considered
+        fs::create_dir(dir.join("subdirectory")).unwrap();
+        let unused = vec![
+            dir.join("crates.json.part"),
+            dir.join("leftover.tmp"),
+            dir.join(PROGRESS_FS),
+        ];
+
+        let mut cache = CratesCache::in_dir(dir.clone(), Duration::from_secs(3600));
+        assert_eq!(cache.stale_files(None).unwrap(), unused);
+        assert_eq!(
+            cache.stale_files(Some(Duration::from_secs(7200))).unwrap(),
+            unused
+        );
+        // All the files of an expired cache are stale, but the key of `--anonymize`
+        let all = cache.stale_files(Some(Duration::from_secs(60))).unwrap();
+        assert_eq!(all.len(), CacheTable::ALL.len() + 1 + unused.len());
+
+        // Without metadata.json the age of the cache is unknown
+        cache.metadata = None;
+        fs::remove_file(dir.join(CratesCache::METADATA_FS)).unwrap();
+        assert_eq!(cache.stale_files(None).unwrap(), unused);
+        assert_eq!(
+            cache
+                .stale_files(Some(Duration::from_secs(7200)))
+                .unwrap()
+                .len(),
+            all.len() - 1
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(cache.stale_files(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn anonymize_key() {
+        let dir = temp_cache_dir("anonymize-key").join("created");
+        let cache = CratesCache::in_dir(dir.clone(), Duration::from_secs(3600));
+        let key = cache.anonymize_key().unwrap();
+        assert_eq!(key.len(), 64);
+        assert_eq!(cache.anonymize_key().unwrap(), key);
+        fs::remove_dir_all(&dir).unwrap();
+        // A new key is generated once the file is gone
+        assert_ne!(cache.anonymize_key().unwrap(), key);
+        fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn prefetch_skips_loaded_tables() {
+        let dir = temp_cache_dir("prefetch");
+        let mut cache = mock_cache(
+            vec![User {
+                id: 7,
+                gh_avatar: None,
+                gh_id: None,
+                gh_login: "dtolnay".to_owned(),
+                name: None,
+            }],
+            Vec::new(),
+        );
+        cache.cache_dir = Some(CacheDir(dir.clone()));
+        // A table that is already loaded is not read again, nor does its file need to exist
+        cache.teams = None;
+        assert!(cache.prefetch(&[CacheTable::Users]).is_ok());
+        fs::write(dir.join(CratesCache::USERS_FS), "{}").unwrap();
+        assert!(cache.prefetch(&[CacheTable::Users]).is_ok());
+        assert_eq!(cache.user_by_id(7).unwrap().login, "dtolnay");
+
+        // The others are read from the cache directory
+        let error = cache
+            .prefetch(&[CacheTable::Users, CacheTable::Teams])
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert!(cache.teams.is_none());
+        fs::write(
+            dir.join(CratesCache::TEAMS_FS),
+            r#"{"3": {"id": 3, "avatar": null, "login": "github:serde-rs:owners", "name": null}}"#,
+        )
+        .unwrap();
+        assert!(cache
+            .prefetch(&[CacheTable::Users, CacheTable::Teams])
+            .is_ok());
+        assert_eq!(cache.team_by_id(3).unwrap().login, "github:serde-rs:owners");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_tables() {
+        let dir = temp_cache_dir("missing-tables");
+        let tables = [CacheTable::Crates, CacheTable::Users];
+        for table in tables {
+            fs::write(dir.join(table.file_name()), "{}").unwrap();
+        }
+        let cache = CratesCache::in_dir(dir.clone(), Duration::from_secs(3600));
+        assert!(cache.has_tables(&tables));
+        assert!(!cache.has_tables(&[CacheTable::Users, CacheTable::Categories]));
+        fs::write(dir.join(CratesCache::CATEGORIES_FS), "{}").unwrap();
+        assert!(cache.has_tables(&[CacheTable::Users, CacheTable::Categories]));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!cache.has_tables(&tables));
+    }
+
+    #[test]
+    fn resume_download() {
+        let dir = temp_cache_dir("resume");
+        let etag = || Some("\"dump-1\"".to_owned());
+        let mut updater = CacheUpdater::new(dir.clone()).unwrap();
+        assert!(updater.resume(etag()).is_empty());
+        updater
+            .store(&mut None, CratesCache::CRATES_FS, vec![1, 2, 3])
+            .unwrap();
+        updater
+            .store(&mut None, CratesCache::USERS_FS, vec![4])
+            .unwrap();
+        assert!(dir.join(PROGRESS_FS).is_file());
+        let staged = BTreeSet::from([
+            CratesCache::CRATES_FS.to_owned(),
+            CratesCache::USERS_FS.to_owned(),
+        ]);
+
+        // the same dump picks up where the download was interrupted
+        let mut resumed = CacheUpdater::new(dir.clone()).unwrap();
+        assert_eq!(resumed.resume(etag()), staged);
+        assert_eq!(resumed.staged_files, staged);
+
+        // another dump, or one that cannot be told apart, starts over
+        let mut other = CacheUpdater::new(dir.clone()).unwrap();
+        assert!(other.resume(Some("\"dump-2\"".to_owned())).is_empty());
+        assert!(other.resume(None).is_empty());
+        assert!(other.staged_files.is_empty());
+
+        // staged files that were deleted since are downloaded again
+        fs::remove_file(dir.join(CratesCache::USERS_FS).with_extension("part")).unwrap();
+        let mut resumed = CacheUpdater::new(dir.clone()).unwrap();
+        assert_eq!(
+            resumed.resume(etag()),
+            BTreeSet::from([CratesCache::CRATES_FS.to_owned()])
+        );
+
+        resumed.commit().unwrap();
+        assert!(!dir.join(PROGRESS_FS).exists());
+        assert!(dir.join(CratesCache::CRATES_FS).is_file());
+        assert!(!dir
+            .join(CratesCache::CRATES_FS)
+            .with_extension("part")
+            .exists());
+        let mut after_commit = CacheUpdater::new(dir.clone()).unwrap();
+        assert!(after_commit.resume(etag()).is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn cache_version_compatibility() {
+        assert!(compatible_versions("0.3.4", "0.3.4"));
+        assert!(compatible_versions("0.3.4", "0.3.5"));
+        assert!(compatible_versions("0.3.5", "0.3.4"));
+        assert!(compatible_versions("1.2.0", "1.3.1"));
+        assert!(!compatible_versions("0.3.4", "0.4.0"));
+        assert!(!compatible_versions("1.2.0", "2.0.0"));
+        // written before the version was recorded
+        assert!(!compatible_versions("", "0.3.4"));
+
+        assert_eq!(cache_format("0.3.3").as_deref(), Some("0.3"));
+        assert_eq!(cache_format("1.2.0").as_deref(), Some("1"));
+        assert_eq!(cache_format(""), None);
+    }
+
+    #[test]
+    fn size_limited_reader() {
+        let read_with_limit = |max_size| {
+            let exceeded = Rc::new(Cell::new(false));
+            let mut reader = SizeLimitedReader {
+                inner: &b"0123456789"[..],
+                remaining: max_size,
+                max_size,
+                exceeded: Rc::clone(&exceeded),
+            };
+            let result = io::Read::read_to_end(&mut reader, &mut Vec::new());
+            (result.is_ok(), exceeded.get())
+        };
+        assert_eq!(read_with_limit(10), (true, false));
+        assert_eq!(read_with_limit(9), (false, true));
+    }
+}
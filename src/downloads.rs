@@ -0,0 +1,204 @@
+//! Download counts of crates from the live crates.io API, for `crates --show-download-count`.
+//!
+//! The counts are a proxy for how widely used a crate is. Fetching them is best effort:
+//! a failure is reported as a warning and the crates are listed without them.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt, io,
+    time::{Duration, SystemTime},
+};
+use tracing::{debug, info, warn};
+
+use crate::api_client::RateLimitedClient;
+use crate::cli::QueryCommandArgs;
+use crate::crates_cache::CratesCache;
+
+/// How long fetched download counts are reused. Much shorter than the validity of the dump,
+/// since the counts change daily.
+pub const MAX_AGE: Duration = Duration::from_secs(24 * 3600);
+
+/// How many times a crate was downloaded
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DownloadCount {
+    pub total: u64,
+    /// Downloads in the last 90 days. crates.io does not always compute it
+    pub recent: Option<u64>,
+}
+
+impl DownloadCount {
+    /// E.g. `2.5M total, 150K recent`
+    pub fn summary(&self) -> String {
+        match self.recent {
+            Some(recent) => format!(
+                "{} total, {} recent",
+                abbreviate(self.total),
+                abbreviate(recent)
+            ),
+            None => format!("{} total", abbreviate(self.total)),
+        }
+    }
+}
+
+impl fmt::Display for DownloadCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({})", self.summary())
+    }
+}
+
+/// A [`DownloadCount`] in the live cache, along with when it was fetched
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedDownloadCount {
+    #[serde(with = "humantime_serde")]
+    pub fetched: SystemTime,
+    #[serde(flatten)]
+    pub count: DownloadCount,
+}
+
+impl CachedDownloadCount {
+    fn is_fresh(&self) -> bool {
+        self.fetched.elapsed().is_ok_and(|age| age < MAX_AGE)
+    }
+}
+
+#[derive(Deserialize)]
+struct CrateResponse {
+    #[serde(rename = "crate")]
+    crate_: CrateDownloads,
+}
+
+#[derive(Deserialize)]
+struct CrateDownloads {
+    downloads: u64,
+    recent_downloads: Option<u64>,
+}
+
+/// Shortens a count for display, e.g. `2.5M` or `150K`
+fn abbreviate(count: u64) -> String {
+    const UNITS: &[(u64, &str)] = &[(1_000_000_000, "B"), (1_000_000, "M"), (1_000, "K")];
+    for &(size, unit) in UNITS {
+        if count >= size {
+            let value = count as f64 / size as f64;
+            return if value < 10.0 {
+                // One decimal, but `2M` rather than `2.0M`
+                let text = format!("{:.1}", value);
+                format!("{}{}", text.trim_end_matches(".0"), unit)
+            } else {
+                format!("{:.0}{}", value.floor(), unit)
+            };
+        }
+    }
+    count.to_string()
+}
+
+/// The download counts of the crates, taken from the live cache while they are younger than
+/// [`MAX_AGE`] and fetched from the live API otherwise, unless `--offline` was given.
+///
+/// Crates whose counts are unavailable are left out. Once a request fails, no more are made.
+pub fn download_counts(
+    crate_names: &[String],
+    args: &QueryCommandArgs,
+) -> HashMap<String, DownloadCount> {
+    let cache = CratesCache::new();
+    let mut cached = cache.live_download_counts();
+    let mut counts = HashMap::new();
+    let mut missing = Vec::new();
+    for crate_name in crate_names {
+        match cached.get(crate_name) {
+            Some(entry) if entry.is_fresh() => {
+                counts.insert(crate_name.clone(), entry.count);
+            }
+            _ => missing.push(crate_name),
+        }
+    }
+    if missing.is_empty() || args.offline() {
+        if !missing.is_empty() {
+            debug!(
+                "Not fetching the download counts of {} crates in --offline mode",
+                missing.len()
+            );
+        }
+        return counts;
+    }
+
+    let mut client = match RateLimitedClient::with_tls(&args.tls) {
+        Ok(client) => client.with_token(args.crates_io_token.clone()),
+        Err(error) => {
+            warn!("Could not fetch download counts from crates.io: {}", error);
+            return counts;
+        }
+    };
+    info!(
+        "Fetching the download counts of {} crates from crates.io. This will take roughly 1 second per crate due to API rate limits",
+        missing.len()
+    );
+    for (i, crate_name) in missing.iter().enumerate() {
+        info!("[{}/{}] {}", i + 1, missing.len(), crate_name);
+        match fetch_download_count(&mut client, crate_name) {
+            Ok(count) => {
+                counts.insert((*crate_name).clone(), count);
+                let fetched = SystemTime::now();
+                cached.insert(
+                    (*crate_name).clone(),
+                    CachedDownloadCount { fetched, count },
+                );
+            }
+            Err(error) => {
+                warn!(
+                    "Could not fetch download counts from crates.io: {}. Continuing without them.",
+                    error
+                );
+                break;
+            }
+        }
+    }
+    // Expired entries are dropped, so that the file does not grow forever
+    cached.retain(|_, entry| entry.is_fresh());
+    if let Err(error) = cache.store_live_download_counts(&cached) {
+        warn!("Could not cache the download counts: {}", error);
+    }
+    counts
+}
+
+fn fetch_download_count(
+    client: &mut RateLimitedClient,
+    crate_name: &str,
+) -> Result<DownloadCount, io::Error> {
+    let url = format!("https://crates.io/api/v1/crates/{}", crate_name);
+    debug!("Fetching {}", url);
+    let response: CrateResponse = client
+        .get(&url)
+        .call()
+        .map_err(io::Error::other)?
+        .into_json()?;
+    Ok(DownloadCount {
+        total: response.crate_.downloads,
+        recent: response.crate_.recent_downloads,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abbreviated_counts() {
+        assert_eq!(abbreviate(999), "999");
+        assert_eq!(abbreviate(1_000), "1K");
+        assert_eq!(abbreviate(1_234), "1.2K");
+        assert_eq!(abbreviate(150_000), "150K");
+        assert_eq!(abbreviate(2_500_000), "2.5M");
+        assert_eq!(abbreviate(3_000_000_000), "3B");
+        let count = DownloadCount {
+            total: 2_500_000,
+            recent: Some(150_000),
+        };
+        assert_eq!(count.to_string(), "(2.5M total, 150K recent)");
+        let count = DownloadCount {
+            recent: None,
+            ..count
+        };
+        assert_eq!(count.to_string(), "(2.5M total)");
+    }
+}
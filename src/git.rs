@@ -0,0 +1,135 @@
+//! Reading the dependency graph as it was at an earlier git revision, for `--since-commit`
+
+use anyhow::{bail, Context};
+use cargo_lock::Lockfile;
+use std::{path::Path, process::Command};
+
+/// Returns the names of the crates.io crates in the `Cargo.lock` of the workspace at
+/// `workspace_root` as it was at `git_ref`
+pub fn crate_names_from_lockfile_at_ref(
+    workspace_root: &Path,
+    git_ref: &str,
+) -> Result<Vec<String>, anyhow::Error> {
+    // `git show` would take it for an option, e.g. `--output=<file>`
+    if git_ref.starts_with('-') {
+        bail!("Invalid git revision `{}`", git_ref);
+    }
+    // `./` makes git resolve the path relative to the directory it runs in, i.e. the
+    // workspace root, rather than to the top level of the repository
+    let object = format!("{}:./Cargo.lock", git_ref);
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(workspace_root)
+        .args(["show", &object])
+        .output()
+        .context("Failed to run `git`")?;
+    if !output.status.success() {
+        bail!(
+            "Could not read Cargo.lock at `{}`:\n{}",
+            git_ref,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+    }
+    let contents = String::from_utf8(output.stdout)
+        .with_context(|| format!("Cargo.lock at `{}` is not valid UTF-8", git_ref))?;
+    let lockfile: Lockfile = contents
+        .parse()
+        .with_context(|| format!("Failed to parse Cargo.lock at `{}`", git_ref))?;
+    let mut names: Vec<String> = lockfile
+        .packages
+        .into_iter()
+        .filter(|package| {
+            package
+                .source
+                .as_ref()
+                .is_some_and(|source| source.is_default_registry())
+        })
+        .map(|package| package.name.as_str().to_owned())
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, path::PathBuf};
+
+    const LOCKFILE: &str = r#"version = 3
+
+[[package]]
+name = "member"
+version = "0.1.0"
+dependencies = [
+ "serde",
+]
+
+[[package]]
+name = "serde"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(["-c", "commit.gpgsign=false"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    }
+
+    /// A repository with a workspace in `workspace/`, whose Cargo.lock has one commit
+    fn repository(name: &str) -> PathBuf {
+        let top = env::temp_dir().join(format!("supply-chain-git-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&top);
+        fs::create_dir_all(top.join("workspace")).unwrap();
+        fs::write(top.join("workspace/Cargo.lock"), LOCKFILE).unwrap();
+        git(&top, &["init", "-q"]);
+        git(&top, &["add", "."]);
+        git(&top, &["commit", "-q", "-m", "lockfile"]);
+        top
+    }
+
+    #[test]
+    fn lockfile_of_workspace_in_subdirectory() {
+        let top = repository("subdirectory");
+        // Later changes are not committed, so they must not show up
+        let mut contents = LOCKFILE.to_owned();
+        contents.push_str(
+            "\n[[package]]\nname = \"rand\"\nversion = \"0.8.0\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+        );
+        fs::write(top.join("workspace/Cargo.lock"), contents).unwrap();
+
+        // The tests run in the directory of this crate, not in the repository
+        let names = crate_names_from_lockfile_at_ref(&top.join("workspace"), "HEAD").unwrap();
+        assert_eq!(names, ["serde"]);
+        fs::remove_dir_all(top).unwrap();
+    }
+
+    #[test]
+    fn no_lockfile_at_top_level() {
+        let top = repository("top-level");
+        let error = crate_names_from_lockfile_at_ref(&top, "HEAD").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Could not read Cargo.lock at `HEAD`"));
+        assert!(crate_names_from_lockfile_at_ref(&top.join("workspace"), "no-such-ref").is_err());
+        fs::remove_dir_all(top).unwrap();
+    }
+
+    #[test]
+    fn options_are_not_revisions() {
+        let top = repository("option");
+        let output = top.join("output");
+        let option = format!("--output={}", output.display());
+        let error = crate_names_from_lockfile_at_ref(&top.join("workspace"), &option).unwrap_err();
+        assert!(error.to_string().starts_with("Invalid git revision"));
+        assert!(!output.exists());
+        fs::remove_dir_all(top).unwrap();
+    }
+}
@@ -12,34 +12,255 @@
 mod api_client;
 mod cli;
 mod common;
+mod config;
 mod crates_cache;
+mod downloads;
+mod git;
+mod output;
 mod publishers;
 mod subcommands;
+mod summary;
+mod supplemental;
+#[cfg(feature = "tui")]
+mod tui;
+mod webhook;
 
-use cli::CliArgs;
+use cli::{CliArgs, QueryCommandArgs};
 use common::MetadataArgs;
+use output::{JsonFormat, OutputFormat};
+use subcommands::DryRunTarget;
 
 fn main() -> Result<(), anyhow::Error> {
     let args = cli::args_parser().fallback_to_usage().run();
     dispatch_command(args)
 }
 
-fn dispatch_command(args: CliArgs) -> Result<(), anyhow::Error> {
+/// The formats of the subcommand with their descriptions, if `--help-formats` was given
+fn help_formats(args: &CliArgs) -> Option<&'static [(&'static str, &'static str)]> {
+    let (help_formats, formats) = match args {
+        CliArgs::Publishers {
+            publishers_args, ..
+        } => (
+            publishers_args.help_formats,
+            subcommands::publishers::FORMATS,
+        ),
+        CliArgs::Crates { crates_args, .. } => {
+            (crates_args.help_formats, subcommands::crates::FORMATS)
+        }
+        CliArgs::Json(cli::PrintJson::Info { help_formats, .. }) => {
+            (*help_formats, subcommands::json::FORMATS)
+        }
+        CliArgs::TrustGraph { help_formats, .. } => {
+            (*help_formats, subcommands::trust_graph::FORMATS)
+        }
+        CliArgs::Update { help_formats, .. } => (*help_formats, subcommands::update::FORMATS),
+        CliArgs::Inspect { .. }
+        | CliArgs::OwnedBy { .. }
+        | CliArgs::Prune { .. }
+        | CliArgs::CacheInfo { .. }
+        | CliArgs::List
+        | CliArgs::VersionVerbose
+        | CliArgs::WriteConfig
+        | CliArgs::GenerateCompletions { .. }
+        | CliArgs::Json(cli::PrintJson::Schema)
+        | CliArgs::Json(cli::PrintJson::Template) => return None,
+    };
+    help_formats.then_some(formats)
+}
+
+/// The arguments and what would be looked up, if `--dry-run` was given
+fn dry_run_target(args: &CliArgs) -> Option<(&QueryCommandArgs, DryRunTarget)> {
+    let (args, target) = match args {
+        CliArgs::Publishers {
+            args, meta_args, ..
+        }
+        | CliArgs::Crates {
+            args, meta_args, ..
+        }
+        | CliArgs::Json(cli::PrintJson::Info {
+            args, meta_args, ..
+        })
+        | CliArgs::TrustGraph {
+            args, meta_args, ..
+        } => (args, DryRunTarget::Dependencies(meta_args.clone())),
+        CliArgs::Inspect {
+            args, crate_name, ..
+        } => (args, DryRunTarget::Crate(crate_name.clone())),
+        CliArgs::OwnedBy { args, login, .. } => (args, DryRunTarget::Owner(login.clone())),
+        CliArgs::Update { .. }
+        | CliArgs::Prune { .. }
+        | CliArgs::CacheInfo { .. }
+        | CliArgs::List
+        | CliArgs::VersionVerbose
+        | CliArgs::WriteConfig
+        | CliArgs::GenerateCompletions { .. }
+        | CliArgs::Json(cli::PrintJson::Schema)
+        | CliArgs::Json(cli::PrintJson::Template) => return None,
+    };
+    args.dry_run.then_some((args, target))
+}
+
+fn dispatch_command(mut args: CliArgs) -> Result<(), anyhow::Error> {
+    // Like `--version`, before reading the config file, which could fail
+    if let Some(formats) = help_formats(&args) {
+        output::print_formats(formats);
+        return Ok(());
+    }
+    match &args {
+        CliArgs::Publishers { args, .. }
+        | CliArgs::Crates { args, .. }
+        | CliArgs::Json(cli::PrintJson::Info { args, .. })
+        | CliArgs::Inspect { args, .. }
+        | CliArgs::OwnedBy { args, .. }
+        | CliArgs::TrustGraph { args, .. } => init_tracing(args.verbose),
+        CliArgs::Update { .. }
+        | CliArgs::Prune { .. }
+        | CliArgs::CacheInfo { .. }
+        | CliArgs::List
+        | CliArgs::VersionVerbose
+        | CliArgs::WriteConfig
+        | CliArgs::GenerateCompletions { .. }
+        | CliArgs::Json(cli::PrintJson::Schema)
+        | CliArgs::Json(cli::PrintJson::Template) => init_tracing(0),
+    }
+    config::apply_config(&mut args)?;
+    if let Some((args, target)) = dry_run_target(&args) {
+        return subcommands::dry_run(args, target);
+    }
+
     match args {
-        CliArgs::Publishers { args, meta_args } => {
-            subcommands::publishers(meta_args, args.diffable, args.cache_max_age)?;
+        CliArgs::Publishers {
+            args,
+            owner_count,
+            publishers_args,
+            reporting,
+            count,
+            meta_args,
+        } => {
+            let passed = subcommands::publishers(
+                meta_args,
+                args,
+                owner_count,
+                publishers_args,
+                reporting,
+                count,
+            )?;
+            if !passed {
+                // Exiting skips flushing, and JSON output does not end with a newline
+                std::io::Write::flush(&mut std::io::stdout())?;
+                std::process::exit(2);
+            }
         }
-        CliArgs::Crates { args, meta_args } => {
-            subcommands::crates(meta_args, args.diffable, args.cache_max_age)?;
+        CliArgs::Crates {
+            args,
+            owner_count,
+            crates_args,
+            reporting,
+            count,
+            meta_args,
+        } => {
+            subcommands::crates(meta_args, args, owner_count, crates_args, reporting, count)?;
+        }
+        CliArgs::Inspect {
+            args,
+            json,
+            crate_name,
+        } => subcommands::inspect(&crate_name, args, json)?,
+        CliArgs::OwnedBy { args, kind, login } => subcommands::owned_by(&login, kind, args)?,
+        CliArgs::TrustGraph {
+            args,
+            format,
+            meta_args,
+            ..
+        } => subcommands::trust_graph(meta_args, args, format)?,
+        CliArgs::CacheInfo {
+            cache_max_age,
+            json,
+            list_cache_files,
+        } => subcommands::cache_info(cache_max_age, json, list_cache_files)?,
+        CliArgs::List => subcommands::list(),
+        CliArgs::VersionVerbose => subcommands::version_verbose(),
+        CliArgs::WriteConfig => subcommands::write_config()?,
+        CliArgs::GenerateCompletions { shell } => subcommands::generate_completions(shell),
+        CliArgs::Prune {
+            older_than,
+            dry_run,
+        } => subcommands::prune(older_than, dry_run)?,
+        CliArgs::Update {
+            cache_max_age,
+            offline,
+            tls,
+            invalidate_crate,
+            with_yanked,
+            with_categories,
+            update_check,
+            format,
+            max_download_size,
+            ..
+        } => {
+            if update_check {
+                let supported = [OutputFormat::Text, OutputFormat::Json];
+                let format = OutputFormat::flag_or_env(format, None, &supported)?
+                    .unwrap_or(OutputFormat::Text);
+                if !subcommands::update_check(offline, &tls, format)? {
+                    std::process::exit(2);
+                }
+            } else if format.is_some() {
+                anyhow::bail!("`--format` requires `--update-check`");
+            } else {
+                subcommands::update(
+                    cache_max_age,
+                    offline,
+                    &tls,
+                    &invalidate_crate,
+                    with_yanked,
+                    with_categories,
+                    max_download_size,
+                )?
+            }
         }
-        CliArgs::Update { cache_max_age } => subcommands::update(cache_max_age)?,
         CliArgs::Json(json) => match json {
             cli::PrintJson::Schema => subcommands::print_schema()?,
-            cli::PrintJson::Info { args, meta_args } => {
-                subcommands::json(meta_args, args.diffable, args.cache_max_age)?;
+            cli::PrintJson::Template => subcommands::print_template(),
+            cli::PrintJson::Info {
+                args,
+                save,
+                format,
+                export_graph,
+                output_template,
+                output_schema,
+                meta_args,
+                ..
+            } => {
+                let format = format.unwrap_or(JsonFormat::Json);
+                subcommands::json(
+                    meta_args,
+                    args,
+                    save,
+                    format,
+                    export_graph,
+                    output_template,
+                    output_schema,
+                )?;
             }
         },
     }
 
     Ok(())
 }
+
+/// Warnings, including retries of failed requests, are always shown, so a WARN subscriber is
+/// installed even without `-v`. Progress and diagnostics are only shown when asked for with `-v`
+fn init_tracing(verbosity: usize) {
+    let level = match verbosity {
+        0 => tracing::Level::WARN,
+        1 => tracing::Level::INFO,
+        _ => tracing::Level::DEBUG,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .without_time()
+        .with_target(false)
+        .init();
+}
@@ -0,0 +1,113 @@
+//! Padding of names to a common width for `--align`, so that what follows them lines up
+
+use std::{
+    borrow::Cow,
+    io::{self, IsTerminal},
+};
+use tracing::warn;
+
+/// A column of names, as wide as the longest one unless capped by `--max-column-width`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Column {
+    width: usize,
+}
+
+impl Column {
+    /// Computes the width from all the labels that will be printed in the column
+    pub fn fitting<'a>(
+        labels: impl IntoIterator<Item = &'a str>,
+        max_width: Option<usize>,
+    ) -> Self {
+        let width = labels
+            .into_iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        Column {
+            width: max_width.map_or(width, |max| width.min(max)),
+        }
+    }
+
+    /// Pads `decorated`, which is displayed as `label` but may contain e.g. hyperlinks or colors,
+    /// to the width of the column. If it does not fit, the plain `label` is truncated instead.
+    pub fn pad(&self, label: &str, decorated: &str) -> String {
+        let len = label.chars().count();
+        if len > self.width {
+            let mut truncated: String = label.chars().take(self.width.saturating_sub(1)).collect();
+            truncated.push('…');
+            truncated
+        } else {
+            format!("{}{}", decorated, " ".repeat(self.width - len))
+        }
+    }
+
+    /// Fits `text` into the column next to `rest` more characters that must be kept whole,
+    /// e.g. markers and links. Shortens `text` if it does not fit, but keeps at least its first
+    /// character even if the column overflows. Returns the text and the padding after `rest`.
+    pub fn fit<'a>(&self, text: &'a str, rest: usize) -> (Cow<'a, str>, String) {
+        let len = text.chars().count();
+        let available = self.width.saturating_sub(rest);
+        if len <= available {
+            return (Cow::Borrowed(text), " ".repeat(available - len));
+        }
+        let keep = available.saturating_sub(1).max(1);
+        if keep + 1 >= len {
+            // The ellipsis would not make it any shorter
+            return (Cow::Borrowed(text), String::new());
+        }
+        let mut shortened: String = text.chars().take(keep).collect();
+        shortened.push('…');
+        (
+            Cow::Owned(shortened),
+            " ".repeat(available.saturating_sub(keep + 1)),
+        )
+    }
+
+    /// Warns if the terminal is too narrow to fit the column after `other` more columns
+    pub fn warn_if_too_wide(&self, other: usize) {
+        if !io::stdout().is_terminal() {
+            return;
+        }
+        let Some((terminal_size::Width(columns), _)) = terminal_size::terminal_size() else {
+            return;
+        };
+        let needed = self.width + other;
+        if usize::from(columns) < needed {
+            warn!(
+                "The terminal is {} columns wide, but {} are needed to align the output. Consider `--max-column-width`.",
+                columns, needed
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pad_and_truncate() {
+        let column = Column::fitting(["serde", "serde_json", "syn"], None);
+        assert_eq!(column.pad("syn", "syn"), "syn       ");
+        assert_eq!(column.pad("serde_json", "serde_json"), "serde_json");
+
+        let column = Column::fitting(["serde", "serde_json", "syn"], Some(6));
+        assert_eq!(column.pad("serde", "serde"), "serde ");
+        assert_eq!(column.pad("serde_json", "serde_json"), "serde…");
+    }
+
+    #[test]
+    fn fit_next_to_the_rest() {
+        let column = Column::fitting(["[build] serde_json:"], Some(14));
+        let fit = |text, rest| {
+            let (text, padding) = column.fit(text, rest);
+            (text.into_owned(), padding)
+        };
+        assert_eq!(fit("syn", 1), ("syn".to_owned(), " ".repeat(10)));
+        assert_eq!(fit("serde_json", 9), ("serd…".to_owned(), String::new()));
+        assert_eq!(fit("serde", 9), ("serde".to_owned(), String::new()));
+        // Overflows rather than dropping what must be kept
+        assert_eq!(fit("serde_json", 20), ("s…".to_owned(), String::new()));
+        assert_eq!(fit("ab", 20), ("ab".to_owned(), String::new()));
+    }
+}
@@ -0,0 +1,374 @@
+//! The graph of workspace packages, crates.io crates and their publishers, for `json --export-graph`.
+//! Meant for graph tools such as Gephi or Neo4j rather than for reading.
+
+use super::ExportGraphFormat;
+use crate::common::{declares_dependency_on, PkgSource, SourcedPackage};
+use crate::publishers::{PublisherData, PublisherKind};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum NodeKind {
+    Workspace,
+    Crate,
+    Publisher,
+}
+
+impl NodeKind {
+    fn name(self) -> &'static str {
+        match self {
+            NodeKind::Workspace => "workspace",
+            NodeKind::Crate => "crate",
+            NodeKind::Publisher => "publisher",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum EdgeKind {
+    /// From a package to one of its dependencies
+    DependsOn,
+    /// From a crate to one of its publishers
+    OwnedBy,
+}
+
+impl EdgeKind {
+    fn name(self) -> &'static str {
+        match self {
+            EdgeKind::DependsOn => "depends_on",
+            EdgeKind::OwnedBy => "owned_by",
+        }
+    }
+}
+
+struct Node {
+    id: String,
+    label: String,
+    kind: NodeKind,
+}
+
+struct Edge {
+    source: String,
+    target: String,
+    kind: EdgeKind,
+}
+
+pub struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+impl Graph {
+    /// Local and crates.io packages become nodes, other packages are left out
+    /// like everything else that cannot be audited
+    pub fn new(
+        dependencies: &[SourcedPackage],
+        owners: &BTreeMap<String, Vec<PublisherData>>,
+    ) -> Self {
+        let mut packages: Vec<&SourcedPackage> = dependencies
+            .iter()
+            .filter(|dep| matches!(dep.source, PkgSource::Local | PkgSource::CratesIo))
+            .collect();
+        packages.sort_by(|a, b| {
+            (&a.package.name, &a.package.version).cmp(&(&b.package.name, &b.package.version))
+        });
+        let package_id =
+            |dep: &SourcedPackage| format!("{}@{}", dep.package.name, dep.package.version);
+
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut publishers: BTreeMap<String, &PublisherData> = BTreeMap::new();
+        for dep in &packages {
+            let kind = match dep.source {
+                PkgSource::CratesIo => NodeKind::Crate,
+                _ => NodeKind::Workspace,
+            };
+            nodes.push(Node {
+                id: package_id(dep),
+                label: format!("{} {}", dep.package.name, dep.package.version),
+                kind,
+            });
+            for other in &packages {
+                if declares_dependency_on(&dep.package, &other.package) {
+                    edges.push(Edge {
+                        source: package_id(dep),
+                        target: package_id(other),
+                        kind: EdgeKind::DependsOn,
+                    });
+                }
+            }
+            if kind != NodeKind::Crate {
+                continue;
+            }
+            for publisher in owners.get(&dep.package.name).into_iter().flatten() {
+                let id = publisher_id(publisher);
+                edges.push(Edge {
+                    source: package_id(dep),
+                    target: id.clone(),
+                    kind: EdgeKind::OwnedBy,
+                });
+                publishers.insert(id, publisher);
+            }
+        }
+        for (id, publisher) in publishers {
+            nodes.push(Node {
+                id,
+                label: publisher.login.clone(),
+                kind: NodeKind::Publisher,
+            });
+        }
+        Graph { nodes, edges }
+    }
+
+    pub fn write(
+        &self,
+        format: ExportGraphFormat,
+        out: &mut impl Write,
+        pretty: bool,
+    ) -> Result<(), io::Error> {
+        match format {
+            ExportGraphFormat::Dot => self.write_dot(out),
+            ExportGraphFormat::Gexf => self.write_gexf(out),
+            ExportGraphFormat::Graphml => self.write_graphml(out),
+            ExportGraphFormat::JsonGraph => self.write_json_graph(out, pretty),
+        }
+    }
+
+    fn write_dot(&self, out: &mut impl Write) -> Result<(), io::Error> {
+        writeln!(out, "digraph dependencies {{")?;
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Workspace => "house",
+                NodeKind::Crate => "box",
+                NodeKind::Publisher => "ellipse",
+            };
+            writeln!(
+                out,
+                "    \"{}\" [label=\"{}\", shape={}];",
+                dot_escape(&node.id),
+                dot_escape(&node.label),
+                shape
+            )?;
+        }
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::DependsOn => "solid",
+                EdgeKind::OwnedBy => "dashed",
+            };
+            writeln!(
+                out,
+                "    \"{}\" -> \"{}\" [style={}];",
+                dot_escape(&edge.source),
+                dot_escape(&edge.target),
+                style
+            )?;
+        }
+        writeln!(out, "}}")
+    }
+
+    fn write_gexf(&self, out: &mut impl Write) -> Result<(), io::Error> {
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(out, r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#)?;
+        writeln!(out, r#"  <graph defaultedgetype="directed">"#)?;
+        writeln!(out, r#"    <attributes class="node">"#)?;
+        writeln!(
+            out,
+            r#"      <attribute id="type" title="type" type="string"/>"#
+        )?;
+        writeln!(out, r#"    </attributes>"#)?;
+        writeln!(out, r#"    <nodes>"#)?;
+        for node in &self.nodes {
+            writeln!(
+                out,
+                r#"      <node id="{}" label="{}"><attvalues><attvalue for="type" value="{}"/></attvalues></node>"#,
+                xml_escape(&node.id),
+                xml_escape(&node.label),
+                node.kind.name()
+            )?;
+        }
+        writeln!(out, r#"    </nodes>"#)?;
+        writeln!(out, r#"    <edges>"#)?;
+        for (i, edge) in self.edges.iter().enumerate() {
+            writeln!(
+                out,
+                r#"      <edge id="{}" source="{}" target="{}" label="{}"/>"#,
+                i,
+                xml_escape(&edge.source),
+                xml_escape(&edge.target),
+                edge.kind.name()
+            )?;
+        }
+        writeln!(out, r#"    </edges>"#)?;
+        writeln!(out, r#"  </graph>"#)?;
+        writeln!(out, r#"</gexf>"#)
+    }
+
+    fn write_graphml(&self, out: &mut impl Write) -> Result<(), io::Error> {
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            out,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        writeln!(
+            out,
+            r#"  <key id="type" for="node" attr.name="type" attr.type="string"/>"#
+        )?;
+        writeln!(
+            out,
+            r#"  <key id="label" for="node" attr.name="label" attr.type="string"/>"#
+        )?;
+        writeln!(
+            out,
+            r#"  <key id="relation" for="edge" attr.name="relation" attr.type="string"/>"#
+        )?;
+        writeln!(out, r#"  <graph edgedefault="directed">"#)?;
+        for node in &self.nodes {
+            writeln!(
+                out,
+                r#"    <node id="{}"><data key="type">{}</data><data key="label">{}</data></node>"#,
+                xml_escape(&node.id),
+                node.kind.name(),
+                xml_escape(&node.label)
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                out,
+                r#"    <edge source="{}" target="{}"><data key="relation">{}</data></edge>"#,
+                xml_escape(&edge.source),
+                xml_escape(&edge.target),
+                edge.kind.name()
+            )?;
+        }
+        writeln!(out, r#"  </graph>"#)?;
+        writeln!(out, r#"</graphml>"#)
+    }
+
+    /// See <https://jsongraphformat.info/>
+    fn write_json_graph(&self, out: &mut impl Write, pretty: bool) -> Result<(), io::Error> {
+        let nodes: Map<String, Value> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let value = json!({
+                    "label": node.label,
+                    "metadata": { "type": node.kind.name() },
+                });
+                (node.id.clone(), value)
+            })
+            .collect();
+        let edges: Vec<Value> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                json!({
+                    "source": edge.source,
+                    "target": edge.target,
+                    "relation": edge.kind.name(),
+                })
+            })
+            .collect();
+        let graph = json!({
+            "graph": {
+                "directed": true,
+                "nodes": nodes,
+                "edges": edges,
+            }
+        });
+        if pretty {
+            serde_json::to_writer_pretty(&mut *out, &graph)?;
+        } else {
+            serde_json::to_writer(&mut *out, &graph)?;
+        }
+        writeln!(out)
+    }
+}
+
+/// User and team IDs are assigned independently, so the kind is part of the node ID
+fn publisher_id(publisher: &PublisherData) -> String {
+    match publisher.kind {
+        PublisherKind::user => format!("user:{}", publisher.login),
+        PublisherKind::team => format!("team:{}", publisher.login),
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> Graph {
+        let node = |id: &str, kind| Node {
+            id: id.to_owned(),
+            label: id.to_owned(),
+            kind,
+        };
+        let edge = |source: &str, target: &str, kind| Edge {
+            source: source.to_owned(),
+            target: target.to_owned(),
+            kind,
+        };
+        Graph {
+            nodes: vec![
+                node("app@0.1.0", NodeKind::Workspace),
+                node("serde@1.0.0", NodeKind::Crate),
+                node("user:dtolnay", NodeKind::Publisher),
+            ],
+            edges: vec![
+                edge("app@0.1.0", "serde@1.0.0", EdgeKind::DependsOn),
+                edge("serde@1.0.0", "user:dtolnay", EdgeKind::OwnedBy),
+            ],
+        }
+    }
+
+    fn render(format: ExportGraphFormat) -> String {
+        let mut out = Vec::new();
+        graph().write(format, &mut out, false).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn dot() {
+        let dot = render(ExportGraphFormat::Dot);
+        assert!(dot.contains("\"app@0.1.0\" [label=\"app@0.1.0\", shape=house];"));
+        assert!(dot.contains("\"serde@1.0.0\" -> \"user:dtolnay\" [style=dashed];"));
+    }
+
+    #[test]
+    fn xml_formats() {
+        let gexf = render(ExportGraphFormat::Gexf);
+        assert!(gexf.contains(r#"<attvalue for="type" value="publisher"/>"#));
+        assert!(gexf.contains(
+            r#"<edge id="0" source="app@0.1.0" target="serde@1.0.0" label="depends_on"/>"#
+        ));
+        let graphml = render(ExportGraphFormat::Graphml);
+        assert!(graphml.contains(r#"<node id="serde@1.0.0"><data key="type">crate</data>"#));
+        assert!(graphml.contains(r#"<data key="relation">owned_by</data>"#));
+        assert_eq!(xml_escape(r#"<"a" & b>"#), "&lt;&quot;a&quot; &amp; b&gt;");
+    }
+
+    #[test]
+    fn json_graph() {
+        let value: Value = serde_json::from_str(&render(ExportGraphFormat::JsonGraph)).unwrap();
+        let graph = &value["graph"];
+        assert_eq!(graph["directed"], true);
+        assert_eq!(
+            graph["nodes"]["user:dtolnay"]["metadata"]["type"],
+            "publisher"
+        );
+        assert_eq!(graph["edges"][1]["relation"], "owned_by");
+    }
+}
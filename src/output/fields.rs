@@ -0,0 +1,234 @@
+//! Tabular output restricted to the columns chosen with `--fields`
+
+use crate::common::{PkgSource, SourcedPackage};
+use crate::publishers::{PublisherData, PublisherKind};
+use serde_json::{Map, Value};
+use std::{
+    path::Path,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Field {
+    /// Name of the crate
+    Crate,
+    /// Login of a user or team that can publish the crate
+    Publisher,
+    /// Whether the publisher is a `user` or a `team`
+    Kind,
+    /// Versions of the crate in the dependency graph
+    Version,
+    /// Repository URL declared by the crate
+    Repository,
+    /// Number of users and teams that can publish the crate
+    OwnerCount,
+}
+
+impl Field {
+    const NAMES: &'static [(&'static str, Field)] = &[
+        ("crate", Field::Crate),
+        ("publisher", Field::Publisher),
+        ("kind", Field::Kind),
+        ("version", Field::Version),
+        ("repository", Field::Repository),
+        ("owner-count", Field::OwnerCount),
+    ];
+
+    fn name(self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|(_, field)| *field == self)
+            .map(|(name, _)| *name)
+            .unwrap()
+    }
+}
+
+/// The comma separated argument of `--fields`, in display order
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FieldList(pub Vec<Field>);
+
+impl FromStr for FieldList {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(|name| {
+                let name = name.trim();
+                match Field::NAMES.iter().find(|(known, _)| *known == name) {
+                    Some((_, field)) => Ok(*field),
+                    None => {
+                        let names: Vec<&str> = Field::NAMES.iter().map(|(name, _)| *name).collect();
+                        Err(format!(
+                            "unknown field '{}', expected one of: {}",
+                            name,
+                            names.join(", ")
+                        ))
+                    }
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(FieldList)
+    }
+}
+
+/// One line of output: a crate together with one of its publishers.
+/// Crates without publishers get a single row with no publisher.
+pub struct Row {
+    crate_name: String,
+    publisher: Option<(String, PublisherKind, u64)>,
+    versions: Vec<String>,
+    repository: Option<String>,
+    owner_count: usize,
+}
+
+impl Row {
+    /// The publisher login, which `publishers` sorts its rows by
+    pub fn publisher_login(&self) -> Option<&str> {
+        self.publisher.as_ref().map(|(login, _, _)| login.as_str())
+    }
+
+    pub fn publisher_kind(&self) -> Option<PublisherKind> {
+        self.publisher.as_ref().map(|(_, kind, _)| *kind)
+    }
+
+    /// The numeric crates.io ID of the publisher, which unlike the login never changes
+    pub fn publisher_id(&self) -> Option<u64> {
+        self.publisher.as_ref().map(|(_, _, id)| *id)
+    }
+
+    fn value(&self, field: Field) -> Value {
+        match field {
+            Field::Crate => Value::from(self.crate_name.as_str()),
+            Field::Publisher => Value::from(self.publisher_login()),
+            Field::Kind => Value::from(self.publisher.as_ref().map(|(_, kind, _)| match kind {
+                PublisherKind::team => "team",
+                PublisherKind::user => "user",
+            })),
+            Field::Version => Value::from(self.versions.join(", ")),
+            Field::Repository => Value::from(self.repository.as_deref()),
+            Field::OwnerCount => Value::from(self.owner_count),
+        }
+    }
+}
+
+/// Expands the crates and their publishers into rows, keeping the order of `crates`
+pub fn rows(crates: &[(String, Vec<PublisherData>)], dependencies: &[SourcedPackage]) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for (crate_name, publishers) in crates {
+        let packages: Vec<_> = dependencies
+            .iter()
+            .filter(|dep| dep.source == PkgSource::CratesIo && &dep.package.name == crate_name)
+            .map(|dep| &dep.package)
+            .collect();
+        let mut versions: Vec<String> = packages.iter().map(|p| p.version.to_string()).collect();
+        versions.sort_unstable();
+        versions.dedup();
+        let repository = packages.iter().find_map(|p| p.repository.clone());
+        let row = |publisher| Row {
+            crate_name: crate_name.clone(),
+            publisher,
+            versions: versions.clone(),
+            repository: repository.clone(),
+            owner_count: publishers.len(),
+        };
+        if publishers.is_empty() {
+            rows.push(row(None));
+        }
+        for publisher in publishers {
+            rows.push(row(Some((
+                publisher.login.clone(),
+                publisher.kind,
+                publisher.id,
+            ))));
+        }
+    }
+    rows
+}
+
+/// Prints `#` comment lines describing the output of [`print_text`], requested by `--prepend-headers`.
+/// The analysis date is left out with `--diffable`, since it would differ on every run.
+pub fn print_headers(
+    fields: &FieldList,
+    workspace_root: &Path,
+    cache_age: Option<Duration>,
+    diffable: bool,
+) {
+    println!(
+        "# Generated by cargo-supply-chain v{}",
+        env!("CARGO_PKG_VERSION")
+    );
+    println!("# Workspace: {}", workspace_root.display());
+    if !diffable {
+        println!(
+            "# Analysis date: {}",
+            humantime::format_rfc3339_seconds(SystemTime::now())
+        );
+    }
+    match cache_age {
+        Some(age) => println!("# Cache age: {}", indicatif::HumanDuration(age)),
+        None => println!("# Cache age: no cache"),
+    }
+    let names: Vec<&str> = fields.0.iter().map(|field| field.name()).collect();
+    println!("# Columns: {}", names.join("\t"));
+}
+
+/// Prints the chosen fields of each row separated by tabs, skipping repeated lines
+pub fn print_text(fields: &FieldList, rows: &[Row]) {
+    let mut previous = None;
+    for row in rows {
+        let line: Vec<String> = fields
+            .0
+            .iter()
+            .map(|&field| match row.value(field) {
+                Value::String(text) => text,
+                Value::Null => String::new(),
+                other => other.to_string(),
+            })
+            .collect();
+        let line = line.join("\t");
+        if previous.as_ref() != Some(&line) {
+            println!("{}", line);
+            previous = Some(line);
+        }
+    }
+}
+
+/// Prints a JSON array with an object per row, containing only the chosen fields
+pub fn print_json(fields: &FieldList, rows: &[Row], diffable: bool) -> Result<(), anyhow::Error> {
+    let mut objects: Vec<Map<String, Value>> = Vec::new();
+    for row in rows {
+        let object: Map<String, Value> = fields
+            .0
+            .iter()
+            .map(|&field| (field.name().to_owned(), row.value(field)))
+            .collect();
+        if objects.last() != Some(&object) {
+            objects.push(object);
+        }
+    }
+    let stdout = std::io::stdout();
+    let handle = stdout.lock();
+    if diffable {
+        serde_json::to_writer_pretty(handle, &objects)?;
+    } else {
+        serde_json::to_writer(handle, &objects)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_field_list() {
+        assert_eq!(
+            "crate,owner-count, kind".parse::<FieldList>().unwrap(),
+            FieldList(vec![Field::Crate, Field::OwnerCount, Field::Kind])
+        );
+        let error = "crate,owners".parse::<FieldList>().unwrap_err();
+        assert!(error.contains("unknown field 'owners'"));
+        assert!(error.contains("owner-count"));
+    }
+}
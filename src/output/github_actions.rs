@@ -0,0 +1,131 @@
+//! Workflow commands that GitHub Actions turns into annotations on the run summary,
+//! see <https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions>
+
+use crate::common::{separated_list, PkgSource, SourcedPackage};
+use crate::publishers::{PublisherData, PublisherKind};
+use std::env;
+
+/// Crates that can be published by more than this many users and teams get a notice
+const MANY_PUBLISHERS: usize = 5;
+
+/// Prints an `::error` for every crate without publishers and a `::notice` for every crate
+/// with many publishers. All other crates are printed as plain log lines.
+pub fn print_crates(
+    crates: &[(String, Vec<PublisherData>)],
+    dependencies: &[SourcedPackage],
+    separator: &str,
+) {
+    for (crate_name, publishers) in crates {
+        let pretty_publishers: Vec<String> = publishers
+            .iter()
+            .map(|p| match p.kind {
+                PublisherKind::team => format!("team \"{}\"", p.display_safe_login()),
+                PublisherKind::user => p.display_safe_login().into_owned(),
+            })
+            .collect();
+        let publishers_list = separated_list(&pretty_publishers, separator);
+        let file = manifest_declaring(crate_name, dependencies);
+        if publishers.is_empty() {
+            print_command(
+                "error",
+                &file,
+                &format!("Crate '{}' has no publisher on record", crate_name),
+            );
+        } else if publishers.len() > MANY_PUBLISHERS {
+            print_command(
+                "notice",
+                &file,
+                &format!(
+                    "Crate '{}' can be published by {} users and teams: {}",
+                    crate_name,
+                    publishers.len(),
+                    publishers_list
+                ),
+            );
+        } else {
+            println!("{}: {}", crate_name, publishers_list);
+        }
+    }
+}
+
+fn print_command(command: &str, file: &str, message: &str) {
+    println!(
+        "::{} file={}::{}",
+        command,
+        escape_property(file),
+        escape_data(message)
+    );
+}
+
+/// The manifest of a local package that depends on `crate_name` directly,
+/// relative to the current directory so that GitHub can match it to a file in the repository.
+/// Falls back to any local manifest if the crate is only a transitive dependency.
+fn manifest_declaring(crate_name: &str, dependencies: &[SourcedPackage]) -> String {
+    let mut local_packages: Vec<_> = dependencies
+        .iter()
+        .filter(|p| p.source == PkgSource::Local)
+        .map(|p| &p.package)
+        .collect();
+    local_packages.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    let package = local_packages
+        .iter()
+        .find(|package| {
+            package
+                .dependencies
+                .iter()
+                .any(|dep| dep.name == crate_name)
+        })
+        .or_else(|| local_packages.first());
+    let Some(package) = package else {
+        return "Cargo.toml".to_owned();
+    };
+    let manifest_path = package.manifest_path.as_std_path();
+    env::current_dir()
+        .ok()
+        .and_then(|dir| manifest_path.strip_prefix(dir).ok().map(ToOwned::to_owned))
+        .unwrap_or_else(|| manifest_path.to_owned())
+        .display()
+        .to_string()
+}
+
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(property: &str) -> String {
+    escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes() {
+        // (input, as data, as a property)
+        let cases = [
+            ("serde", "serde", "serde"),
+            ("100%", "100%25", "100%25"),
+            ("a\r\nb", "a%0D%0Ab", "a%0D%0Ab"),
+            (
+                "crates/a b/Cargo.toml",
+                "crates/a b/Cargo.toml",
+                "crates/a b/Cargo.toml",
+            ),
+            ("C:\\Cargo.toml", "C:\\Cargo.toml", "C%3A\\Cargo.toml"),
+            ("serde, syn", "serde, syn", "serde%2C syn"),
+            ("::error::", "::error::", "%3A%3Aerror%3A%3A"),
+            // Escapes are not escaped twice
+            ("%0A", "%250A", "%250A"),
+            ("%3A,:", "%253A,:", "%253A%2C%3A"),
+        ];
+        for &(input, data, property) in cases.iter() {
+            assert_eq!(escape_data(input), data, "data {:?}", input);
+            assert_eq!(escape_property(input), property, "property {:?}", input);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! A collapsible Markdown table of the crates and their publishers, ready to be posted
+//! as a comment on a pull request, e.g. with `gh pr comment --body-file`
+
+use crate::common::separated_list;
+use crate::downloads::DownloadCount;
+use crate::publishers::{PublisherData, PublisherKind};
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::Duration,
+};
+
+pub fn print_crates(
+    crates: &[(String, Vec<PublisherData>)],
+    download_counts: Option<&HashMap<String, DownloadCount>>,
+    cache_age: Option<Duration>,
+    since: Option<&str>,
+    separator: &str,
+) {
+    print!(
+        "{}",
+        render(crates, download_counts, cache_age, since, separator)
+    );
+}
+
+/// A `<details>` block whose `<summary>` has the totals, so that a long table stays folded.
+/// With `since`, i.e. `--since-commit`, the summary starts with a warning sign
+/// if the crates added since then can be published by anyone.
+/// With `download_counts`, i.e. `--show-download-count`, the table has a column for them.
+fn render(
+    crates: &[(String, Vec<PublisherData>)],
+    download_counts: Option<&HashMap<String, DownloadCount>>,
+    cache_age: Option<Duration>,
+    since: Option<&str>,
+    separator: &str,
+) -> String {
+    let publishers: BTreeSet<(PublisherKind, &str)> = crates
+        .iter()
+        .flat_map(|(_, publishers)| publishers)
+        .map(|p| (p.kind, p.login.as_str()))
+        .collect();
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    let mut summary = format!("{} crates.io crate{}", crates.len(), plural(crates.len()));
+    if let Some(git_ref) = since {
+        summary.push_str(&format!(" added since <code>{}</code>", escape(git_ref)));
+    }
+    summary.push_str(&format!(
+        ", {} publisher{}",
+        publishers.len(),
+        plural(publishers.len())
+    ));
+    if since.is_some() && !publishers.is_empty() {
+        summary.insert_str(0, "\u{26a0}\u{fe0f} ");
+    }
+
+    let mut out = String::new();
+    out.push_str("<details>\n");
+    out.push_str(&format!("<summary>{}</summary>\n\n", summary));
+    if !crates.is_empty() {
+        if download_counts.is_some() {
+            out.push_str("| Crate | Publishers | Downloads |\n");
+            out.push_str("| --- | --- | --- |\n");
+        } else {
+            out.push_str("| Crate | Publishers |\n");
+            out.push_str("| --- | --- |\n");
+        }
+        for (crate_name, publishers) in crates {
+            let pretty_publishers: Vec<String> = publishers
+                .iter()
+                .map(|p| match p.kind {
+                    PublisherKind::team => format!("team \"{}\"", p.display_safe_login()),
+                    PublisherKind::user => p.display_safe_login().into_owned(),
+                })
+                .collect();
+            let publishers_list = if publishers.is_empty() {
+                "*none on record*".to_owned()
+            } else {
+                escape(&separated_list(&pretty_publishers, separator))
+            };
+            out.push_str(&format!(
+                "| [{}](https://crates.io/crates/{}) | {} |",
+                crate_name, crate_name, publishers_list
+            ));
+            if let Some(download_counts) = download_counts {
+                let downloads = download_counts
+                    .get(crate_name)
+                    .map_or_else(|| "*unknown*".to_owned(), DownloadCount::summary);
+                out.push_str(&format!(" {} |", downloads));
+            }
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    match cache_age {
+        Some(age) => out.push_str(&format!(
+            "Publisher data from the crates.io database dump, {} old.\n",
+            indicatif::HumanDuration(age)
+        )),
+        None => out.push_str("Publisher data from the live crates.io API.\n"),
+    }
+    out.push_str("</details>\n");
+    out
+}
+
+/// Logins are not Markdown, so they must neither end a table cell nor open an HTML tag
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::publishers::publisher;
+
+    #[test]
+    fn render_table() {
+        let crates = vec![
+            (
+                "serde".to_owned(),
+                vec![
+                    publisher(2, "github:serde-rs:publish", PublisherKind::team),
+                    publisher(1, "dtolnay", PublisherKind::user),
+                ],
+            ),
+            ("orphan".to_owned(), Vec::new()),
+        ];
+        let age = Duration::from_secs(3 * 3600);
+        assert_eq!(
+            render(&crates, None, Some(age), None, ", "),
+            "<details>\n\
+            <summary>2 crates.io crates, 2 publishers</summary>\n\n\
+            | Crate | Publishers |\n\
+            | --- | --- |\n\
+            | [serde](https://crates.io/crates/serde) | team \"github:serde-rs:publish\", dtolnay |\n\
+            | [orphan](https://crates.io/crates/orphan) | *none on record* |\n\n\
+            Publisher data from the crates.io database dump, 3 hours old.\n\
+            </details>\n"
+        );
+        let summary = render(&crates[..1], None, None, Some("origin/main"), " | ");
+        assert!(summary.starts_with(
+            "<details>\n<summary>\u{26a0}\u{fe0f} 1 crates.io crate added since <code>origin/main</code>"
+        ));
+        assert!(summary.contains("publish\" \\| dtolnay"));
+        assert!(summary.contains("live crates.io API"));
+        assert!(!render(&[], None, None, Some("HEAD~1"), ", ").contains('\u{26a0}'));
+    }
+
+    #[test]
+    fn download_count_column() {
+        let crates = vec![
+            (
+                "serde".to_owned(),
+                vec![publisher(1, "dtolnay", PublisherKind::user)],
+            ),
+            ("orphan".to_owned(), Vec::new()),
+        ];
+        let counts: HashMap<String, DownloadCount> = [(
+            "serde".to_owned(),
+            DownloadCount {
+                total: 250_000_000,
+                recent: Some(30_000_000),
+            },
+        )]
+        .iter()
+        .cloned()
+        .collect();
+        let table = render(&crates, Some(&counts), None, None, ", ");
+        assert!(table.contains("| Crate | Publishers | Downloads |\n| --- | --- | --- |\n"));
+        assert!(table.contains("| dtolnay | 250M total, 30M recent |\n"));
+        assert!(table.contains("| *none on record* | *unknown* |\n"));
+    }
+
+    #[test]
+    fn escape_markup() {
+        assert_eq!(escape("<img src=x>|a&b"), "&lt;img src=x&gt;\\|a&amp;b");
+    }
+}
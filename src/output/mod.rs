@@ -0,0 +1,406 @@
+//! Machine-oriented output formats, as opposed to the plain text printed by default
+
+pub mod align;
+pub mod export_graph;
+pub mod fields;
+pub mod github_actions;
+pub mod github_markdown;
+pub mod template;
+
+use anyhow::bail;
+use std::{
+    env,
+    io::{self, IsTerminal},
+    str::FromStr,
+};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// Workflow commands that GitHub Actions displays as annotations
+    GithubActions,
+    /// A collapsible Markdown table, e.g. for a comment on a pull request
+    GithubMarkdown,
+    /// A JSON document, described by `--print-schema`
+    Json,
+}
+
+impl OutputFormat {
+    const NAMES: &'static [(&'static str, OutputFormat)] = &[
+        ("text", OutputFormat::Text),
+        ("github-actions", OutputFormat::GithubActions),
+        ("github-markdown", OutputFormat::GithubMarkdown),
+        ("json", OutputFormat::Json),
+    ];
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|(_, format)| *format == self)
+            .map(|(name, _)| *name)
+            .unwrap()
+    }
+
+    /// Sets the format of all commands that do not get `--format`, e.g. in a CI environment
+    pub const ENV_VAR: &'static str = "CARGO_SUPPLY_CHAIN_FORMAT";
+
+    /// `flag` if it was given, otherwise the format set by `CARGO_SUPPLY_CHAIN_FORMAT`,
+    /// otherwise the one `configured` in the config file.
+    /// A valid format that is not one of the `supported` ones is ignored, so that the variable
+    /// can be set for all commands even though not every command supports every format.
+    pub fn flag_or_env(
+        flag: Option<Self>,
+        configured: Option<Self>,
+        supported: &[Self],
+    ) -> Result<Option<Self>, anyhow::Error> {
+        Self::select(flag, env::var(Self::ENV_VAR), configured, supported)
+    }
+
+    /// [`flag_or_env`](Self::flag_or_env) with the value of `CARGO_SUPPLY_CHAIN_FORMAT`
+    fn select(
+        flag: Option<Self>,
+        env_value: Result<String, env::VarError>,
+        configured: Option<Self>,
+        supported: &[Self],
+    ) -> Result<Option<Self>, anyhow::Error> {
+        if flag.is_some() {
+            return Ok(flag);
+        }
+        let from_env = match env_value {
+            Ok(value) if !value.is_empty() => match value.parse() {
+                Ok(format) => Some(format),
+                Err(error) => bail!("{} in {}", error, Self::ENV_VAR),
+            },
+            Ok(_) | Err(env::VarError::NotPresent) => None,
+            Err(env::VarError::NotUnicode(_)) => bail!("{} is not valid Unicode", Self::ENV_VAR),
+        };
+        Ok(from_env
+            .or(configured)
+            .filter(|format| supported.contains(format)))
+    }
+
+    /// The format used when none is requested explicitly.
+    /// Inside GitHub Actions this is `github-actions`, otherwise `text`.
+    pub fn detect() -> Self {
+        let is_set = |var| env::var(var).is_ok_and(|value| value == "true");
+        if is_set("CI") && is_set("GITHUB_ACTIONS") {
+            OutputFormat::GithubActions
+        } else {
+            OutputFormat::Text
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_name("format", Self::NAMES, s)
+    }
+}
+
+/// Output formats of the `json` subcommand
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum JsonFormat {
+    /// A single JSON document, written once all crates are processed
+    Json,
+    /// One JSON object per line, written as soon as each crate is processed
+    Ndjson,
+}
+
+impl JsonFormat {
+    const NAMES: &'static [(&'static str, JsonFormat)] =
+        &[("json", JsonFormat::Json), ("ndjson", JsonFormat::Ndjson)];
+}
+
+impl FromStr for JsonFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_name("format", Self::NAMES, s)
+    }
+}
+
+/// Output formats of the `trust-graph` subcommand
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GraphFormat {
+    /// Graphviz DOT, e.g. for rendering with `dot -Tsvg`
+    Dot,
+    /// A Mermaid flowchart, which GitHub renders inside Markdown
+    Mermaid,
+}
+
+impl GraphFormat {
+    const NAMES: &'static [(&'static str, GraphFormat)] =
+        &[("dot", GraphFormat::Dot), ("mermaid", GraphFormat::Mermaid)];
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_name("format", Self::NAMES, s)
+    }
+}
+
+/// Output formats of `json --export-graph`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ExportGraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// GEXF, the native format of Gephi
+    Gexf,
+    /// GraphML, which Neo4j and most graph tools can import
+    Graphml,
+    /// JSON Graph Format
+    JsonGraph,
+}
+
+impl ExportGraphFormat {
+    const NAMES: &'static [(&'static str, ExportGraphFormat)] = &[
+        ("dot", ExportGraphFormat::Dot),
+        ("gexf", ExportGraphFormat::Gexf),
+        ("graphml", ExportGraphFormat::Graphml),
+        ("json-graph", ExportGraphFormat::JsonGraph),
+    ];
+}
+
+impl FromStr for ExportGraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_name("graph format", Self::NAMES, s)
+    }
+}
+
+/// Whether to highlight output with ANSI colors
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ColorMode {
+    /// Only when printing to a terminal, and `NO_COLOR` is not set
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    const NAMES: &'static [(&'static str, ColorMode)] = &[
+        ("auto", ColorMode::Auto),
+        ("always", ColorMode::Always),
+        ("never", ColorMode::Never),
+    ];
+
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+        }
+    }
+}
+
+/// Orders of the crates listed by the `crates` subcommand
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SortKey {
+    /// Alphabetical by crate name
+    Name,
+    /// Fewest publishers first, so that crates with a single maintainer come first
+    Publishers,
+    /// Crates owned by teams first, since anyone added to the team can publish them,
+    /// then the crates that the most users can publish
+    Risk,
+    /// Most downloaded first, so that the crates with the widest impact come first
+    Downloads,
+}
+
+impl SortKey {
+    const NAMES: &'static [(&'static str, SortKey)] = &[
+        ("name", SortKey::Name),
+        ("publishers", SortKey::Publishers),
+        ("risk", SortKey::Risk),
+        ("downloads", SortKey::Downloads),
+    ];
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_name("sort key", Self::NAMES, s)
+    }
+}
+
+/// Shells that `generate-completions` can write a completion script for
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ShellChoice {
+    Bash,
+    Zsh,
+    Fish,
+    Elvish,
+}
+
+impl ShellChoice {
+    const NAMES: &'static [(&'static str, ShellChoice)] = &[
+        ("bash", ShellChoice::Bash),
+        ("zsh", ShellChoice::Zsh),
+        ("fish", ShellChoice::Fish),
+        ("elvish", ShellChoice::Elvish),
+    ];
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|(_, shell)| *shell == self)
+            .map(|(name, _)| *name)
+            .unwrap()
+    }
+}
+
+impl FromStr for ShellChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_name("shell", Self::NAMES, s)
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_name("color mode", Self::NAMES, s)
+    }
+}
+
+/// Crate counts at which publishers are highlighted in yellow and in red
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ColorThreshold {
+    pub yellow: usize,
+    pub red: usize,
+}
+
+impl Default for ColorThreshold {
+    fn default() -> Self {
+        ColorThreshold { yellow: 3, red: 10 }
+    }
+}
+
+impl ColorThreshold {
+    /// Wraps `text` in the color for a publisher of `crate_count` crates
+    pub fn paint(&self, text: &str, crate_count: usize) -> String {
+        if crate_count >= self.red {
+            format!("\x1b[31m{}\x1b[0m", text)
+        } else if crate_count >= self.yellow {
+            format!("\x1b[33m{}\x1b[0m", text)
+        } else {
+            text.to_owned()
+        }
+    }
+}
+
+impl FromStr for ColorThreshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected two numbers such as '3,10', got '{}'", s);
+        let (yellow, red) = s.split_once(',').ok_or_else(invalid)?;
+        let yellow: usize = yellow.trim().parse().map_err(|_| invalid())?;
+        let red: usize = red.trim().parse().map_err(|_| invalid())?;
+        if yellow > red {
+            return Err(format!(
+                "the yellow threshold {} is greater than the red threshold {}",
+                yellow, red
+            ));
+        }
+        Ok(ColorThreshold { yellow, red })
+    }
+}
+
+/// Prints the values of `--format` of a subcommand with their descriptions, for `--help-formats`
+pub fn print_formats(formats: &[(&str, &str)]) {
+    print!("{}", format_list(formats));
+}
+
+fn format_list(formats: &[(&str, &str)]) -> String {
+    let width = formats
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    formats
+        .iter()
+        .map(|(name, description)| format!("{:width$}  {}\n", name, description, width = width))
+        .collect()
+}
+
+fn from_name<T: Copy>(what: &str, names: &[(&str, T)], s: &str) -> Result<T, String> {
+    match names.iter().find(|(name, _)| *name == s) {
+        Some((_, value)) => Ok(*value),
+        None => {
+            let names: Vec<&str> = names.iter().map(|(name, _)| *name).collect();
+            Err(format!(
+                "unknown {} '{}', expected one of: {}",
+                what,
+                s,
+                names.join(", ")
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_env_or_config() {
+        use OutputFormat::*;
+        let supported = [Text, Json];
+        let var = |value: &str| Ok(value.to_owned());
+        let select = |flag, env_value, configured| {
+            OutputFormat::select(flag, env_value, configured, &supported)
+        };
+
+        // The flag takes precedence, even over an invalid variable
+        assert_eq!(
+            select(Some(Text), var("json"), Some(Json)).unwrap(),
+            Some(Text)
+        );
+        assert_eq!(
+            select(Some(Json), var("markdown"), None).unwrap(),
+            Some(Json)
+        );
+        // Then the variable, then the config file
+        assert_eq!(select(None, var("json"), Some(Text)).unwrap(), Some(Json));
+        assert_eq!(select(None, var("text"), None).unwrap(), Some(Text));
+        assert_eq!(
+            select(None, Err(env::VarError::NotPresent), Some(Json)).unwrap(),
+            Some(Json)
+        );
+        assert_eq!(
+            select(None, Err(env::VarError::NotPresent), None).unwrap(),
+            None
+        );
+        // An empty variable counts as unset
+        assert_eq!(select(None, var(""), Some(Json)).unwrap(), Some(Json));
+        assert_eq!(select(None, var(""), None).unwrap(), None);
+        // A format the command does not support is ignored, but not a misspelled one
+        assert_eq!(select(None, var("github-actions"), None).unwrap(), None);
+        assert_eq!(
+            select(None, var("github-markdown"), Some(Json)).unwrap(),
+            None
+        );
+        assert_eq!(
+            select(None, var("text"), Some(GithubActions)).unwrap(),
+            Some(Text)
+        );
+        assert_eq!(
+            select(None, Err(env::VarError::NotPresent), Some(GithubActions)).unwrap(),
+            None
+        );
+        assert!(select(None, var("markdown"), Some(Json)).is_err());
+        assert!(select(None, var("JSON"), None).is_err());
+        let not_unicode = env::VarError::NotUnicode(std::ffi::OsString::from("json"));
+        assert!(select(None, Err(not_unicode), None).is_err());
+    }
+}
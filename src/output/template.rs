@@ -0,0 +1,138 @@
+//! Custom text output from a user-provided [Tera](https://keats.github.io/tera/) template,
+//! requested with `json --output-template`
+
+use crate::publishers::{PublisherData, PublisherKind};
+use crate::summary::PublisherSummary;
+use anyhow::Context as _;
+use serde::Serialize;
+use std::{collections::BTreeMap, time::Duration};
+use tera::{Context, Tera};
+
+/// An example template showing what the context provides, printed by `json --print-template`
+pub const DEFAULT_TEMPLATE: &str = include_str!("default.txt");
+
+#[derive(Serialize)]
+struct TemplateCrate<'a> {
+    name: &'a str,
+    publishers: &'a [PublisherData],
+}
+
+/// A publisher along with the names of the crates it can publish
+#[derive(Serialize)]
+struct TemplatePublisher<'a> {
+    #[serde(flatten)]
+    publisher: &'a PublisherData,
+    crates: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct TemplateMetadata {
+    version: &'static str,
+    cache_age_seconds: Option<u64>,
+}
+
+/// Renders `template`, named after the file it was read from.
+///
+/// The fields of `base`, the regular JSON output, are available at the top level of the context,
+/// along with `crates`, `users`, `teams`, `stats` and `metadata`.
+pub fn render(
+    name: &str,
+    template: &str,
+    base: &impl Serialize,
+    crates: &BTreeMap<String, Vec<PublisherData>>,
+    cache_age: Option<Duration>,
+) -> Result<String, anyhow::Error> {
+    let mut context = Context::from_serialize(base)?;
+    let crate_list: Vec<TemplateCrate> = crates
+        .iter()
+        .map(|(name, publishers)| TemplateCrate { name, publishers })
+        .collect();
+    context.insert("crates", &crate_list);
+    context.insert("users", &publishers_of_kind(crates, PublisherKind::user));
+    context.insert("teams", &publishers_of_kind(crates, PublisherKind::team));
+    context.insert(
+        "stats",
+        &PublisherSummary::new(crates, &BTreeMap::new(), false),
+    );
+    context.insert(
+        "metadata",
+        &TemplateMetadata {
+            version: env!("CARGO_PKG_VERSION"),
+            cache_age_seconds: cache_age.map(|age| age.as_secs()),
+        },
+    );
+
+    // Tera reports the line and column of syntax errors in the error chain
+    let mut tera = Tera::default();
+    tera.add_raw_template(name, template)
+        .with_context(|| format!("Invalid template {}", name))?;
+    tera.render(name, &context)
+        .with_context(|| format!("Failed to render template {}", name))
+}
+
+/// Sorted by login, each with the crates sorted by name
+fn publishers_of_kind(
+    crates: &BTreeMap<String, Vec<PublisherData>>,
+    kind: PublisherKind,
+) -> Vec<TemplatePublisher<'_>> {
+    let mut publishers: BTreeMap<&str, TemplatePublisher> = BTreeMap::new();
+    for (crate_name, crate_publishers) in crates {
+        for publisher in crate_publishers.iter().filter(|p| p.kind == kind) {
+            publishers
+                .entry(&publisher.login)
+                .or_insert_with(|| TemplatePublisher {
+                    publisher,
+                    crates: Vec::new(),
+                })
+                .crates
+                .push(crate_name);
+        }
+    }
+    publishers.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::publishers::publisher;
+    use serde_json::json;
+
+    fn crates() -> BTreeMap<String, Vec<PublisherData>> {
+        let mut crates = BTreeMap::new();
+        crates.insert(
+            "serde".to_owned(),
+            vec![
+                publisher(3618, "dtolnay", PublisherKind::user),
+                publisher(7, "github:serde-rs:owners", PublisherKind::team),
+            ],
+        );
+        crates.insert(
+            "syn".to_owned(),
+            vec![publisher(3618, "dtolnay", PublisherKind::user)],
+        );
+        crates
+    }
+
+    #[test]
+    fn default_template() {
+        let base = json!({ "not_audited": { "local_crates": ["app"] } });
+        let text = render("default.txt", DEFAULT_TEMPLATE, &base, &crates(), None).unwrap();
+        assert!(text.contains("2 crates.io crates can be published by 2 users and teams."));
+        assert!(text.contains("\nserde: dtolnay, github:serde-rs:owners\n"));
+        assert!(text.contains("\ndtolnay: serde, syn\n"));
+        assert!(text.contains("\ngithub:serde-rs:owners: serde\n"));
+        assert!(text.contains("from the local filesystem: app"));
+    }
+
+    #[test]
+    fn template_errors() {
+        let base = json!({});
+        let error = render("bad.txt", "ok\n{% for %}", &base, &crates(), None).unwrap_err();
+        let message = format!("{:#}", error);
+        assert!(message.starts_with("Invalid template bad.txt"));
+        assert!(message.contains("2:"), "no line number in {}", message);
+
+        let error = render("missing.txt", "{{ nope }}", &base, &crates(), None).unwrap_err();
+        assert!(format!("{:#}", error).starts_with("Failed to render template missing.txt"));
+    }
+}
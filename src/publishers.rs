@@ -1,16 +1,22 @@
-use crate::api_client::RateLimitedClient;
-use crate::crates_cache::{CacheState, CratesCache};
+use crate::api_client::{RateLimitedClient, Redacted};
+use crate::cli::{MetadataArgs, OwnerCountArgs, QueryCommandArgs};
+use crate::crates_cache::{CacheState, CacheTable, CratesCache};
+use crate::git::crate_names_from_lockfile_at_ref;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
-    collections::BTreeMap,
-    io::{self, ErrorKind},
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashSet},
+    io::{self, ErrorKind, Write},
+    thread,
     time::Duration,
 };
+use tracing::{debug, info, warn};
 
 #[cfg(test)]
 use schemars::JsonSchema;
 
-use crate::common::{crate_names_from_source, PkgSource, SourcedPackage};
+use crate::common::{crate_names_from_source, workspace_root, PkgSource, SourcedPackage};
 
 #[derive(Deserialize)]
 struct UsersResponse {
@@ -36,6 +42,75 @@ pub struct PublisherData {
     pub name: Option<String>,
     /// Avatar image URL
     pub avatar: Option<String>,
+    /// Email address. Only present with `--include-email` when using the live crates.io API,
+    /// never when using the cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+impl PublisherData {
+    /// The login for printing to a terminal. Logins can contain control characters,
+    /// which could e.g. erase lines printed earlier, so those are removed.
+    pub fn display_safe_login(&self) -> Cow<'_, str> {
+        strip_control_chars(&self.login)
+    }
+
+    /// The display name for printing to a terminal, see [`display_safe_login`](Self::display_safe_login)
+    pub fn display_safe_name(&self) -> Option<Cow<'_, str>> {
+        self.name.as_deref().map(strip_control_chars)
+    }
+
+    /// Replaces the login with an opaque ID for `--anonymize`, e.g. `user_3f0c85a1d2b7e694`,
+    /// and drops the other details that identify the publisher. Returns the real login.
+    ///
+    /// The ID is derived from a keyed hash of the login, so it is the same in every report
+    /// made with `key`, but cannot be recomputed from the public logins without it.
+    /// It keeps 64 bits of the hash, like the numeric ID, so that publishers are not
+    /// merged by a collision in the maps keyed by login.
+    pub fn anonymize(&mut self, key: &[u8]) -> String {
+        let digest = hmac_sha256(key, self.login.as_bytes());
+        let mut id_bytes = [0; 8];
+        id_bytes.copy_from_slice(&digest[..8]);
+        let hex = format!("{:016x}", u64::from_be_bytes(id_bytes));
+        let kind = match self.kind {
+            PublisherKind::user => "user",
+            PublisherKind::team => "team",
+        };
+        self.id = u64::from_be_bytes(id_bytes);
+        self.name = None;
+        self.avatar = None;
+        self.email = None;
+        std::mem::replace(&mut self.login, format!("{}_{}", kind, hex))
+    }
+}
+
+/// HMAC-SHA256 as in RFC 2104
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let padded = |pad: u8| block.map(|byte| byte ^ pad);
+    let inner = Sha256::new()
+        .chain_update(padded(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(padded(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+fn strip_control_chars(text: &str) -> Cow<'_, str> {
+    if text.chars().any(char::is_control) {
+        Cow::Owned(text.chars().filter(|c| !c.is_control()).collect())
+    } else {
+        Cow::Borrowed(text)
+    }
 }
 
 impl PartialEq for PublisherData {
@@ -69,6 +144,23 @@ pub enum PublisherKind {
     user,
 }
 
+impl std::str::FromStr for PublisherKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(PublisherKind::user),
+            "team" => Ok(PublisherKind::team),
+            _ => Err(format!("unknown kind '{}', expected one of: user, team", s)),
+        }
+    }
+}
+
+/// Returns the GitHub organization of a team login such as `github:tokio-rs:mio-core`
+pub fn extract_github_org(login: &str) -> Option<&str> {
+    login.strip_prefix("github:")?.split(':').next()
+}
+
 pub fn publisher_users(
     client: &mut RateLimitedClient,
     crate_name: &str,
@@ -94,6 +186,7 @@ fn get_with_retry(
     client: &mut RateLimitedClient,
     attempts: u8,
 ) -> Result<ureq::Response, io::Error> {
+    debug!("Fetching {}", url);
     let mut resp = client
         .get(url)
         .call()
@@ -102,7 +195,8 @@ fn get_with_retry(
     let mut count = 1;
     let mut wait = 5;
     while resp.status() != 200 && count <= attempts {
-        eprintln!(
+        // A warning, so that the wait does not look like a hang
+        warn!(
             "Failed retrieving {:?}, trying again in {} seconds, attempt {}/{}",
             url, wait, count, attempts
         );
@@ -120,9 +214,23 @@ fn get_with_retry(
     Ok(resp)
 }
 
+pub const OFFLINE_NO_CACHE: &str =
+    "Cache is stale or missing; cannot fetch live data in --offline mode. \
+Run 'cargo supply-chain update' on a networked machine first.";
+
+/// The cache tables needed to look up the publishers of a crate
+pub const PUBLISHER_TABLES: &[CacheTable] = &[
+    CacheTable::Crates,
+    CacheTable::CrateOwners,
+    CacheTable::Users,
+    CacheTable::Teams,
+];
+
 pub fn fetch_owners_of_crates(
     dependencies: &[SourcedPackage],
-    max_age: Duration,
+    published_members: &BTreeSet<String>,
+    metadata_args: &MetadataArgs,
+    args: &QueryCommandArgs,
 ) -> Result<
     (
         BTreeMap<String, Vec<PublisherData>>,
@@ -130,66 +238,484 @@ pub fn fetch_owners_of_crates(
     ),
     io::Error,
 > {
-    let crates_io_names = crate_names_from_source(dependencies, PkgSource::CratesIo);
-    let mut client = RateLimitedClient::new();
+    let mut users: BTreeMap<String, Vec<PublisherData>> = BTreeMap::new();
+    let mut teams: BTreeMap<String, Vec<PublisherData>> = BTreeMap::new();
+    // The crates that were not fetched have been reported already
+    fetch_owners_of_crates_with(
+        dependencies,
+        published_members,
+        metadata_args,
+        args,
+        |crate_name, pub_users, pub_teams| {
+            users.insert(crate_name.to_owned(), pub_users);
+            teams.insert(crate_name.to_owned(), pub_teams);
+            Ok(())
+        },
+    )?;
+    Ok((users, teams))
+}
+
+/// The names of the crates whose owners are looked up: the crates.io crates among
+/// `dependencies` and `published_members`, narrowed down by `--crate-filter-regex`
+/// and `--since-commit`. `metadata_args` locate the `Cargo.lock` that `--since-commit` reads
+pub fn crates_to_fetch(
+    dependencies: &[SourcedPackage],
+    published_members: &BTreeSet<String>,
+    metadata_args: &MetadataArgs,
+    args: &QueryCommandArgs,
+) -> Result<Vec<String>, io::Error> {
+    let mut crates_io_names = crate_names_from_source(dependencies, PkgSource::CratesIo);
+    crates_io_names.extend(published_members.iter().cloned());
+    crates_io_names.sort_unstable();
+    crates_io_names.dedup();
+    if let Some(filter) = &args.crate_filter {
+        crates_io_names.retain(|name| filter.is_match(name));
+    }
+    if let Some(git_ref) = &args.since_commit {
+        let old_names: HashSet<String> = workspace_root(metadata_args.clone())
+            .and_then(|root| crate_names_from_lockfile_at_ref(&root, git_ref))
+            .map_err(|e| io::Error::other(format!("{:#}", e)))?
+            .into_iter()
+            .collect();
+        crates_io_names.retain(|name| !old_names.contains(name));
+        info!(
+            "{} crates.io crates were added since {}",
+            crates_io_names.len(),
+            git_ref
+        );
+    }
+    Ok(crates_io_names)
+}
+
+/// Like [`fetch_owners_of_crates`], but passes the users and teams of each crate
+/// to `on_crate` as soon as they are known instead of collecting them.
+///
+/// Returns the names of the crates that were skipped because of `--no-default-registry-fallback`
+pub fn fetch_owners_of_crates_with(
+    dependencies: &[SourcedPackage],
+    published_members: &BTreeSet<String>,
+    metadata_args: &MetadataArgs,
+    args: &QueryCommandArgs,
+    mut on_crate: impl FnMut(&str, Vec<PublisherData>, Vec<PublisherData>) -> Result<(), io::Error>,
+) -> Result<Vec<String>, io::Error> {
+    let crates_io_names = crates_to_fetch(dependencies, published_members, metadata_args, args)?;
+    let mut client =
+        RateLimitedClient::with_tls(&args.tls)?.with_token(args.crates_io_token.clone());
     let mut cached = CratesCache::new();
-    let using_cache = match cached.expire(max_age) {
-        CacheState::Fresh => true,
+    let max_age = args.cache_max_age();
+    let state = cached.expire(max_age, args.max_age_warning);
+    if let CacheState::FreshButAging(age) = state {
+        warn!(
+            "Cache is {} old. Consider running `cargo supply-chain update` for more accurate results.",
+            indicatif::HumanDuration(age)
+        );
+    }
+    let using_cache = match state {
+        CacheState::Expired | CacheState::Unknown | CacheState::Incompatible(_)
+            if args.offline() =>
+        {
+            return Err(io::Error::other(OFFLINE_NO_CACHE));
+        }
+        CacheState::Fresh | CacheState::FreshButAging(_) => match cached.prefetch(PUBLISHER_TABLES)
+        {
+            Ok(()) => true,
+            Err(_) if args.offline() => {
+                return Err(io::Error::other(OFFLINE_NO_CACHE));
+            }
+            Err(_) => {
+                warn!("The `crates.io` cache is incomplete. Run `cargo supply-chain update` to regenerate it.");
+                false
+            }
+        },
         CacheState::Expired => {
-            eprintln!(
-                "\nIgnoring expired cache, older than {}.",
+            warn!(
+                "Ignoring expired cache, older than {}. Run `cargo supply-chain update` to update it.",
                 // we use humantime rather than indicatif because we take humantime input
                 // and here we simply repeat it back to the user
                 humantime::format_duration(max_age)
             );
-            eprintln!("  Run `cargo supply-chain update` to update it.");
             false
         }
         CacheState::Unknown => {
-            eprintln!("\nThe `crates.io` cache was not found or it is invalid.");
-            eprintln!("  Run `cargo supply-chain update` to generate it.");
+            warn!("The `crates.io` cache was not found or it is invalid. Run `cargo supply-chain update` to generate it.");
+            false
+        }
+        CacheState::Incompatible(_) => {
+            warn!("{}", state.incompatibility().unwrap());
             false
         }
     };
-    let mut users: BTreeMap<String, Vec<PublisherData>> = BTreeMap::new();
-    let mut teams: BTreeMap<String, Vec<PublisherData>> = BTreeMap::new();
+    // Reported once the progress bar is done, so that it does not garble them
+    let mut ownerless_crates = Vec::new();
+    let mut not_fetched = Vec::new();
+    let mut anonymized = BTreeMap::new();
+    let key = if args.anonymize {
+        Some(anonymize_key(args)?)
+    } else {
+        None
+    };
+    let mut on_crate =
+        |crate_name: &str, mut users: Vec<PublisherData>, mut teams: Vec<PublisherData>| {
+            if let Some(key) = &key {
+                let publishers = users.iter_mut().chain(teams.iter_mut());
+                anonymize(publishers, key, &mut anonymized);
+            }
+            on_crate(crate_name, users, teams)
+        };
+    // Counts towards `--batch-size`, which only limits requests to the live API
+    let mut fetched_live = 0;
+    let yanked = if using_cache && !args.ignore_yanked {
+        yanked_dependencies(&mut cached, dependencies, &crates_io_names)
+    } else {
+        Vec::new()
+    };
 
     if using_cache {
         let age = cached.age().unwrap();
-        eprintln!(
-            "\nUsing cached data. Cache age: {}",
+        info!(
+            "Using cached data. Cache age: {}",
             indicatif::HumanDuration(age)
         );
     } else {
-        eprintln!("\nFetching publisher info from crates.io");
-        eprintln!("This will take roughly 2 seconds per crate due to API rate limits");
+        info!("Fetching publisher info from crates.io. This will take roughly 2 seconds per crate due to API rate limits");
     }
 
-    let bar = indicatif::ProgressBar::new(crates_io_names.len() as u64)
+    // With verbose output the log messages below report progress instead,
+    // and a progress bar would garble them
+    let bar = if args.verbose > 0 {
+        indicatif::ProgressBar::hidden()
+    } else {
+        indicatif::ProgressBar::new(crates_io_names.len() as u64)
+    }
     .with_prefix("Preparing")
     .with_style(
         indicatif::ProgressStyle::default_bar()
-        .template("{prefix:>12.bright.cyan} [{bar:27}] {pos:>4}/{len:4} ETA {eta:3} - {msg:.cyan}").unwrap()
-        .progress_chars("=> ")
+            .template(
+                "{prefix:>12.bright.cyan} [{bar:27}] {pos:>4}/{len:4} ETA {eta:3} - {msg:.cyan}",
+            )
+            .unwrap()
+            .progress_chars("=> "),
     );
 
     for (i, crate_name) in crates_io_names.iter().enumerate() {
         bar.set_message(crate_name.clone());
         bar.set_position((i + 1) as u64);
+        info!("[{}/{}] {}", i + 1, crates_io_names.len(), crate_name);
         let cached_users = cached.publisher_users(crate_name);
         let cached_teams = cached.publisher_teams(crate_name);
         if let (Some(pub_users), Some(pub_teams)) = (cached_users, cached_teams) {
+            debug!("Cache hit for {}", crate_name);
+            bar.set_prefix("Loading cache");
+            if pub_users.is_empty() && pub_teams.is_empty() {
+                ownerless_crates.push(crate_name.clone());
+            }
+            on_crate(crate_name, pub_users, pub_teams)?;
+        } else if cached.crate_exists(crate_name) == Some(true) {
+            // The crate is in the dump, but no owners are recorded for it
+            debug!("Cache hit for {}, with no owners", crate_name);
             bar.set_prefix("Loading cache");
-            users.insert(crate_name.clone(), pub_users);
-            teams.insert(crate_name.clone(), pub_teams);
+            ownerless_crates.push(crate_name.clone());
+            on_crate(crate_name, Vec::new(), Vec::new())?;
         } else {
             // Handle crates not found in the cache by fetching live data for them
+            debug!("Cache miss for {}", crate_name);
+            if using_cache && args.no_default_registry_fallback {
+                debug!("Not fetching {} from the live API", crate_name);
+                not_fetched.push(crate_name.clone());
+                continue;
+            }
+            if args.offline() {
+                let message = format!(
+                    "Crate `{}` is not in the cache; cannot fetch live data in --offline mode.",
+                    crate_name
+                );
+                return Err(io::Error::other(message));
+            }
             bar.set_prefix("Downloading");
-            let pusers = publisher_users(&mut client, crate_name)?;
-            users.insert(crate_name.clone(), pusers);
+            let mut pusers = publisher_users(&mut client, crate_name)?;
+            if !args.include_email {
+                pusers.iter_mut().for_each(|user| user.email = None);
+            }
             let pteams = publisher_teams(&mut client, crate_name)?;
-            teams.insert(crate_name.clone(), pteams);
+            on_crate(crate_name, pusers, pteams)?;
+            fetched_live += 1;
+            if let Some(batch_size) = args.batch_size {
+                let remaining = i + 1 < crates_io_names.len();
+                if remaining && fetched_live % batch_size.get() == 0 {
+                    pause_between_batches(&bar, args, i + 1, crates_io_names.len())?;
+                }
+            }
         }
     }
-    Ok((users, teams))
+    bar.finish();
+    for crate_name in ownerless_crates {
+        warn!(
+            "{} has 0 owners on record in the DB dump - this is unusual.",
+            crate_name
+        );
+    }
+    if !not_fetched.is_empty() {
+        warn!(
+            "{} crates are not in the cache and were not fetched because of --no-default-registry-fallback: {}",
+            not_fetched.len(),
+            not_fetched.join(", ")
+        );
+    }
+    for (crate_name, version) in yanked {
+        warn!(
+            "Your dependency graph includes yanked version: {} {}.",
+            crate_name, version
+        );
+    }
+    print_anonymized(&anonymized);
+    Ok(not_fetched)
+}
+
+/// The crates.io dependencies among `crate_names` whose version in the graph is yanked,
+/// as far as the cache knows
+fn yanked_dependencies(
+    cached: &mut CratesCache,
+    dependencies: &[SourcedPackage],
+    crate_names: &[String],
+) -> Vec<(String, String)> {
+    let mut yanked: Vec<(String, String)> = dependencies
+        .iter()
+        .filter(|dep| dep.source == PkgSource::CratesIo)
+        // Sorted by `crates_to_fetch`
+        .filter(|dep| crate_names.binary_search(&dep.package.name).is_ok())
+        .map(|dep| (dep.package.name.clone(), dep.package.version.to_string()))
+        .filter(|(name, version)| cached.is_version_yanked(name, version) == Some(true))
+        .collect();
+    yanked.sort_unstable();
+    yanked.dedup();
+    yanked
+}
+
+/// The key of the IDs of `--anonymize`: `--anonymize-key`, or else the one in the cache directory
+pub fn anonymize_key(args: &QueryCommandArgs) -> Result<Vec<u8>, io::Error> {
+    if let Some(Redacted(key)) = &args.anonymize_key {
+        return Ok(key.clone().into_bytes());
+    }
+    CratesCache::new().anonymize_key().map_err(|error| match error.kind() {
+        ErrorKind::NotFound => io::Error::other(
+            "There is no cache directory to keep the key of `--anonymize` in, pass `--anonymize-key`",
+        ),
+        _ => error,
+    })
+}
+
+/// Anonymizes the `publishers` for `--anonymize` with the [`anonymize_key`],
+/// adding their real logins to `anonymized`
+pub fn anonymize<'a>(
+    publishers: impl IntoIterator<Item = &'a mut PublisherData>,
+    key: &[u8],
+    anonymized: &mut BTreeMap<String, String>,
+) {
+    for publisher in publishers {
+        let login = publisher.anonymize(key);
+        anonymized.insert(publisher.login.clone(), login);
+    }
+}
+
+/// Lists the real logins behind the IDs of `--anonymize` on stderr, keeping them out of the report
+pub fn print_anonymized(anonymized: &BTreeMap<String, String>) {
+    if anonymized.is_empty() {
+        return;
+    }
+    eprintln!("\nAnonymized publishers, for your reference only:");
+    for (id, login) in anonymized {
+        eprintln!(" - {}: {}", id, strip_control_chars(login));
+    }
+}
+
+/// The workspace members that are published on crates.io, for `--include-published-members`.
+/// Empty unless `include` is set. Members that are not on crates.io are skipped silently.
+pub fn published_workspace_members(
+    include: bool,
+    dependencies: &[SourcedPackage],
+    args: &QueryCommandArgs,
+) -> Result<BTreeSet<String>, io::Error> {
+    let mut published = BTreeSet::new();
+    if !include {
+        return Ok(published);
+    }
+    let mut cached = CratesCache::new();
+    // An expired cache is not consulted, like when fetching the publishers
+    cached.expire(args.cache_max_age(), None);
+    let mut client =
+        RateLimitedClient::with_tls(&args.tls)?.with_token(args.crates_io_token.clone());
+    for name in crate_names_from_source(dependencies, PkgSource::Local) {
+        let exists = match cached.crate_exists(&name) {
+            Some(exists) => exists,
+            None if args.offline() => {
+                return Err(io::Error::other(OFFLINE_NO_CACHE));
+            }
+            None => crate_is_published(&mut client, &name)?,
+        };
+        if exists {
+            debug!("Workspace member {} is published on crates.io", name);
+            published.insert(name);
+        } else {
+            debug!("Workspace member {} is not published on crates.io", name);
+        }
+    }
+    Ok(published)
+}
+
+fn crate_is_published(client: &mut RateLimitedClient, crate_name: &str) -> Result<bool, io::Error> {
+    let url = format!("https://crates.io/api/v1/crates/{}", crate_name);
+    debug!("Fetching {}", url);
+    match client.get(&url).call() {
+        Ok(_) => Ok(true),
+        Err(ureq::Error::Status(404, _)) => Ok(false),
+        Err(error) => Err(io::Error::other(error)),
+    }
+}
+
+/// Sleeps for `--batch-pause-secs`, or waits for the user to press Enter
+fn pause_between_batches(
+    bar: &indicatif::ProgressBar,
+    args: &QueryCommandArgs,
+    done: usize,
+    total: usize,
+) -> Result<(), io::Error> {
+    match args.batch_pause_secs {
+        Some(secs) => {
+            info!(
+                "Fetched {}/{} crates, pausing for {} seconds",
+                done, total, secs
+            );
+            thread::sleep(Duration::from_secs(secs));
+            Ok(())
+        }
+        None => bar.suspend(|| {
+            eprint!(
+                "Fetched {}/{} crates, press Enter to continue or Ctrl-C to abort.",
+                done, total
+            );
+            io::stderr().flush()?;
+            io::stdin().read_line(&mut String::new())?;
+            Ok(())
+        }),
+    }
+}
+
+/// Removes the crates whose combined number of users and teams is out of the requested range
+pub fn retain_by_owner_count(
+    users: &mut BTreeMap<String, Vec<PublisherData>>,
+    teams: &mut BTreeMap<String, Vec<PublisherData>>,
+    filter: &OwnerCountArgs,
+) {
+    let crate_names: BTreeSet<String> = users.keys().chain(teams.keys()).cloned().collect();
+    for crate_name in crate_names {
+        let owners =
+            users.get(&crate_name).map_or(0, Vec::len) + teams.get(&crate_name).map_or(0, Vec::len);
+        if !filter.matches(owners) {
+            users.remove(&crate_name);
+            teams.remove(&crate_name);
+        }
+    }
+}
+
+/// A publisher with only the details that the tests look at
+#[cfg(test)]
+pub(crate) fn publisher(id: u64, login: &str, kind: PublisherKind) -> PublisherData {
+    PublisherData {
+        id,
+        login: login.to_owned(),
+        kind,
+        name: None,
+        avatar: None,
+        email: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_github_org, publisher, PublisherData, PublisherKind};
+
+    #[test]
+    fn github_org() {
+        assert_eq!(
+            extract_github_org("github:tokio-rs:mio-core"),
+            Some("tokio-rs")
+        );
+        assert_eq!(
+            extract_github_org("github:rust-lang:libs"),
+            Some("rust-lang")
+        );
+        assert_eq!(extract_github_org("gitlab:foo:bar"), None);
+    }
+
+    #[test]
+    fn display_safe_login() {
+        let publisher = PublisherData {
+            name: Some("Mallory\r\n".to_owned()),
+            ..publisher(1, "mallory\x1b[2J", PublisherKind::user)
+        };
+        assert_eq!(publisher.display_safe_login(), "mallory[2J");
+        assert_eq!(publisher.display_safe_name().as_deref(), Some("Mallory"));
+        let publisher = PublisherData {
+            login: "dtolnay".to_owned(),
+            name: None,
+            ..publisher
+        };
+        assert!(matches!(
+            publisher.display_safe_login(),
+            std::borrow::Cow::Borrowed("dtolnay")
+        ));
+        assert_eq!(publisher.display_safe_name(), None);
+    }
+
+    #[test]
+    fn hmac() {
+        // The test cases 2 and 6 of RFC 4231
+        let hex = |digest: [u8; 32]| -> String {
+            digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+        };
+        assert_eq!(
+            hex(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hex(hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn anonymize() {
+        let key = b"supply-chain";
+        let mut user = PublisherData {
+            id: 1,
+            login: "dtolnay".to_owned(),
+            kind: PublisherKind::user,
+            name: Some("David Tolnay".to_owned()),
+            avatar: Some("https://avatars.githubusercontent.com/u/1940490".to_owned()),
+            email: None,
+        };
+        assert_eq!(user.anonymize(key), "dtolnay");
+        assert_eq!(user.login, "user_3848c5eafdfaae8b");
+        assert_eq!(user.id, 0x3848c5eafdfaae8b);
+        assert_eq!((user.name, user.avatar), (None, None));
+        let mut team = PublisherData {
+            name: Some("libs".to_owned()),
+            ..publisher(2, "github:rust-lang:libs", PublisherKind::team)
+        };
+        assert_eq!(team.anonymize(key), "github:rust-lang:libs");
+        assert_eq!(team.login, "team_6b3867af57ff171d");
+        // Another key gives other IDs
+        let mut other = publisher(1, "dtolnay", PublisherKind::user);
+        other.anonymize(b"another key");
+        assert_ne!(other.login, user.login);
+        // Both would be `user_81229` if the ID were shorter
+        let mut first = publisher(3, "user90", PublisherKind::user);
+        let mut second = publisher(4, "user553", PublisherKind::user);
+        first.anonymize(key);
+        second.anonymize(key);
+        assert_ne!(first.login, second.login);
+    }
 }
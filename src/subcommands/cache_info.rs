@@ -0,0 +1,170 @@
+//! `cache-info` shows the state of the local cache, for debugging stale or missing data
+
+use crate::crates_cache::{CacheFile, CacheStats, CratesCache};
+use serde::Serialize;
+use std::time::{Duration, SystemTime};
+
+/// The output of `cache-info --list-cache-files --json`
+#[derive(Debug, Serialize)]
+struct CacheFiles {
+    /// When crates.io created the cached dump, as opposed to when the files were written
+    #[serde(with = "humantime_serde")]
+    data_freshness: Option<SystemTime>,
+    files: Vec<CacheFile>,
+}
+
+pub fn cache_info(max_age: Duration, json: bool, list_files: bool) -> Result<(), anyhow::Error> {
+    if list_files {
+        return list_cache_files(json);
+    }
+    let stats = CratesCache::new().stats(max_age)?;
+    if json {
+        let stdout = std::io::stdout();
+        serde_json::to_writer_pretty(stdout.lock(), &stats)?;
+        println!();
+    } else {
+        print_text(&stats, max_age);
+    }
+    Ok(())
+}
+
+fn print_text(stats: &CacheStats, max_age: Duration) {
+    let unknown = || "unknown".to_owned();
+    let location = stats.location.as_ref();
+    println!(
+        "location: {}",
+        location.map_or_else(unknown, |dir| dir.display().to_string())
+    );
+    println!(
+        "state: {} (valid while younger than {})",
+        stats.state,
+        humantime::format_duration(max_age)
+    );
+    let time = |time: Option<_>| {
+        time.map_or_else(unknown, |time| {
+            humantime::format_rfc3339_seconds(time).to_string()
+        })
+    };
+    println!("dump timestamp: {}", time(stats.timestamp));
+    println!("downloaded: {}", time(stats.downloaded));
+    println!(
+        "age: {}",
+        stats
+            .age
+            .map_or_else(unknown, |age| { indicatif::HumanDuration(age).to_string() })
+    );
+    println!(
+        "created by version: {}",
+        stats.tool_version.clone().unwrap_or_else(unknown)
+    );
+    let count = |count: Option<usize>| count.map_or_else(unknown, |count| count.to_string());
+    println!("crates: {}", count(stats.crate_count));
+    println!("users: {}", count(stats.user_count));
+    println!("teams: {}", count(stats.team_count));
+    if stats.files.is_empty() {
+        println!("files: none");
+    } else {
+        println!("files:");
+        for (name, size) in &stats.files {
+            println!("  {}: {}", name, indicatif::HumanBytes(*size));
+        }
+    }
+}
+
+fn cache_files(cache: &mut CratesCache) -> Result<CacheFiles, std::io::Error> {
+    Ok(CacheFiles {
+        data_freshness: cache.timestamp(),
+        files: cache.files()?,
+    })
+}
+
+fn list_cache_files(json: bool) -> Result<(), anyhow::Error> {
+    let mut cache = CratesCache::new();
+    let output = cache_files(&mut cache)?;
+    if json {
+        let stdout = std::io::stdout();
+        serde_json::to_writer_pretty(stdout.lock(), &output)?;
+        println!();
+        return Ok(());
+    }
+    let time = |time: SystemTime| humantime::format_rfc3339_seconds(time).to_string();
+    println!(
+        "location: {}",
+        cache
+            .location()
+            .map_or_else(|| "unknown".to_owned(), |dir| dir.display().to_string())
+    );
+    println!(
+        "data freshness: {}",
+        output
+            .data_freshness
+            .map_or_else(|| "unknown".to_owned(), time)
+    );
+    let width = output
+        .files
+        .iter()
+        .map(|file| file.name.len())
+        .max()
+        .unwrap_or_default();
+    for file in &output.files {
+        match file.size {
+            Some(size) => println!(
+                "  {:width$}  {:>10}  modified {}",
+                file.name,
+                indicatif::HumanBytes(size).to_string(),
+                file.modified.map_or_else(|| "unknown".to_owned(), time),
+                width = width
+            ),
+            None => println!("  {:width$}  MISSING", file.name, width = width),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn cache_files_json() {
+        let dir = std::env::temp_dir().join(format!(
+            "supply-chain-cache-info-files-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("crates.json"), "{}").unwrap();
+
+        let mut cache = CratesCache::in_dir(dir.clone(), Duration::from_secs(3600));
+        let json = serde_json::to_value(cache_files(&mut cache).unwrap()).unwrap();
+        let output = json.as_object().unwrap();
+        let keys: Vec<_> = output.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["data_freshness", "files"]);
+        // Like the other timestamps of the JSON output
+        let freshness = output["data_freshness"].as_str().unwrap();
+        assert!(humantime::parse_rfc3339(freshness).is_ok());
+
+        let files = output["files"].as_array().unwrap();
+        assert!(files.len() > 1);
+        for file in files {
+            let keys: Vec<_> = file
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(String::as_str)
+                .collect();
+            assert_eq!(keys, ["modified", "name", "size"]);
+            if file["name"] == "crates.json" {
+                assert_eq!(file["size"], 2);
+                assert!(file["modified"].is_string());
+            } else {
+                assert!(file["size"].is_null());
+                assert!(file["modified"].is_null());
+            }
+        }
+        assert!(files.iter().any(|file| file["name"] == "crates.json"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,14 @@
+//! `generate-completions` subcommand prints a completion script for the given shell.
+//! The script calls back into `cargo-supply-chain` to complete each word,
+//! so it stays up to date with the installed version.
+
+use crate::cli::args_parser;
+use crate::output::ShellChoice;
+
+pub fn generate_completions(shell: ShellChoice) {
+    // bpaf prints the script when invoked with a flag of its own
+    let flag = format!("--bpaf-complete-style-{}", shell.name());
+    if let Err(failure) = args_parser().run_inner(&[flag.as_str()][..]) {
+        failure.print_message(100);
+    }
+}
@@ -1,65 +1,852 @@
-use crate::publishers::{fetch_owners_of_crates, PublisherKind};
+use anyhow::bail;
+
+use crate::cli::{CratesArgs, OwnerCountArgs, QueryCommandArgs, ReportingArgs};
+use crate::crates_cache::{CacheState, CacheTable, CratesCache};
+use crate::downloads::{self, DownloadCount};
+use crate::output::{
+    self, align::Column, github_actions, github_markdown, ColorMode, OutputFormat, SortKey,
+};
+use crate::publishers::{
+    extract_github_org, fetch_owners_of_crates, published_workspace_members, retain_by_owner_count,
+    PublisherData, PublisherKind,
+};
+use crate::summary::{diffable_header, print_counts, PublisherSummary};
+use crate::supplemental::SupplementalPublishers;
 use crate::{
-    common::{comma_separated_list, complain_about_non_crates_io_crates, sourced_dependencies},
+    common::{
+        build_only_crate_names, complain_about_non_crates_io_crates, hyperlink,
+        proc_macro_crate_names, separated_list, sourced_dependencies, workspace_root,
+        SourcedPackage,
+    },
     MetadataArgs,
 };
+use serde::Serialize;
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+};
+
+#[cfg(test)]
+use schemars::JsonSchema;
+
+/// The values of `--format`, listed by `--help-formats`
+pub const FORMATS: &[(&str, &str)] = &[
+    (
+        "text",
+        "Numbered list of the crates and their publishers, the default outside of GitHub Actions",
+    ),
+    (
+        "github-actions",
+        "Annotations on the workflow run, the default in GitHub Actions",
+    ),
+    (
+        "github-markdown",
+        "Collapsible Markdown table to post as a comment on a pull request",
+    ),
+    ("json", "JSON document described by `--print-schema`"),
+];
+
+/// The output of `crates --format json`
+#[cfg_attr(test, derive(JsonSchema))]
+#[derive(Debug, Serialize)]
+struct CratesOutput {
+    /// Maps crate names to info about the publishers of each crate
+    crates_io_crates: BTreeMap<String, Vec<PublisherData>>,
+    /// Maps crate names to how many users and teams can publish them.
+    /// Only present with `--show-counts`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner_counts: Option<BTreeMap<String, OwnerCounts>>,
+}
+
+/// How many users and teams can publish a crate
+#[cfg_attr(test, derive(JsonSchema))]
+#[derive(Debug, Serialize, PartialEq, Eq)]
+struct OwnerCounts {
+    users: usize,
+    teams: usize,
+}
+
+impl OwnerCounts {
+    fn of(publishers: &[PublisherData]) -> Self {
+        let teams = publishers
+            .iter()
+            .filter(|p| p.kind == PublisherKind::team)
+            .count();
+        OwnerCounts {
+            users: publishers.len() - teams,
+            teams,
+        }
+    }
+}
+
+impl fmt::Display for OwnerCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        write!(
+            f,
+            "[{} user{}, {} team{}]",
+            self.users,
+            plural(self.users),
+            self.teams,
+            plural(self.teams)
+        )
+    }
+}
 
 pub fn crates(
     metadata_args: MetadataArgs,
-    diffable: bool,
-    max_age: std::time::Duration,
+    args: QueryCommandArgs,
+    owner_count: OwnerCountArgs,
+    crates_args: CratesArgs,
+    reporting: ReportingArgs,
+    count: bool,
 ) -> Result<(), anyhow::Error> {
-    let dependencies = sourced_dependencies(metadata_args)?;
-    complain_about_non_crates_io_crates(&dependencies);
-    let (mut owners, publisher_teams) = fetch_owners_of_crates(&dependencies, max_age)?;
+    if crates_args.print_schema {
+        if crates_args.format != Some(OutputFormat::Json) {
+            bail!("`--print-schema` requires `--format json`");
+        }
+        println!("{}", JSON_SCHEMA);
+        return Ok(());
+    }
+    if count && args.diffable {
+        bail!("`--count` cannot be combined with `--diffable`");
+    }
+    if count && crates_args.format.is_some() {
+        bail!("`--count` cannot be combined with `--format`");
+    }
+    if crates_args.align.align && args.diffable {
+        bail!("`--align` cannot be combined with `--diffable`");
+    }
+    if crates_args.prepend_headers
+        && (crates_args.fields.is_none() || crates_args.format == Some(OutputFormat::Json))
+    {
+        bail!("`--prepend-headers` requires `--fields` with text output");
+    }
+    if crates_args.group_by_category && !crates_args.show_crate_categories {
+        bail!("`--group-by-category` requires `--show-crate-categories`");
+    }
+    if crates_args.group_by_category && args.diffable {
+        bail!("`--group-by-category` cannot be combined with `--diffable`");
+    }
+    if crates_args.show_crate_categories {
+        let text = crates_args.format.is_none_or(|f| f == OutputFormat::Text);
+        if count || crates_args.interactive || crates_args.fields.is_some() || !text {
+            bail!("`--show-crate-categories` is only supported with the default text output");
+        }
+    }
+    if crates_args.show_download_count {
+        let supported = crates_args
+            .format
+            .is_none_or(|f| matches!(f, OutputFormat::Text | OutputFormat::GithubMarkdown));
+        if count || crates_args.interactive || crates_args.fields.is_some() || !supported {
+            bail!(
+                "`--show-download-count` is only supported with text and `github-markdown` output"
+            );
+        }
+    }
+    let root = if crates_args.prepend_headers {
+        Some(workspace_root(metadata_args.clone())?)
+    } else {
+        None
+    };
+    let supplemental = SupplementalPublishers::load(args.publishers_file.as_deref())?;
+    let tag_build_only = metadata_args.include_build_dependencies;
+    let include_members = metadata_args.include_published_members;
+    let dependencies = sourced_dependencies(metadata_args.clone())?;
+    let members = published_workspace_members(include_members, &dependencies, &args)?;
+    complain_about_non_crates_io_crates(&dependencies, &members, &reporting);
+    let (mut owners, mut publisher_teams) =
+        fetch_owners_of_crates(&dependencies, &members, &metadata_args, &args)?;
+    let summary = args
+        .summary
+        .then(|| PublisherSummary::new(&owners, &publisher_teams, args.since_commit.is_some()));
+    retain_by_owner_count(&mut owners, &mut publisher_teams, &owner_count);
+    if crates_args.only_proc_macros {
+        let proc_macros = proc_macro_crate_names(&dependencies);
+        owners.retain(|crate_name, _| proc_macros.contains(crate_name));
+        publisher_teams.retain(|crate_name, _| proc_macros.contains(crate_name));
+    }
 
+    if count {
+        print_counts(&owners, &publisher_teams);
+        if let Some(summary) = summary {
+            summary.print();
+        }
+        return Ok(());
+    }
+
+    let header = args
+        .diffable
+        .then(|| diffable_header(&owners, &publisher_teams));
     for (crate_name, publishers) in publisher_teams {
         owners.entry(crate_name).or_default().extend(publishers);
     }
 
     let mut ordered_owners: Vec<_> = owners.into_iter().collect();
-    if diffable {
-        // Sort alphabetically by crate name
-        ordered_owners.sort_unstable_by_key(|(name, _)| name.clone());
+    if crates_args.show_missing {
+        ordered_owners.retain(|(_, publishers)| publishers.is_empty());
+    }
+    // Diffable output is always sorted alphabetically, so that crates do not move around
+    let sort_key = if args.diffable {
+        SortKey::Name
     } else {
-        // Order by the number of owners, but put crates owned by teams first
-        ordered_owners.sort_unstable_by_key(|(name, publishers)| {
-            (
-                !publishers.iter().any(|p| p.kind == PublisherKind::team), // contains at least one team
-                usize::MAX - publishers.len(),
-                name.clone(),
-            )
+        crates_args.sort_by.unwrap_or(SortKey::Risk)
+    };
+    let download_counts =
+        (crates_args.show_download_count || sort_key == SortKey::Downloads).then(|| {
+            let crate_names: Vec<String> = ordered_owners
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect();
+            downloads::download_counts(&crate_names, &args)
         });
+    match sort_key {
+        SortKey::Name => ordered_owners.sort_unstable_by_key(|(name, _)| name.clone()),
+        SortKey::Publishers => ordered_owners
+            .sort_unstable_by_key(|(name, publishers)| (publishers.len(), name.clone())),
+        SortKey::Risk => {
+            // Order by the number of owners, but put crates owned by teams first
+            ordered_owners.sort_unstable_by_key(|(name, publishers)| {
+                (
+                    !publishers.iter().any(|p| p.kind == PublisherKind::team), // contains at least one team
+                    usize::MAX - publishers.len(),
+                    name.clone(),
+                )
+            })
+        }
+        SortKey::Downloads => {
+            // Crates whose download counts could not be fetched go last
+            let counts = download_counts.as_ref();
+            ordered_owners.sort_unstable_by_key(|(name, _)| {
+                let total = counts.and_then(|counts| counts.get(name)).map(|c| c.total);
+                (Reverse(total), name.clone())
+            })
+        }
     }
+    // Only shown if requested, not when they were only fetched for sorting
+    let download_counts = download_counts.filter(|_| crates_args.show_download_count);
     for (_, publishers) in &mut ordered_owners {
         // For each crate put teams first
         publishers.sort_unstable_by_key(|p| (p.kind, p.login.clone()));
     }
 
-    if !diffable {
+    if crates_args.interactive {
+        return interactive(ordered_owners);
+    }
+
+    if let Some(fields) = &crates_args.fields {
+        let rows = output::fields::rows(&ordered_owners, &dependencies);
+        if let Some(root) = &root {
+            let cache_age = CratesCache::new().age();
+            output::fields::print_headers(fields, root, cache_age, args.diffable);
+        }
+        let supported = [OutputFormat::Text, OutputFormat::Json];
+        match OutputFormat::flag_or_env(crates_args.format, args.config_format, &supported)?
+            .unwrap_or(OutputFormat::Text)
+        {
+            OutputFormat::Text => output::fields::print_text(fields, &rows),
+            OutputFormat::Json => output::fields::print_json(fields, &rows, args.diffable)?,
+            format @ (OutputFormat::GithubActions | OutputFormat::GithubMarkdown) => {
+                bail!(
+                    "`--fields` cannot be combined with `--format {}`",
+                    format.name()
+                )
+            }
+        }
+        if let Some(summary) = summary {
+            summary.print();
+        }
+        return Ok(());
+    }
+
+    let supported = [
+        OutputFormat::Text,
+        OutputFormat::GithubActions,
+        OutputFormat::GithubMarkdown,
+        OutputFormat::Json,
+    ];
+    match OutputFormat::flag_or_env(crates_args.format, args.config_format, &supported)?
+        .unwrap_or_else(OutputFormat::detect)
+    {
+        OutputFormat::Text => {
+            let annotations = Annotations {
+                categories: if crates_args.show_crate_categories {
+                    Some(cached_categories(&ordered_owners, &args)?)
+                } else {
+                    None
+                },
+                download_counts,
+            };
+            if let Some(header) = header {
+                println!("{}", header);
+            }
+            print_text(
+                &ordered_owners,
+                &annotations,
+                &dependencies,
+                tag_build_only,
+                &args,
+                &crates_args,
+                &supplemental,
+            )
+        }
+        OutputFormat::GithubActions => {
+            github_actions::print_crates(&ordered_owners, &dependencies, args.separator())
+        }
+        OutputFormat::GithubMarkdown => {
+            let mut cache = CratesCache::new();
+            let cache_age = match cache.expire(args.cache_max_age(), None) {
+                CacheState::Fresh | CacheState::FreshButAging(_) => cache.age(),
+                _ => None,
+            };
+            github_markdown::print_crates(
+                &ordered_owners,
+                download_counts.as_ref(),
+                cache_age,
+                args.since_commit.as_deref(),
+                args.separator(),
+            )
+        }
+        OutputFormat::Json => print_json(&ordered_owners, args.diffable, crates_args.show_counts)?,
+    }
+
+    if !ordered_owners.is_empty() && !args.no_summary() {
+        eprintln!("\nNote: there may be outstanding publisher invitations. crates.io provides no way to list them.");
+        eprintln!("See https://github.com/rust-lang/crates.io/issues/2868 for more info.");
+    }
+
+    if let Some(summary) = summary {
+        summary.print();
+    }
+    Ok(())
+}
+
+/// Looks up the categories of the crates in the cached DB dump. The crates that are not
+/// in the dump, e.g. very new ones, have none.
+fn cached_categories(
+    ordered_owners: &[(String, Vec<PublisherData>)],
+    args: &QueryCommandArgs,
+) -> Result<HashMap<String, Vec<String>>, anyhow::Error> {
+    let mut cache = CratesCache::new();
+    let usable = matches!(
+        cache.expire(args.cache_max_age(), None),
+        CacheState::Fresh | CacheState::FreshButAging(_)
+    );
+    let tables = [
+        CacheTable::Crates,
+        CacheTable::Categories,
+        CacheTable::CrateCategories,
+    ];
+    if !usable || cache.prefetch(&tables).is_err() {
+        bail!("`--show-crate-categories` needs the categories from the `crates.io` cache. Run `cargo supply-chain update --with-categories` to download them.");
+    }
+    Ok(ordered_owners
+        .iter()
+        .map(|(crate_name, _)| {
+            let categories = cache.crate_categories(crate_name).unwrap_or_default();
+            (crate_name.clone(), categories)
+        })
+        .collect())
+}
+
+/// Groups the indices of `crate_names` by category, in alphabetical order. A crate with
+/// several categories is in several groups, and the crates with none are under `None`.
+fn group_by_category<'a>(
+    crate_names: &[&str],
+    categories: &'a HashMap<String, Vec<String>>,
+) -> BTreeMap<Option<&'a str>, Vec<usize>> {
+    let mut groups: BTreeMap<Option<&str>, Vec<usize>> = BTreeMap::new();
+    for (i, crate_name) in crate_names.iter().enumerate() {
+        match categories.get(*crate_name) {
+            Some(crate_categories) if !crate_categories.is_empty() => {
+                for category in crate_categories {
+                    groups.entry(Some(category.as_str())).or_default().push(i);
+                }
+            }
+            _ => groups.entry(None).or_default().push(i),
+        }
+    }
+    groups
+}
+
+/// Data about the crates that is only looked up for the text output
+struct Annotations {
+    /// From `--show-crate-categories`
+    categories: Option<HashMap<String, Vec<String>>>,
+    /// From `--show-download-count`
+    download_counts: Option<HashMap<String, DownloadCount>>,
+}
+
+fn print_text(
+    ordered_owners: &[(String, Vec<PublisherData>)],
+    annotations: &Annotations,
+    dependencies: &[SourcedPackage],
+    tag_build_only: bool,
+    args: &QueryCommandArgs,
+    crates_args: &CratesArgs,
+    supplemental: &SupplementalPublishers,
+) {
+    let diffable = args.diffable;
+    let categories = annotations.categories.as_ref();
+    let build_only = if tag_build_only {
+        build_only_crate_names(dependencies)
+    } else {
+        HashSet::new()
+    };
+    let proc_macros = proc_macro_crate_names(dependencies);
+    let link_crates = crates_args.link_crates;
+    // Full team logins are more stable, so keep them in diffable output
+    let show_org = crates_args.show_org && !diffable;
+    let depths = crates_args.show_depth.then(|| crate_depths(dependencies));
+    // Diffable output is meant for tools, which can look up the publishers file themselves
+    let annotation = |p: &PublisherData| {
+        if diffable {
+            String::new()
+        } else {
+            supplemental.annotation(&p.login)
+        }
+    };
+    let colors = crates_args.color.is_some_and(ColorMode::enabled) && !diffable;
+    // Like colors, but on by default when printing to a terminal
+    let link_escapes = crates_args.color.unwrap_or(ColorMode::Auto).enabled() && !diffable;
+    if crates_args.show_missing {
+        if !diffable && !ordered_owners.is_empty() {
+            println!("\nThe following crates have no publisher data on record \u{2014} this may indicate a supply chain risk.\n");
+        }
+    } else if !diffable {
         println!(
             "\nDependency crates with the people and teams that can publish them to crates.io:\n"
         );
     }
-    for (i, (crate_name, publishers)) in ordered_owners.iter().enumerate() {
-        let pretty_publishers: Vec<String> = publishers
+    // The crate names are printed in a column with `--align`,
+    // so all of them must be known before printing the first one
+    let mut lines = Vec::new();
+    for (crate_name, publishers) in ordered_owners {
+        let mut pretty_publishers: Vec<String> = publishers
             .iter()
-            .map(|p| match p.kind {
-                PublisherKind::team => format!("team \"{}\"", p.login),
-                PublisherKind::user => p.login.to_string(),
+            .map(|p| (p, p.display_safe_login()))
+            .map(|(p, login)| match p.kind {
+                PublisherKind::team if show_org => match extract_github_org(&login) {
+                    Some(org) => format!("org:{}{}", org, annotation(p)),
+                    None => format!("team \"{}\"{}", login, annotation(p)),
+                },
+                PublisherKind::team => format!("team \"{}\"{}", login, annotation(p)),
+                PublisherKind::user if link_crates => format!(
+                    "{}{}",
+                    hyperlink(
+                        &login,
+                        &format!("https://crates.io/users/{}", login),
+                        link_escapes
+                    ),
+                    annotation(p)
+                ),
+                PublisherKind::user => format!("{}{}", login, annotation(p)),
             })
             .collect();
-        let publishers_list = comma_separated_list(&pretty_publishers);
+        // Teams are sorted by login, so the teams of an org are next to each other
+        pretty_publishers.dedup();
+        let mut publishers_list = separated_list(&pretty_publishers, args.separator());
+        if crates_args.show_counts {
+            publishers_list.push_str(&format!(" {}", OwnerCounts::of(publishers)));
+        }
+        if let Some(crate_categories) = categories.and_then(|c| c.get(crate_name)) {
+            publishers_list.push_str(&format!(" [{}]", crate_categories.join(", ")));
+        }
+        let download_count = annotations
+            .download_counts
+            .as_ref()
+            .and_then(|counts| counts.get(crate_name));
+        if let Some(download_count) = download_count {
+            publishers_list.push_str(&format!(" {}", download_count));
+        }
+        let mut label = CrateLabel::new(
+            crate_name,
+            link_crates.then_some(link_escapes),
+            depths.as_ref().and_then(|depths| depths.get(crate_name)),
+        );
+        label.build_only = build_only.contains(crate_name);
+        // With `--only-proc-macros` every crate is a proc-macro, so there is no need to say so
+        label.proc_macro = proc_macros.contains(crate_name) && !crates_args.only_proc_macros;
+        // With `--show-missing` every crate is missing its publishers, so there is no need to say so
+        label.no_owner = publishers.is_empty() && !crates_args.show_missing;
+        lines.push((label, publishers_list));
+    }
+
+    let labels: Vec<String> = lines.iter().map(|(label, _)| label.text()).collect();
+    let column = crates_args.align.column(labels.iter().map(String::as_str));
+    let index_width = lines.len().to_string().len();
+    if let Some(column) = &column {
+        // The column is preceded by the index, e.g. `12. `
+        column.warn_if_too_wide(index_width + 2);
+    }
+    let print_line = |number: usize, (label, publishers_list): &(CrateLabel, String)| {
         if diffable {
-            println!("{}: {}", crate_name, publishers_list);
+            println!("{} {}", label.display(colors, None), publishers_list);
+        } else if let Some(column) = &column {
+            println!(
+                "{:>width$}. {} {}",
+                number,
+                label.display(colors, Some(column)),
+                publishers_list,
+                width = index_width
+            );
         } else {
-            println!("{}. {}: {}", i + 1, crate_name, publishers_list);
+            println!(
+                "{}. {} {}",
+                number,
+                label.display(colors, None),
+                publishers_list
+            );
+        }
+    };
+    match categories.filter(|_| crates_args.group_by_category) {
+        Some(categories) => {
+            let crate_names: Vec<&str> = ordered_owners
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect();
+            let mut groups = group_by_category(&crate_names, categories);
+            // Named categories first, then the crates without any
+            let uncategorized = groups.remove(&None);
+            let groups = groups
+                .into_iter()
+                .chain(uncategorized.map(|indices| (None, indices)));
+            for (category, indices) in groups {
+                println!("\n{}:\n", category.unwrap_or("uncategorized"));
+                for (number, &i) in indices.iter().enumerate() {
+                    print_line(number + 1, &lines[i]);
+                }
+            }
+        }
+        None => {
+            for (i, line) in lines.iter().enumerate() {
+                print_line(i + 1, line);
+            }
         }
     }
+}
 
-    if !ordered_owners.is_empty() {
-        eprintln!("\nNote: there may be outstanding publisher invitations. crates.io provides no way to list them.");
-        eprintln!("See https://github.com/rust-lang/crates.io/issues/2868 for more info.");
+/// How a crate is named in the text output, before its publishers
+struct CrateLabel {
+    name: String,
+    /// The page of the crate on crates.io with `--link-crates`, as a hyperlink over the name
+    link: Option<String>,
+    /// The page of the crate if it is printed instead, the depth and the colon
+    suffix: String,
+    no_owner: bool,
+    proc_macro: bool,
+    build_only: bool,
+}
+
+impl CrateLabel {
+    /// `link_escapes` is `Some` with `--link-crates`,
+    /// and tells whether to link the name or to print the URL after it
+    fn new(crate_name: &str, link_escapes: Option<bool>, depth: Option<&usize>) -> Self {
+        let url = format!("https://crates.io/crates/{}", crate_name);
+        let (link, mut suffix) = match link_escapes {
+            Some(true) => (Some(url), String::new()),
+            Some(false) => (None, format!(" ({})", url)),
+            None => (None, String::new()),
+        };
+        if let Some(depth) = depth {
+            suffix.push_str(&format!(" (depth {})", depth));
+        }
+        suffix.push(':');
+        CrateLabel {
+            name: crate_name.to_owned(),
+            link,
+            suffix,
+            no_owner: false,
+            proc_macro: false,
+            build_only: false,
+        }
+    }
+
+    fn markers(&self) -> String {
+        let mut markers = String::new();
+        if self.no_owner {
+            markers.push_str("[NO OWNER] ");
+        }
+        if self.proc_macro {
+            markers.push_str("[proc-macro] ");
+        }
+        if self.build_only {
+            markers.push_str("[build] ");
+        }
+        markers
+    }
+
+    /// The label as displayed, without escape sequences, for the width of `--align`
+    fn text(&self) -> String {
+        format!("{}{}{}", self.markers(), self.name, self.suffix)
     }
+
+    /// The label with its escape sequences, padded to the `column` of `--align`.
+    /// Only the name is shortened to fit, so the markers and the link are always shown.
+    fn display(&self, colors: bool, column: Option<&Column>) -> String {
+        let mut markers = self.markers();
+        let rest = markers.chars().count() + self.suffix.chars().count();
+        let (name, padding) = match column {
+            Some(column) => column.fit(&self.name, rest),
+            None => (Cow::Borrowed(self.name.as_str()), String::new()),
+        };
+        let name = match &self.link {
+            Some(url) => hyperlink(&name, url, true),
+            None => name.into_owned(),
+        };
+        if self.no_owner && colors {
+            markers = markers.replacen("[NO OWNER]", "\x1b[31m[NO OWNER]\x1b[0m", 1);
+        }
+        format!("{}{}{}{}", markers, name, self.suffix, padding)
+    }
+}
+
+fn print_json(
+    ordered_owners: &[(String, Vec<PublisherData>)],
+    diffable: bool,
+    show_counts: bool,
+) -> Result<(), anyhow::Error> {
+    let owner_counts = show_counts.then(|| {
+        ordered_owners
+            .iter()
+            .map(|(name, publishers)| (name.clone(), OwnerCounts::of(publishers)))
+            .collect()
+    });
+    let output = CratesOutput {
+        crates_io_crates: ordered_owners.iter().cloned().collect(),
+        owner_counts,
+    };
+    let stdout = std::io::stdout();
+    let handle = stdout.lock();
+    if diffable {
+        serde_json::to_writer_pretty(handle, &output)?;
+    } else {
+        serde_json::to_writer(handle, &output)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "tui")]
+fn interactive(ordered_owners: Vec<(String, Vec<PublisherData>)>) -> Result<(), anyhow::Error> {
+    crate::tui::run(ordered_owners)?;
     Ok(())
 }
+
+#[cfg(not(feature = "tui"))]
+fn interactive(_ordered_owners: Vec<(String, Vec<PublisherData>)>) -> Result<(), anyhow::Error> {
+    bail!(
+        "`--interactive` is not available: cargo-supply-chain was built without the `tui` feature"
+    )
+}
+
+/// Maps crate names to the smallest depth at which any version of the crate appears
+fn crate_depths(dependencies: &[SourcedPackage]) -> HashMap<String, usize> {
+    let mut depths: HashMap<String, usize> = HashMap::new();
+    for dep in dependencies {
+        if let Some(depth) = dep.depth {
+            let min_depth = depths.entry(dep.package.name.clone()).or_insert(depth);
+            *min_depth = (*min_depth).min(depth);
+        }
+    }
+    depths
+}
+
+const JSON_SCHEMA: &str = r##"{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "CratesOutput",
+  "description": "The output of `crates --format json`",
+  "type": "object",
+  "required": [
+    "crates_io_crates"
+  ],
+  "properties": {
+    "crates_io_crates": {
+      "description": "Maps crate names to info about the publishers of each crate",
+      "type": "object",
+      "additionalProperties": {
+        "type": "array",
+        "items": {
+          "$ref": "#/definitions/PublisherData"
+        }
+      }
+    },
+    "owner_counts": {
+      "description": "Maps crate names to how many users and teams can publish them. Only present with `--show-counts`",
+      "type": [
+        "object",
+        "null"
+      ],
+      "additionalProperties": {
+        "$ref": "#/definitions/OwnerCounts"
+      }
+    }
+  },
+  "definitions": {
+    "OwnerCounts": {
+      "description": "How many users and teams can publish a crate",
+      "type": "object",
+      "required": [
+        "teams",
+        "users"
+      ],
+      "properties": {
+        "teams": {
+          "type": "integer",
+          "format": "uint",
+          "minimum": 0.0
+        },
+        "users": {
+          "type": "integer",
+          "format": "uint",
+          "minimum": 0.0
+        }
+      }
+    },
+    "PublisherData": {
+      "description": "Data about a single publisher received from a crates.io API endpoint",
+      "type": "object",
+      "required": [
+        "id",
+        "kind",
+        "login"
+      ],
+      "properties": {
+        "avatar": {
+          "description": "Avatar image URL",
+          "type": [
+            "string",
+            "null"
+          ]
+        },
+        "email": {
+          "description": "Email address. Only present with `--include-email` when using the live crates.io API, never when using the cache",
+          "type": [
+            "string",
+            "null"
+          ]
+        },
+        "id": {
+          "type": "integer",
+          "format": "uint64",
+          "minimum": 0.0
+        },
+        "kind": {
+          "$ref": "#/definitions/PublisherKind"
+        },
+        "login": {
+          "type": "string"
+        },
+        "name": {
+          "description": "Display name. It is NOT guaranteed to be unique!",
+          "type": [
+            "string",
+            "null"
+          ]
+        }
+      }
+    },
+    "PublisherKind": {
+      "type": "string",
+      "enum": [
+        "team",
+        "user"
+      ]
+    }
+  }
+}"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::publishers::publisher;
+    use schemars::schema_for;
+
+    #[test]
+    fn test_json_schema() {
+        let schema = schema_for!(CratesOutput);
+        let schema = serde_json::to_string_pretty(&schema).unwrap();
+        assert_eq!(schema, JSON_SCHEMA);
+    }
+
+    #[test]
+    fn formats_are_valid() {
+        for (name, _) in FORMATS {
+            let format: OutputFormat = name.parse().unwrap();
+            assert_eq!(format.name(), *name);
+        }
+    }
+
+    #[test]
+    fn owner_counts() {
+        let counts = OwnerCounts::of(&[
+            publisher(1, "github:rust-lang:libs", PublisherKind::team),
+            publisher(2, "dtolnay", PublisherKind::user),
+            publisher(3, "alexcrichton", PublisherKind::user),
+        ]);
+        assert_eq!(counts, OwnerCounts { users: 2, teams: 1 });
+        assert_eq!(counts.to_string(), "[2 users, 1 team]");
+        assert_eq!(OwnerCounts::of(&[]).to_string(), "[0 users, 0 teams]");
+    }
+
+    #[test]
+    fn aligned_crate_labels() {
+        // `--link-crates` without hyperlinks prints the URLs, which count towards the width
+        let serde = CrateLabel::new("serde", Some(false), None);
+        let syn = CrateLabel::new("syn", Some(false), Some(&2));
+        let labels = [serde.text(), syn.text()];
+        let column = Column::fitting(labels.iter().map(String::as_str), None);
+        assert_eq!(
+            serde.display(false, Some(&column)),
+            format!("serde (https://crates.io/crates/serde):{}", " ".repeat(6))
+        );
+        assert_eq!(
+            syn.display(false, Some(&column)),
+            "syn (https://crates.io/crates/syn) (depth 2):"
+        );
+
+        // Too long for the column, so the name is shortened, but not the URL
+        let column = Column::fitting(labels.iter().map(String::as_str), Some(40));
+        assert_eq!(
+            syn.display(false, Some(&column)),
+            "s… (https://crates.io/crates/syn) (depth 2):"
+        );
+        // Nor the marker and the hyperlink
+        let mut serde_json = CrateLabel::new("serde_json", Some(true), None);
+        serde_json.no_owner = true;
+        assert_eq!(serde_json.text(), "[NO OWNER] serde_json:");
+        let column = Column::fitting([serde_json.text().as_str()], Some(20));
+        assert_eq!(
+            serde_json.display(true, Some(&column)),
+            "\x1b[31m[NO OWNER]\x1b[0m \x1b]8;;https://crates.io/crates/serde_json\x1b\\serde_j…\x1b]8;;\x1b\\:"
+        );
+        assert_eq!(
+            serde_json.display(false, None),
+            "[NO OWNER] \x1b]8;;https://crates.io/crates/serde_json\x1b\\serde_json\x1b]8;;\x1b\\:"
+        );
+    }
+
+    #[test]
+    fn groups_by_category() {
+        let categories: HashMap<String, Vec<String>> = [
+            ("ring", vec!["cryptography", "no-std"]),
+            ("rustls", vec!["cryptography", "network-programming"]),
+            ("libc", vec![]),
+        ]
+        .iter()
+        .map(|(name, c)| (name.to_string(), c.iter().map(|c| c.to_string()).collect()))
+        .collect();
+        let groups = group_by_category(&["ring", "rustls", "libc", "anyhow"], &categories);
+        let expected: BTreeMap<Option<&str>, Vec<usize>> = [
+            (None, vec![2, 3]),
+            (Some("cryptography"), vec![0, 1]),
+            (Some("network-programming"), vec![1]),
+            (Some("no-std"), vec![0]),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        assert_eq!(groups, expected);
+    }
+}
@@ -0,0 +1,215 @@
+//! `--dry-run` of the querying commands checks their configuration without contacting crates.io,
+//! for pre-flight checks in CI before the actual audit
+
+use crate::api_client::RateLimitedClient;
+use crate::cli::{MetadataArgs, QueryCommandArgs};
+use crate::common::sourced_dependencies_and_workspace_members;
+use crate::crates_cache::{CacheState, CratesCache};
+use crate::publishers::{crates_to_fetch, OFFLINE_NO_CACHE, PUBLISHER_TABLES};
+use crate::supplemental::SupplementalPublishers;
+use anyhow::bail;
+use std::{
+    collections::BTreeSet,
+    env,
+    io::{self, Write},
+    time::Duration,
+};
+
+/// What the command would look up
+pub enum DryRunTarget {
+    /// The dependencies of a workspace, for `crates`, `publishers`, `json` and `trust-graph`
+    Dependencies(MetadataArgs),
+    /// A single crate, for `inspect`
+    Crate(String),
+    /// The crates of a publisher, for `owned-by`. Those only ever come from the cache.
+    Owner(String),
+}
+
+pub fn dry_run(args: &QueryCommandArgs, target: DryRunTarget) -> Result<(), anyhow::Error> {
+    let mut cached = CratesCache::new();
+    dry_run_with_cache(&mut cached, args, target, &mut io::stdout().lock())
+}
+
+/// Writes to `out` what would be looked up, and whether from `cached` or the live API
+fn dry_run_with_cache(
+    cached: &mut CratesCache,
+    args: &QueryCommandArgs,
+    target: DryRunTarget,
+    out: &mut impl Write,
+) -> Result<(), anyhow::Error> {
+    // Fails the same way as the actual run on a missing or invalid file
+    if let Some(path) = &args.publishers_file {
+        SupplementalPublishers::load(Some(path))?;
+        writeln!(out, "publishers file: {}", path.display())?;
+    }
+    if let Some(path) = &args.tls.ca_bundle {
+        RateLimitedClient::with_tls(&args.tls)?;
+        writeln!(out, "CA bundle: {}", path.display())?;
+    }
+    if args.crates_io_token.is_some() {
+        writeln!(out, "crates.io API token: given")?;
+    }
+
+    let state = cached.expire(args.cache_max_age(), args.max_age_warning);
+    let usable = matches!(state, CacheState::Fresh | CacheState::FreshButAging(_))
+        && cached.prefetch(PUBLISHER_TABLES).is_ok();
+    match (&state, cached.age()) {
+        (CacheState::Fresh | CacheState::FreshButAging(_), Some(age)) if usable => writeln!(
+            out,
+            "cache: {}, {} old",
+            state.name(),
+            indicatif::HumanDuration(age)
+        ),
+        (CacheState::Fresh | CacheState::FreshButAging(_), _) => writeln!(out, "cache: incomplete"),
+        _ => writeln!(out, "cache: {}", state.name()),
+    }?;
+
+    let crate_names = match target {
+        DryRunTarget::Dependencies(metadata_args) => {
+            // `cargo metadata` would otherwise update the index if Cargo.lock is out of date
+            env::set_var("CARGO_NET_OFFLINE", "true");
+            let include_members = metadata_args.include_published_members;
+            let (dependencies, members) =
+                sourced_dependencies_and_workspace_members(metadata_args.clone())?;
+            writeln!(out, "dependencies: {}", dependencies.len())?;
+            if include_members {
+                // Whether they are published is only known from crates.io
+                writeln!(
+                    out,
+                    "workspace members to check for being published: {}",
+                    members.len()
+                )?;
+            }
+            crates_to_fetch(&dependencies, &BTreeSet::new(), &metadata_args, args)?
+        }
+        DryRunTarget::Crate(crate_name) => vec![crate_name],
+        DryRunTarget::Owner(login) => {
+            if !usable {
+                bail!("The `crates.io` cache is expired, missing or incomplete. Run `cargo supply-chain update` first.");
+            }
+            writeln!(out, "crates of {} would be looked up in the cache", login)?;
+            return Ok(());
+        }
+    };
+
+    let (from_cache, live): (Vec<&String>, Vec<&String>) =
+        crate_names.iter().partition(|crate_name| {
+            usable
+                && ((cached.publisher_users(crate_name).is_some()
+                    && cached.publisher_teams(crate_name).is_some())
+                    || cached.crate_exists(crate_name) == Some(true))
+        });
+    writeln!(out, "crates.io crates to look up: {}", crate_names.len())?;
+    writeln!(out, "  from the cache: {}", from_cache.len())?;
+    if usable && args.no_default_registry_fallback {
+        writeln!(
+            out,
+            "  skipped because of --no-default-registry-fallback: {}",
+            live.len()
+        )?;
+        return Ok(());
+    }
+    writeln!(out, "  from the live crates.io API: {}", live.len())?;
+    if !live.is_empty() {
+        if args.offline() {
+            if usable {
+                bail!(
+                    "{} crates are not in the cache; cannot fetch live data in --offline mode.",
+                    live.len()
+                );
+            }
+            bail!(OFFLINE_NO_CACHE);
+        }
+        // The API allows one request per second, and every crate takes two
+        writeln!(
+            out,
+            "estimated time to fetch: {}",
+            indicatif::HumanDuration(Duration::from_secs(2 * live.len() as u64))
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{args_parser, CliArgs};
+    use bpaf::Args;
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    /// A cache directory with the publisher tables, holding only `serde`
+    fn temp_cache_dir() -> PathBuf {
+        let dir = env::temp_dir().join(format!("supply-chain-dry-run-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let tables = [
+            (
+                "crates.json",
+                r#"{"serde": {"name": "serde", "id": 1, "repository": null}}"#,
+            ),
+            ("crate_owners.json", "{}"),
+            ("users.json", "{}"),
+            ("teams.json", "{}"),
+        ];
+        for (file, contents) in tables.iter() {
+            fs::write(dir.join(file), contents).unwrap();
+        }
+        dir
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn lists_targets_without_fetching() {
+        let dir = temp_cache_dir();
+        let files = file_names(&dir);
+        let run = |args: &[&str], target| {
+            let args = match args_parser().run_inner(Args::from(args)).unwrap() {
+                CliArgs::Inspect { args, .. } | CliArgs::OwnedBy { args, .. } => args,
+                _ => unreachable!(),
+            };
+            let mut cached = CratesCache::in_dir(dir.clone(), Duration::from_secs(3 * 3600));
+            let mut out = Vec::new();
+            dry_run_with_cache(&mut cached, &args, target, &mut out)
+                .map(|()| String::from_utf8(out).unwrap())
+        };
+
+        let crate_target = |name: &str| DryRunTarget::Crate(name.to_owned());
+        assert_eq!(
+            run(&["inspect", "serde", "--dry-run"], crate_target("serde")).unwrap(),
+            "cache: fresh, 3 hours old\n\
+             crates.io crates to look up: 1\n  from the cache: 1\n  from the live crates.io API: 0\n"
+        );
+        assert_eq!(
+            run(&["inspect", "rand", "--dry-run"], crate_target("rand")).unwrap(),
+            "cache: fresh, 3 hours old\n\
+             crates.io crates to look up: 1\n  from the cache: 0\n  from the live crates.io API: 1\n\
+             estimated time to fetch: 2 seconds\n"
+        );
+        assert_eq!(
+            run(
+                &["owned-by", "dtolnay", "--dry-run"],
+                DryRunTarget::Owner("dtolnay".to_owned())
+            )
+            .unwrap(),
+            "cache: fresh, 3 hours old\ncrates of dtolnay would be looked up in the cache\n"
+        );
+        // What is missing from the cache cannot be looked up offline
+        let offline = ["inspect", "rand", "--dry-run", "--offline"];
+        assert!(run(&offline, crate_target("rand")).is_err());
+        // Nothing was downloaded into the cache
+        assert_eq!(file_names(&dir), files);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
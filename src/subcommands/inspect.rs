@@ -0,0 +1,151 @@
+//! `inspect` subcommand reports everything known about the publishers of a single crate
+
+use crate::api_client::RateLimitedClient;
+use crate::cli::QueryCommandArgs;
+use crate::crates_cache::{CacheState, CratesCache};
+use crate::publishers::{
+    anonymize, anonymize_key, extract_github_org, print_anonymized, publisher_teams,
+    publisher_users, PublisherData, PUBLISHER_TABLES,
+};
+use anyhow::bail;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tracing::{info, warn};
+
+#[derive(Debug, Serialize)]
+struct InspectOutput {
+    name: String,
+    /// Repository URL declared by the crate. Only known when using the cache.
+    repository: Option<String>,
+    /// Whether the data comes from the local cache rather than the live crates.io API
+    from_cache: bool,
+    users: Vec<PublisherData>,
+    teams: Vec<PublisherData>,
+}
+
+pub fn inspect(crate_name: &str, args: QueryCommandArgs, json: bool) -> Result<(), anyhow::Error> {
+    let mut cached = CratesCache::new();
+    let state = cached.expire(args.cache_max_age(), args.max_age_warning);
+    if let CacheState::FreshButAging(age) = state {
+        warn!(
+            "Cache is {} old. Consider running `cargo supply-chain update` for more accurate results.",
+            indicatif::HumanDuration(age)
+        );
+    }
+    if let Some(message) = state.incompatibility() {
+        warn!("{}", message);
+    }
+    let using_cache = matches!(state, CacheState::Fresh | CacheState::FreshButAging(_))
+        && cached.prefetch(PUBLISHER_TABLES).is_ok();
+
+    let cached_owners = if using_cache {
+        match (
+            cached.publisher_users(crate_name),
+            cached.publisher_teams(crate_name),
+        ) {
+            (Some(users), Some(teams)) => Some((users, teams)),
+            // The crate is in the dump, but no owners are recorded for it
+            _ if cached.crate_exists(crate_name) == Some(true) => Some((Vec::new(), Vec::new())),
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    let mut output = InspectOutput {
+        name: crate_name.to_owned(),
+        repository: None,
+        from_cache: cached_owners.is_some(),
+        users: Vec::new(),
+        teams: Vec::new(),
+    };
+    match cached_owners {
+        Some((users, teams)) => {
+            info!("Using cached data for {}", crate_name);
+            output.repository = cached.crate_repository(crate_name);
+            output.users = users;
+            output.teams = teams;
+        }
+        None if args.offline() => bail!(
+            "Crate `{}` is not in the cache; cannot fetch live data in --offline mode.",
+            crate_name
+        ),
+        None => {
+            info!("Fetching publisher info for {} from crates.io", crate_name);
+            let mut client =
+                RateLimitedClient::with_tls(&args.tls)?.with_token(args.crates_io_token.clone());
+            output.users = publisher_users(&mut client, crate_name)?;
+            if !args.include_email {
+                output.users.iter_mut().for_each(|user| user.email = None);
+            }
+            output.teams = publisher_teams(&mut client, crate_name)?;
+        }
+    }
+    if args.anonymize {
+        let mut anonymized = BTreeMap::new();
+        let publishers = output.users.iter_mut().chain(output.teams.iter_mut());
+        anonymize(publishers, &anonymize_key(&args)?, &mut anonymized);
+        print_anonymized(&anonymized);
+    }
+    output.users.sort_unstable_by(|a, b| a.login.cmp(&b.login));
+    output.teams.sort_unstable_by(|a, b| a.login.cmp(&b.login));
+
+    if json {
+        let stdout = std::io::stdout();
+        let handle = stdout.lock();
+        if args.diffable {
+            serde_json::to_writer_pretty(handle, &output)?;
+        } else {
+            serde_json::to_writer(handle, &output)?;
+        }
+    } else {
+        print_report(&output, args.no_summary());
+    }
+    Ok(())
+}
+
+fn print_report(output: &InspectOutput, no_summary: bool) {
+    println!("Crate: {}", output.name);
+    println!("Page: https://crates.io/crates/{}", output.name);
+    match &output.repository {
+        Some(repository) => println!("Repository: {}", repository),
+        None if output.from_cache => println!("Repository: none declared"),
+        None => println!("Repository: unknown, only available from the cache"),
+    }
+    let source = if output.from_cache {
+        "local cache"
+    } else {
+        "crates.io API"
+    };
+    println!("Data source: {}", source);
+
+    println!("\nUsers that can publish updates:");
+    if output.users.is_empty() {
+        println!(" none");
+    }
+    for user in &output.users {
+        let login = user.display_safe_login();
+        match user.display_safe_name() {
+            Some(name) => println!(" - {}, {} (https://crates.io/users/{})", login, name, login),
+            None => println!(" - {} (https://crates.io/users/{})", login, login),
+        }
+    }
+
+    println!("\nTeams whose members can publish updates:");
+    if output.teams.is_empty() {
+        println!(" none");
+    }
+    for team in &output.teams {
+        let login = team.display_safe_login();
+        match extract_github_org(&login) {
+            Some(org) => println!(" - \"{}\" (https://github.com/{})", login, org),
+            None => println!(" - \"{}\"", login),
+        }
+    }
+    if !output.teams.is_empty() && !no_summary {
+        eprintln!("\nGithub teams are black boxes. It's impossible to get the member list without explicit permission.");
+    }
+    if output.users.is_empty() && output.teams.is_empty() {
+        warn!("{} has 0 owners on record - this is unusual.", output.name);
+    }
+}
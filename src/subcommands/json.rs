@@ -1,22 +1,65 @@
 //! `json` subcommand is equivalent to `crates`,
 //! but provides structured output and more info about each publisher.
-use crate::publishers::{fetch_owners_of_crates, PublisherData};
+use crate::cli::{QueryCommandArgs, SaveOnSuccess};
+use crate::crates_cache::{CacheState, CratesCache};
+use crate::output::{export_graph::Graph, template, ExportGraphFormat, JsonFormat};
+use crate::publishers::{
+    fetch_owners_of_crates, fetch_owners_of_crates_with, published_workspace_members, PublisherData,
+};
+use crate::subcommands::json_schema::write_schema;
+use crate::summary::PublisherSummary;
+use crate::supplemental::{SupplementalPublisher, SupplementalPublishers};
 use crate::{
-    common::{crate_names_from_source, sourced_dependencies, PkgSource},
+    common::{crate_names_from_source, sourced_dependencies_and_workspace_members, PkgSource},
     MetadataArgs,
 };
+use anyhow::{bail, Context};
 use serde::Serialize;
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
 
 #[cfg(test)]
 use schemars::JsonSchema;
 
+/// The values of `--format`, listed by `--help-formats`
+pub const FORMATS: &[(&str, &str)] = &[
+    (
+        "json",
+        "JSON document described by `--print-schema`, printed once all crates are processed. The default",
+    ),
+    (
+        "ndjson",
+        "One JSON object per line, printed as soon as each crate is processed",
+    ),
+];
+
 #[cfg_attr(test, derive(JsonSchema))]
 #[derive(Debug, Serialize, Default, Clone)]
 pub struct StructuredOutput {
+    /// When the analysis was run, in RFC 3339 format. Omitted with `--diffable`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    audit_timestamp: Option<String>,
     not_audited: NotAudited,
+    /// Names of the crates.io crates that are missing from the cache and were not fetched
+    /// from the live API because of `--no-default-registry-fallback`
+    not_fetched: Vec<String>,
     /// Maps crate names to info about the publishers of each crate
     crates_io_crates: BTreeMap<String, Vec<PublisherData>>,
+    /// Maps the logins of publishers to the data about them from `--publishers-file`.
+    /// Only present if the file was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    supplemental_publishers: Option<BTreeMap<String, SupplementalPublisher>>,
+    /// Maps crate names to the repository URL each crate declares. The URLs come from the
+    /// database dump, so they are `null` when the cache is not used
+    repositories: BTreeMap<String, Option<String>>,
+    /// Names of all the packages in the workspace, whether or not they were analyzed
+    workspace_members: Vec<String>,
 }
 
 #[cfg_attr(test, derive(JsonSchema))]
@@ -24,41 +67,466 @@ pub struct StructuredOutput {
 pub struct NotAudited {
     /// Names of crates that are imported from a location in the local filesystem, not from a registry
     local_crates: Vec<String>,
-    /// Names of crates that are neither from crates.io nor from a local filesystem
+    /// Crates that are imported from a git repository
+    git_crates: Vec<GitCrateInfo>,
+    /// Names of crates that are neither from crates.io, nor from a git repository, nor from a local filesystem
     foreign_crates: Vec<String>,
 }
 
+#[cfg_attr(test, derive(JsonSchema))]
+#[derive(Debug, Serialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GitCrateInfo {
+    name: String,
+    /// URL of the git repository
+    url: String,
+    /// The exact commit that is used
+    rev: String,
+}
+
+/// Prints the example template of `--output-template`, for `json --print-template`
+pub fn print_template() {
+    print!("{}", template::DEFAULT_TEMPLATE);
+}
+
 pub fn json(
-    args: MetadataArgs,
-    diffable: bool,
-    max_age: std::time::Duration,
+    metadata_args: MetadataArgs,
+    args: QueryCommandArgs,
+    save: Option<SaveOnSuccess>,
+    format: JsonFormat,
+    export_graph: Option<ExportGraphFormat>,
+    output_template: Option<PathBuf>,
+    output_schema: Option<PathBuf>,
 ) -> Result<(), anyhow::Error> {
+    if format == JsonFormat::Ndjson && (args.summary || save.is_some()) {
+        bail!("`--format ndjson` cannot be combined with `--summary` or `--save-on-success`");
+    }
+    if output_template.is_some()
+        && (format == JsonFormat::Ndjson || args.summary || export_graph.is_some())
+    {
+        bail!("`--output-template` cannot be combined with `--format ndjson`, `--summary` or `--export-graph`");
+    }
+    // The schema only describes the JSON document printed by default
+    if output_schema.is_some()
+        && (format == JsonFormat::Ndjson
+            || args.summary
+            || export_graph.is_some()
+            || output_template.is_some())
+    {
+        bail!("`--output-schema` cannot be combined with `--format ndjson`, `--summary`, `--export-graph` or `--output-template`");
+    }
+    // Read the template before spending minutes on fetching the data
+    let template = match output_template {
+        Some(path) => match fs::read_to_string(&path) {
+            Ok(contents) => Some((path.display().to_string(), contents)),
+            Err(error) => bail!("Could not read template {}: {}", path.display(), error),
+        },
+        None => None,
+    };
+    if let Some(graph_format) = export_graph {
+        if format == JsonFormat::Ndjson || args.summary || save.is_some() {
+            bail!("`--export-graph` cannot be combined with `--format ndjson`, `--summary` or `--save-on-success`");
+        }
+        let include_members = metadata_args.include_published_members;
+        let (dependencies, _) = sourced_dependencies_and_workspace_members(metadata_args.clone())?;
+        let members = published_workspace_members(include_members, &dependencies, &args)?;
+        let (mut owners, teams) =
+            fetch_owners_of_crates(&dependencies, &members, &metadata_args, &args)?;
+        for (crate_name, publishers) in teams {
+            owners.entry(crate_name).or_default().extend(publishers);
+        }
+        let graph = Graph::new(&dependencies, &owners);
+        let stdout = io::stdout();
+        graph.write(graph_format, &mut stdout.lock(), args.diffable)?;
+        return Ok(());
+    }
+    let supplemental = match &args.publishers_file {
+        Some(path) => Some(SupplementalPublishers::load(Some(path))?),
+        None => None,
+    };
     let mut output = StructuredOutput::default();
-    let dependencies = sourced_dependencies(args)?;
+    // A timestamp would make every run differ from the previous one
+    if !args.diffable {
+        output.audit_timestamp =
+            Some(humantime::format_rfc3339_seconds(SystemTime::now()).to_string());
+    }
+    let include_members = metadata_args.include_published_members;
+    let (dependencies, workspace_members) =
+        sourced_dependencies_and_workspace_members(metadata_args.clone())?;
+    output.workspace_members = workspace_members;
+    let members = published_workspace_members(include_members, &dependencies, &args)?;
     // Report non-crates.io dependencies
-    output.not_audited.local_crates = crate_names_from_source(&dependencies, PkgSource::Local);
+    // Path dependencies outside the workspace are in the local filesystem too
+    output.not_audited.local_crates = dependencies
+        .iter()
+        .filter(|p| matches!(p.source, PkgSource::Local | PkgSource::Path { .. }))
+        .map(|p| p.package.name.clone())
+        .filter(|name| !members.contains(name))
+        .collect();
     output.not_audited.foreign_crates = crate_names_from_source(&dependencies, PkgSource::Foreign);
+    output.not_audited.git_crates = dependencies
+        .iter()
+        .filter_map(|p| match &p.source {
+            PkgSource::Git { url, rev } => Some(GitCrateInfo {
+                name: p.package.name.clone(),
+                url: url.clone(),
+                rev: rev.clone(),
+            }),
+            _ => None,
+        })
+        .collect();
+    output.not_audited.git_crates.sort_unstable();
+    output.not_audited.git_crates.dedup();
     output.not_audited.local_crates.sort_unstable();
+    output.not_audited.local_crates.dedup();
     output.not_audited.foreign_crates.sort_unstable();
-    // Fetch list of owners and publishers
-    let (mut owners, publisher_teams) = fetch_owners_of_crates(&dependencies, max_age)?;
-    // Merge the two maps we received into one
-    for (crate_name, publishers) in publisher_teams {
-        owners.entry(crate_name).or_default().extend(publishers);
-    }
-    // Sort the vectors of publisher data. This helps when diffing the output,
-    // but we do it unconditionally because it's cheap and helps users pull less hair when debugging.
-    for list in owners.values_mut() {
-        list.sort_unstable_by_key(|x| x.id);
-    }
-    output.crates_io_crates = owners;
-    // Print the result to stdout
-    let stdout = std::io::stdout();
-    let handle = stdout.lock();
-    if diffable {
-        serde_json::to_writer_pretty(handle, &output)?;
-    } else {
-        serde_json::to_writer(handle, &output)?;
+
+    // Repository URLs are only in the database dump, so looking them up costs no API calls
+    let mut cache = CratesCache::new();
+    let cache = match cache.expire(args.cache_max_age(), None) {
+        CacheState::Fresh | CacheState::FreshButAging(_) => Some(cache),
+        _ => None,
+    };
+    let mut writer: Box<dyn JsonOutputWriter> = match format {
+        JsonFormat::Json => Box::new(StructuredOutputWriter {
+            output,
+            cache,
+            diffable: args.diffable,
+            summary: args.summary,
+            since_baseline: args.since_commit.is_some(),
+            save,
+            template,
+            supplemental,
+        }),
+        JsonFormat::Ndjson => Box::new(NdJsonWriter { out: io::stdout() }),
+    };
+    // Fetch list of owners and publishers, handing each crate to the writer as soon as it's known
+    let not_fetched = fetch_owners_of_crates_with(
+        &dependencies,
+        &members,
+        &metadata_args,
+        &args,
+        |crate_name, users, teams| {
+            let mut publishers = users;
+            publishers.extend(teams);
+            // Sort the vectors of publisher data. This helps when diffing the output,
+            // but we do it unconditionally because it's cheap and helps users pull less hair when debugging.
+            publishers.sort_unstable_by_key(|x| x.id);
+            writer.write_crate_entry(crate_name, publishers)
+        },
+    )?;
+    writer.finish(not_fetched)?;
+    // Written last, so that a failed run does not leave a schema without output behind
+    if let Some(path) = output_schema {
+        write_schema(&path)
+            .with_context(|| format!("Could not write the schema to {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Receives the publishers of each crate as they are fetched and writes them out
+trait JsonOutputWriter {
+    fn write_crate_entry(
+        &mut self,
+        crate_name: &str,
+        publishers: Vec<PublisherData>,
+    ) -> Result<(), io::Error>;
+
+    /// Called once all crates have been processed, with the ones that were not fetched
+    fn finish(self: Box<Self>, not_fetched: Vec<String>) -> Result<(), anyhow::Error>;
+}
+
+/// Collects everything into a [`StructuredOutput`] and prints it at the end
+struct StructuredOutputWriter {
+    output: StructuredOutput,
+    diffable: bool,
+    summary: bool,
+    /// Whether only the crates added since `--since-commit` are listed
+    since_baseline: bool,
+    save: Option<SaveOnSuccess>,
+    /// Name and contents of the `--output-template`, rendered instead of printing JSON
+    template: Option<(String, String)>,
+    supplemental: Option<SupplementalPublishers>,
+    /// The fresh cache to look up repository URLs in, if there is one
+    cache: Option<CratesCache>,
+}
+
+impl JsonOutputWriter for StructuredOutputWriter {
+    fn write_crate_entry(
+        &mut self,
+        crate_name: &str,
+        publishers: Vec<PublisherData>,
+    ) -> Result<(), io::Error> {
+        let repository = self
+            .cache
+            .as_mut()
+            .and_then(|cache| cache.crate_repository(crate_name));
+        self.output
+            .repositories
+            .insert(crate_name.to_owned(), repository);
+        self.output
+            .crates_io_crates
+            .insert(crate_name.to_owned(), publishers);
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>, not_fetched: Vec<String>) -> Result<(), anyhow::Error> {
+        let mut output = self.output;
+        output.not_fetched = not_fetched;
+        if let Some(supplemental) = &self.supplemental {
+            output.supplemental_publishers =
+                Some(supplemental.of(output.crates_io_crates.values().flatten()));
+        }
+        // Publishers carry their kind, so there is no need to tell users and teams apart here
+        let summary = self.summary.then(|| {
+            PublisherSummary::new(
+                &output.crates_io_crates,
+                &BTreeMap::new(),
+                self.since_baseline,
+            )
+        });
+        // Print the result to stdout
+        let stdout = std::io::stdout();
+        let mut handle = stdout.lock();
+        if let Some((name, contents)) = &self.template {
+            let text = template::render(
+                name,
+                contents,
+                &output,
+                &output.crates_io_crates,
+                CratesCache::new().age(),
+            )?;
+            handle.write_all(text.as_bytes())?;
+        } else if let Some(summary) = &summary {
+            if self.diffable {
+                serde_json::to_writer_pretty(handle, summary)?;
+            } else {
+                serde_json::to_writer(handle, summary)?;
+            }
+        } else if self.diffable {
+            serde_json::to_writer_pretty(handle, &output)?;
+        } else {
+            serde_json::to_writer(handle, &output)?;
+        }
+        if let Some(save) = self.save {
+            save_snapshot(&output, &save.save_on_success)?;
+            if let Some(keep_last) = save.keep_last {
+                prune_snapshots(&save.save_on_success, keep_last)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A single line of `--format ndjson` output
+#[derive(Serialize)]
+struct CrateEntry<'a> {
+    #[serde(rename = "crate")]
+    crate_name: &'a str,
+    publishers: &'a [PublisherData],
+}
+
+/// Writes one line per crate immediately, without buffering
+struct NdJsonWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JsonOutputWriter for NdJsonWriter<W> {
+    fn write_crate_entry(
+        &mut self,
+        crate_name: &str,
+        publishers: Vec<PublisherData>,
+    ) -> Result<(), io::Error> {
+        let entry = CrateEntry {
+            crate_name,
+            publishers: &publishers,
+        };
+        serde_json::to_writer(&mut self.out, &entry)?;
+        writeln!(self.out)?;
+        self.out.flush()
+    }
+
+    /// The crates that were not fetched are only reported on stderr, since every line is a crate
+    fn finish(mut self: Box<Self>, _not_fetched: Vec<String>) -> Result<(), anyhow::Error> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+const SNAPSHOT_PREFIX: &str = "supply-chain-";
+const SNAPSHOT_SUFFIX: &str = ".json";
+
+/// The file name of the snapshot taken at `time`, e.g. `supply-chain-20210518T143000.123456Z.json`
+fn snapshot_name(time: SystemTime) -> String {
+    // ISO 8601 basic format. Unlike the extended format it contains no colons,
+    // so it is a valid file name on Windows too. Its width is fixed, so sorting
+    // the file names sorts the snapshots chronologically.
+    let timestamp: String = humantime::format_rfc3339_micros(time)
+        .to_string()
+        .chars()
+        .filter(|c| !matches!(c, '-' | ':'))
+        .collect();
+    format!("{}{}{}", SNAPSHOT_PREFIX, timestamp, SNAPSHOT_SUFFIX)
+}
+
+/// Writes the output in `--diffable` format to `{dir}/supply-chain-{timestamp}.json`,
+/// creating `dir` if needed. Returns the path of the new snapshot.
+fn save_snapshot(output: &StructuredOutput, dir: &Path) -> Result<PathBuf, anyhow::Error> {
+    fs::create_dir_all(dir)?;
+    let mut time = SystemTime::now();
+    let (path, file) = loop {
+        let path = dir.join(snapshot_name(time));
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => break (path, file),
+            // Saved within the same microsecond, so take the next one, which still sorts after it
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                time += Duration::from_micros(1);
+            }
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("Could not create snapshot {}", path.display()))
+            }
+        }
+    };
+    serde_json::to_writer_pretty(io::BufWriter::new(file), output)?;
+    eprintln!("Saved a snapshot of the output to {}", path.display());
+    Ok(path)
+}
+
+/// Deletes all but the `keep_last` most recent snapshots in `dir`.
+/// Files not created by `save_snapshot` are left alone.
+fn prune_snapshots(dir: &Path, keep_last: NonZeroUsize) -> Result<(), io::Error> {
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with(SNAPSHOT_PREFIX) && name.ends_with(SNAPSHOT_SUFFIX) {
+            snapshots.push(entry.path());
+        }
+    }
+    snapshots.sort_unstable();
+    let excess = snapshots.len().saturating_sub(keep_last.get());
+    for path in &snapshots[..excess] {
+        fs::remove_file(path)?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::publishers::{publisher, PublisherKind};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn ndjson_lines() {
+        let mut writer = NdJsonWriter { out: Vec::new() };
+        let publishers = vec![
+            publisher(1, "dtolnay", PublisherKind::user),
+            // Escaped in JSON, so it cannot break the line
+            publisher(2, "github:serde-rs:\nowners", PublisherKind::team),
+        ];
+        writer.write_crate_entry("serde", publishers).unwrap();
+        writer.write_crate_entry("orphan", Vec::new()).unwrap();
+
+        let output = String::from_utf8(writer.out).unwrap();
+        assert!(output.ends_with('\n'));
+        let lines: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["crate"], "serde");
+        assert_eq!(lines[0]["publishers"][0]["login"], "dtolnay");
+        assert_eq!(
+            lines[0]["publishers"][1]["login"],
+            "github:serde-rs:\nowners"
+        );
+        assert_eq!(lines[1], json!({ "crate": "orphan", "publishers": [] }));
+    }
+
+    /// An empty directory of its own for each test
+    fn temp_snapshot_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "supply-chain-snapshots-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn snapshot_names_sort_chronologically() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_621_348_200);
+        assert_eq!(
+            snapshot_name(time),
+            "supply-chain-20210518T143000.000000Z.json"
+        );
+        let times = [
+            time,
+            time + Duration::from_micros(1),
+            time + Duration::from_millis(999),
+            time + Duration::from_secs(1),
+            time + Duration::from_secs(86_400),
+        ];
+        let names: Vec<String> = times.iter().map(|time| snapshot_name(*time)).collect();
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted, names);
+    }
+
+    #[test]
+    fn snapshots_are_never_overwritten() {
+        let dir = temp_snapshot_dir("overwrite");
+        let output = StructuredOutput::default();
+        let saved: Vec<PathBuf> = (0..3)
+            .map(|_| save_snapshot(&output, &dir).unwrap())
+            .collect();
+        let names: Vec<String> = saved
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        // Saved in quick succession, but each in a file of its own, in order
+        assert_eq!(file_names(&dir), names);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn prune_old_snapshots() {
+        let dir = temp_snapshot_dir("prune");
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_621_348_200);
+        let names: Vec<String> = (0..3)
+            .map(|days| snapshot_name(time + Duration::from_secs(days * 86_400)))
+            .collect();
+        for name in names.iter().chain(["notes.json".to_owned()].iter()) {
+            fs::write(dir.join(name), "{}").unwrap();
+        }
+
+        prune_snapshots(&dir, NonZeroUsize::new(5).unwrap()).unwrap();
+        assert_eq!(file_names(&dir).len(), 4);
+        // The oldest snapshot goes, other files stay
+        prune_snapshots(&dir, NonZeroUsize::new(2).unwrap()).unwrap();
+        assert_eq!(
+            file_names(&dir),
+            ["notes.json", names[1].as_str(), names[2].as_str()]
+        );
+        prune_snapshots(&dir, NonZeroUsize::new(1).unwrap()).unwrap();
+        assert_eq!(file_names(&dir), ["notes.json", names[2].as_str()]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
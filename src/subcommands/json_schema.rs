@@ -1,21 +1,40 @@
 //! The schema for the JSON subcommand output
 
-use std::io::{Result, Write};
+use std::{
+    fs,
+    io::{Result, Write},
+    path::Path,
+};
 
 pub fn print_schema() -> Result<()> {
     writeln!(std::io::stdout(), "{}", JSON_SCHEMA)?;
     Ok(())
 }
 
+/// Writes the schema to a file, for `--output-schema`
+pub fn write_schema(path: &Path) -> Result<()> {
+    fs::write(path, format!("{}\n", JSON_SCHEMA))
+}
+
 const JSON_SCHEMA: &str = r##"{
   "$schema": "http://json-schema.org/draft-07/schema#",
   "title": "StructuredOutput",
   "type": "object",
   "required": [
     "crates_io_crates",
-    "not_audited"
+    "not_audited",
+    "not_fetched",
+    "repositories",
+    "workspace_members"
   ],
   "properties": {
+    "audit_timestamp": {
+      "description": "When the analysis was run, in RFC 3339 format. Omitted with `--diffable`",
+      "type": [
+        "string",
+        "null"
+      ]
+    },
     "crates_io_crates": {
       "description": "Maps crate names to info about the publishers of each crate",
       "type": "object",
@@ -28,23 +47,86 @@ const JSON_SCHEMA: &str = r##"{
     },
     "not_audited": {
       "$ref": "#/definitions/NotAudited"
+    },
+    "not_fetched": {
+      "description": "Names of the crates.io crates that are missing from the cache and were not fetched from the live API because of `--no-default-registry-fallback`",
+      "type": "array",
+      "items": {
+        "type": "string"
+      }
+    },
+    "repositories": {
+      "description": "Maps crate names to the repository URL each crate declares. The URLs come from the database dump, so they are `null` when the cache is not used",
+      "type": "object",
+      "additionalProperties": {
+        "type": [
+          "string",
+          "null"
+        ]
+      }
+    },
+    "supplemental_publishers": {
+      "description": "Maps the logins of publishers to the data about them from `--publishers-file`. Only present if the file was given",
+      "type": [
+        "object",
+        "null"
+      ],
+      "additionalProperties": {
+        "$ref": "#/definitions/SupplementalPublisher"
+      }
+    },
+    "workspace_members": {
+      "description": "Names of all the packages in the workspace, whether or not they were analyzed",
+      "type": "array",
+      "items": {
+        "type": "string"
+      }
     }
   },
   "definitions": {
+    "GitCrateInfo": {
+      "type": "object",
+      "required": [
+        "name",
+        "rev",
+        "url"
+      ],
+      "properties": {
+        "name": {
+          "type": "string"
+        },
+        "rev": {
+          "description": "The exact commit that is used",
+          "type": "string"
+        },
+        "url": {
+          "description": "URL of the git repository",
+          "type": "string"
+        }
+      }
+    },
     "NotAudited": {
       "type": "object",
       "required": [
         "foreign_crates",
+        "git_crates",
         "local_crates"
       ],
       "properties": {
         "foreign_crates": {
-          "description": "Names of crates that are neither from crates.io nor from a local filesystem",
+          "description": "Names of crates that are neither from crates.io, nor from a git repository, nor from a local filesystem",
           "type": "array",
           "items": {
             "type": "string"
           }
         },
+        "git_crates": {
+          "description": "Crates that are imported from a git repository",
+          "type": "array",
+          "items": {
+            "$ref": "#/definitions/GitCrateInfo"
+          }
+        },
         "local_crates": {
           "description": "Names of crates that are imported from a location in the local filesystem, not from a registry",
           "type": "array",
@@ -70,6 +152,13 @@ const JSON_SCHEMA: &str = r##"{
             "null"
           ]
         },
+        "email": {
+          "description": "Email address. Only present with `--include-email` when using the live crates.io API, never when using the cache",
+          "type": [
+            "string",
+            "null"
+          ]
+        },
         "id": {
           "type": "integer",
           "format": "uint64",
@@ -96,6 +185,38 @@ const JSON_SCHEMA: &str = r##"{
         "team",
         "user"
       ]
+    },
+    "SupplementalPublisher": {
+      "description": "Supplemental data about a publisher, maintained by your organization",
+      "type": "object",
+      "required": [
+        "trusted",
+        "verified_name"
+      ],
+      "properties": {
+        "email": {
+          "description": "Verified email address",
+          "type": [
+            "string",
+            "null"
+          ]
+        },
+        "organization": {
+          "description": "Organization the publisher belongs to",
+          "type": [
+            "string",
+            "null"
+          ]
+        },
+        "trusted": {
+          "description": "Whether your organization trusts the publisher",
+          "type": "boolean"
+        },
+        "verified_name": {
+          "description": "Real name of the publisher, as verified by your organization",
+          "type": "string"
+        }
+      }
     }
   }
 }"##;
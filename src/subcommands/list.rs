@@ -0,0 +1,56 @@
+//! `--list` prints the subcommands in the same format as `cargo --list`
+
+const SUBCOMMANDS: &[(&str, &str)] = &[
+    (
+        "publishers",
+        "Lists all crates.io publishers in the dependency graph and owned crates for each",
+    ),
+    (
+        "crates",
+        "List all crates in dependency graph and crates.io publishers for each",
+    ),
+    (
+        "json",
+        "Detailed info on publishers of all crates in the dependency graph, in JSON",
+    ),
+    (
+        "inspect",
+        "Detailed info on the publishers of a single crate, which need not be a dependency",
+    ),
+    (
+        "owned-by",
+        "List all crates on crates.io that a user or team can publish",
+    ),
+    (
+        "trust-graph",
+        "Graph of the crates in the dependency graph and their crates.io publishers",
+    ),
+    (
+        "prune",
+        "Delete stale files from the local cache without downloading anything",
+    ),
+    (
+        "update",
+        "Download the latest daily dump from crates.io to speed up other commands",
+    ),
+    (
+        "cache-info",
+        "Show the location, age and contents of the local cache",
+    ),
+    (
+        "generate-completions",
+        "Print a script that makes the shell complete subcommands and flags",
+    ),
+];
+
+pub fn list() {
+    let width = SUBCOMMANDS
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    println!("Available subcommands:");
+    for (name, description) in SUBCOMMANDS {
+        println!("    {:width$}  {}", name, description, width = width);
+    }
+}
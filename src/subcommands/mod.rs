@@ -1,11 +1,31 @@
+pub mod cache_info;
+pub mod completions;
 pub mod crates;
+pub mod dry_run;
+pub mod inspect;
 pub mod json;
 pub mod json_schema;
+pub mod list;
+pub mod owned_by;
+pub mod prune;
 pub mod publishers;
+pub mod trust_graph;
 pub mod update;
+pub mod version;
+pub mod write_config;
 
+pub use cache_info::cache_info;
+pub use completions::generate_completions;
 pub use crates::crates;
-pub use json::json;
+pub use dry_run::{dry_run, DryRunTarget};
+pub use inspect::inspect;
+pub use json::{json, print_template};
 pub use json_schema::print_schema;
+pub use list::list;
+pub use owned_by::owned_by;
+pub use prune::prune;
 pub use publishers::publishers;
-pub use update::update;
+pub use trust_graph::trust_graph;
+pub use update::{update, update_check};
+pub use version::version_verbose;
+pub use write_config::write_config;
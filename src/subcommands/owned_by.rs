@@ -0,0 +1,41 @@
+//! `owned-by` subcommand is the inverse of `crates`: it lists the crates of a given publisher
+
+use crate::cli::QueryCommandArgs;
+use crate::crates_cache::{CacheState, CratesCache};
+use crate::publishers::PublisherKind;
+use anyhow::bail;
+use tracing::warn;
+
+pub fn owned_by(
+    login: &str,
+    kind: Option<PublisherKind>,
+    args: QueryCommandArgs,
+) -> Result<(), anyhow::Error> {
+    let mut cache = CratesCache::new();
+    let state = cache.expire(args.cache_max_age(), args.max_age_warning);
+    match state {
+        CacheState::Fresh => {}
+        CacheState::FreshButAging(age) => warn!(
+            "Cache is {} old. Consider running `cargo supply-chain update` for more accurate results.",
+            indicatif::HumanDuration(age)
+        ),
+        CacheState::Incompatible(_) => bail!("{}", state.incompatibility().unwrap()),
+        CacheState::Expired | CacheState::Unknown => bail!(
+            "The `crates.io` cache is expired or missing. Run `cargo supply-chain update` first."
+        ),
+    }
+    let crates = match cache.crates_owned_by(login, kind) {
+        Some(crates) => crates,
+        None => bail!(
+            "The `crates.io` cache is incomplete. Run `cargo supply-chain update` to regenerate it."
+        ),
+    };
+
+    if !args.diffable {
+        println!("\n{} can publish {} crates:\n", login, crates.len());
+    }
+    for crate_name in &crates {
+        println!("{}", crate_name);
+    }
+    Ok(())
+}
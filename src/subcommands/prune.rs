@@ -0,0 +1,105 @@
+use crate::crates_cache::CratesCache;
+use std::{
+    fs,
+    io::{self, Write},
+    time::Duration,
+};
+
+pub fn prune(older_than: Option<Duration>, dry_run: bool) -> Result<(), anyhow::Error> {
+    let mut cache = CratesCache::new();
+    prune_cache(&mut cache, older_than, dry_run, &mut io::stdout().lock())
+}
+
+/// Deletes the stale files of `cache`, or only lists them with `dry_run`, reporting each on `out`
+fn prune_cache(
+    cache: &mut CratesCache,
+    older_than: Option<Duration>,
+    dry_run: bool,
+    out: &mut impl Write,
+) -> Result<(), anyhow::Error> {
+    let files = cache.stale_files(older_than)?;
+    if files.is_empty() {
+        eprintln!("Nothing to prune");
+        return Ok(());
+    }
+    for path in &files {
+        if dry_run {
+            writeln!(out, "Would delete {}", path.display())?;
+        } else {
+            fs::remove_file(path)?;
+            writeln!(out, "Deleted {}", path.display())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    /// A cache directory of its own, with a table and a leftover of an interrupted download
+    fn temp_cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "supply-chain-prune-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for file in ["crates.json", "crates.json.part"].iter() {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        dir
+    }
+
+    fn prune_output(dir: &Path, older_than: Option<Duration>, dry_run: bool) -> Vec<String> {
+        let mut cache = CratesCache::in_dir(dir.to_owned(), Duration::from_secs(3600));
+        let mut out = Vec::new();
+        prune_cache(&mut cache, older_than, dry_run, &mut out).unwrap();
+        let prefix = format!("{}{}", dir.display(), std::path::MAIN_SEPARATOR);
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| line.replacen(&prefix, "", 1))
+            .collect()
+    }
+
+    #[test]
+    fn selects_by_age() {
+        let dir = temp_cache_dir("age");
+        let hours = |n| Some(Duration::from_secs(n * 3600));
+        // The cache is an hour old, so only the leftover is stale unless that is too old
+        assert_eq!(
+            prune_output(&dir, None, true),
+            ["Would delete crates.json.part"]
+        );
+        assert_eq!(
+            prune_output(&dir, hours(2), true),
+            ["Would delete crates.json.part"]
+        );
+        assert_eq!(
+            prune_output(&dir, Some(Duration::from_secs(60)), true),
+            ["Would delete crates.json", "Would delete crates.json.part"]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn dry_run_deletes_nothing() {
+        let dir = temp_cache_dir("dry-run");
+        let older_than = Some(Duration::from_secs(60));
+        prune_output(&dir, older_than, true);
+        assert!(dir.join("crates.json").exists());
+        assert!(dir.join("crates.json.part").exists());
+
+        assert_eq!(
+            prune_output(&dir, older_than, false),
+            ["Deleted crates.json", "Deleted crates.json.part"]
+        );
+        assert!(!dir.join("crates.json").exists());
+        assert!(!dir.join("crates.json.part").exists());
+        assert!(prune_output(&dir, older_than, false).is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,82 +1,494 @@
 use std::collections::BTreeMap;
 
-use crate::publishers::fetch_owners_of_crates;
+use anyhow::bail;
+use serde::Serialize;
+
+#[cfg(test)]
+use schemars::JsonSchema;
+
+use crate::cli::{
+    AlignArgs, OwnerCountArgs, PublishersArgs, QueryCommandArgs, ReportingArgs, TrustLevel,
+};
+use crate::crates_cache::CratesCache;
+use crate::output::{fields, ColorThreshold, OutputFormat};
+use crate::publishers::{
+    anonymize, anonymize_key, extract_github_org, fetch_owners_of_crates, print_anonymized,
+    published_workspace_members, retain_by_owner_count,
+};
+use crate::summary::{diffable_header, print_counts, PublisherSummary};
+use crate::supplemental::SupplementalPublishers;
+use crate::webhook::{self, ViolatingPublisher, Violation};
 use crate::MetadataArgs;
 use crate::{
-    common::{comma_separated_list, complain_about_non_crates_io_crates, sourced_dependencies},
+    common::{
+        complain_about_non_crates_io_crates, separated_list, sourced_dependencies, workspace_root,
+        PkgSource, SourcedPackage,
+    },
     publishers::PublisherData,
 };
 
+/// The output of `publishers --format json`
+#[cfg_attr(test, derive(JsonSchema))]
+#[derive(Debug, Serialize)]
+struct PublishersOutput {
+    /// Publishers along with the crates each of them can publish
+    publishers: Vec<PublisherCrates>,
+}
+
+#[cfg_attr(test, derive(JsonSchema))]
+#[derive(Debug, Serialize)]
+struct PublisherCrates {
+    publisher: PublisherData,
+    /// Names of the crates that the publisher can publish
+    crates: Vec<String>,
+    /// Maps the names of the crates to their versions in the dependency graph.
+    /// Only present with `--show-versions`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    versions: Option<BTreeMap<String, Vec<String>>>,
+}
+
+/// The values of `--format`, listed by `--help-formats`
+pub const FORMATS: &[(&str, &str)] = &[
+    (
+        "text",
+        "Numbered list of the publishers and their crates, the default",
+    ),
+    ("json", "JSON document described by `--print-schema`"),
+];
+
+/// Returns whether `--check` passed, i.e. no publishers were listed. Always `true` without it.
 pub fn publishers(
     metadata_args: MetadataArgs,
-    diffable: bool,
-    max_age: std::time::Duration,
-) -> Result<(), anyhow::Error> {
-    let dependencies = sourced_dependencies(metadata_args)?;
-    complain_about_non_crates_io_crates(&dependencies);
-    let (publisher_users, publisher_teams) = fetch_owners_of_crates(&dependencies, max_age)?;
+    args: QueryCommandArgs,
+    owner_count: OwnerCountArgs,
+    publishers_args: PublishersArgs,
+    reporting: ReportingArgs,
+    count: bool,
+) -> Result<bool, anyhow::Error> {
+    let supported = [OutputFormat::Text, OutputFormat::Json];
+    let format = OutputFormat::flag_or_env(publishers_args.format, args.config_format, &supported)?
+        .unwrap_or(OutputFormat::Text);
+    if publishers_args.print_schema {
+        if format != OutputFormat::Json {
+            bail!("`--print-schema` requires `--format json`");
+        }
+        println!("{}", JSON_SCHEMA);
+        return Ok(true);
+    }
+    if matches!(
+        format,
+        OutputFormat::GithubActions | OutputFormat::GithubMarkdown
+    ) {
+        bail!(
+            "`--format {}` is only supported by the `crates` subcommand",
+            format.name()
+        );
+    }
+    if count && args.diffable {
+        bail!("`--count` cannot be combined with `--diffable`");
+    }
+    if count && publishers_args.format.is_some() {
+        bail!("`--count` cannot be combined with `--format`");
+    }
+    if count && publishers_args.check {
+        bail!("`--check` cannot be combined with `--count`");
+    }
+    if publishers_args.webhook.is_some() && !publishers_args.check {
+        bail!("`--webhook` requires `--check`");
+    }
+    if count && publishers_args.show_versions {
+        bail!("`--show-versions` cannot be combined with `--count`");
+    }
+    if publishers_args.fields.is_some() && publishers_args.show_versions {
+        bail!(
+            "`--show-versions` cannot be combined with `--fields`, use the `version` field instead"
+        );
+    }
+    if publishers_args.only_users && publishers_args.only_teams {
+        bail!("`--only-users` cannot be combined with `--only-teams`");
+    }
+    if publishers_args.align.align && args.diffable {
+        bail!("`--align` cannot be combined with `--diffable`");
+    }
+    let trust_tiers = publishers_args.trust_tiers();
+    let colors = publishers_args.colors();
+    if let Some(tiers) = trust_tiers {
+        if tiers.medium > tiers.high {
+            bail!("`--trust-medium-min` cannot be greater than `--trust-high-min`");
+        }
+    }
+    if publishers_args.prepend_headers
+        && (publishers_args.fields.is_none() || format == OutputFormat::Json)
+    {
+        bail!("`--prepend-headers` requires `--fields` with text output");
+    }
+    let root = if publishers_args.prepend_headers || publishers_args.webhook.is_some() {
+        Some(workspace_root(metadata_args.clone())?)
+    } else {
+        None
+    };
+    let supplemental = SupplementalPublishers::load(args.publishers_file.as_deref())?;
+    let include_members = metadata_args.include_published_members;
+    let dependencies = sourced_dependencies(metadata_args.clone())?;
+    let versions = publishers_args
+        .show_versions
+        .then(|| crate_versions(&dependencies));
+    let members = published_workspace_members(include_members, &dependencies, &args)?;
+    complain_about_non_crates_io_crates(&dependencies, &members, &reporting);
+    // Anonymized once filtered, so that the filters match the real logins
+    let fetch_args = QueryCommandArgs {
+        anonymize: false,
+        ..args.clone()
+    };
+    let (mut publisher_users, mut publisher_teams) =
+        fetch_owners_of_crates(&dependencies, &members, &metadata_args, &fetch_args)?;
+    let summary = args.summary.then(|| {
+        PublisherSummary::new(
+            &publisher_users,
+            &publisher_teams,
+            args.since_commit.is_some(),
+        )
+    });
+    retain_by_owner_count(&mut publisher_users, &mut publisher_teams, &owner_count);
+
+    if count {
+        print_counts(&publisher_users, &publisher_teams);
+        if let Some(summary) = summary {
+            summary.print();
+        }
+        return Ok(true);
+    }
+    // Before anything is counted, so that e.g. the `--diffable` header matches the list
+    retain_shown_publishers(&mut publisher_users, &mut publisher_teams, &publishers_args);
+    if args.anonymize {
+        let mut anonymized = BTreeMap::new();
+        let publishers = publisher_users
+            .values_mut()
+            .chain(publisher_teams.values_mut());
+        anonymize(
+            publishers.flatten(),
+            &anonymize_key(&args)?,
+            &mut anonymized,
+        );
+        print_anonymized(&anonymized);
+    }
+
+    if let (Some(url), Some(root)) = (&publishers_args.webhook, &root) {
+        let listed = listed_publishers(&publisher_users, &publisher_teams);
+        if !listed.is_empty() {
+            let violation = Violation::new("publishers", root.clone(), listed);
+            webhook::notify(url, &args.tls, &violation);
+        }
+    }
+
+    if let Some(field_list) = &publishers_args.fields {
+        let mut crates = publisher_users.clone();
+        for (crate_name, publishers) in &publisher_teams {
+            crates
+                .entry(crate_name.clone())
+                .or_default()
+                .extend(publishers.iter().cloned());
+        }
+        let crates: Vec<_> = crates.into_iter().collect();
+        let mut rows = fields::rows(&crates, &dependencies);
+        // Group the lines by publisher, like the rest of the output of this subcommand
+        rows.sort_by(|a, b| a.publisher_login().cmp(&b.publisher_login()));
+        if format == OutputFormat::Json {
+            fields::print_json(field_list, &rows, args.diffable)?;
+        } else {
+            if let Some(root) = root.as_ref().filter(|_| publishers_args.prepend_headers) {
+                let cache_age = CratesCache::new().age();
+                fields::print_headers(field_list, root, cache_age, args.diffable);
+            }
+            fields::print_text(field_list, &rows);
+        }
+        if let Some(summary) = summary {
+            summary.print();
+        }
+        let found = rows.iter().any(|row| row.publisher_login().is_some());
+        return Ok(!(publishers_args.check && found));
+    }
 
     // Group data by user rather than by crate
     let mut user_to_crate_map = transpose_publishers_map(&publisher_users);
     let mut team_to_crate_map = transpose_publishers_map(&publisher_teams);
+    let found = !user_to_crate_map.is_empty() || !team_to_crate_map.is_empty();
 
     // Sort crate names alphabetically
     user_to_crate_map.values_mut().for_each(|c| c.sort());
     team_to_crate_map.values_mut().for_each(|c| c.sort());
 
-    if diffable {
+    if format == OutputFormat::Json {
+        print_json(
+            user_to_crate_map,
+            team_to_crate_map,
+            versions.as_ref(),
+            args.diffable,
+        )?;
+        return Ok(!(publishers_args.check && found));
+    }
+
+    if args.diffable {
+        println!("{}", diffable_header(&publisher_users, &publisher_teams));
         // empty map just means 0 loop iterations here
         let sorted_map = sort_transposed_map_for_diffing(user_to_crate_map);
         for (user, crates) in &sorted_map {
-            let crate_list = comma_separated_list(crates);
-            println!("user \"{}\": {}", &user.login, crate_list);
+            let crate_list =
+                separated_list(&crate_labels(crates, versions.as_ref()), args.separator());
+            println!("user \"{}\": {}", user.display_safe_login(), crate_list);
         }
-    } else if !publisher_users.is_empty() {
+    } else if !user_to_crate_map.is_empty() {
         println!("\nThe following individuals can publish updates for your dependencies:\n");
         let map_for_display = sort_transposed_map_for_display(user_to_crate_map);
-        for (i, (user, crates)) in map_for_display.iter().enumerate() {
-            // We do not print usernames, since you can embed terminal control sequences in them
-            // and erase yourself from the output that way.
-            let crate_list = comma_separated_list(crates);
-            println!(" {}. {} via crates: {}", i + 1, &user.login, crate_list);
+        let lines: Vec<_> = map_for_display
+            .iter()
+            .map(|(user, crates)| {
+                // Logins are sanitized, since you can embed terminal control sequences in them
+                // and erase yourself from the output that way.
+                let safe_login = user.display_safe_login();
+                let crate_list =
+                    separated_list(&crate_labels(crates, versions.as_ref()), args.separator());
+                let tier = trust_prefix(trust_tiers, crates.len());
+                let login = paint(colors, &safe_login, crates.len());
+                let annotation = supplemental.annotation(&user.login);
+                let label = format!("{}{}{}", tier, safe_login, annotation);
+                (
+                    label,
+                    format!("{}{}{}", tier, login, annotation),
+                    crate_list,
+                )
+            })
+            .collect();
+        print_lines(&lines, &publishers_args.align);
+        if !args.no_summary() {
+            eprintln!("\nNote: there may be outstanding publisher invitations. crates.io provides no way to list them.");
+            eprintln!("See https://github.com/rust-lang/crates.io/issues/2868 for more info.");
         }
-        eprintln!("\nNote: there may be outstanding publisher invitations. crates.io provides no way to list them.");
-        eprintln!("See https://github.com/rust-lang/crates.io/issues/2868 for more info.");
     }
 
-    if diffable {
+    if args.diffable {
         let sorted_map = sort_transposed_map_for_diffing(team_to_crate_map);
         for (team, crates) in &sorted_map {
-            let crate_list = comma_separated_list(crates);
-            println!("team \"{}\": {}", &team.login, crate_list);
+            let crate_list =
+                separated_list(&crate_labels(crates, versions.as_ref()), args.separator());
+            println!("team \"{}\": {}", team.display_safe_login(), crate_list);
         }
-    } else if !publisher_teams.is_empty() {
+    } else if !team_to_crate_map.is_empty() {
         println!(
             "\nAll members of the following teams can publish updates for your dependencies:\n"
         );
         let map_for_display = sort_transposed_map_for_display(team_to_crate_map);
-        for (i, (team, crates)) in map_for_display.iter().enumerate() {
-            let crate_list = comma_separated_list(crates);
-            if let (true, Some(org)) = (
-                team.login.starts_with("github:"),
-                team.login.split(':').nth(1),
-            ) {
-                println!(
-                    " {}. \"{}\" (https://github.com/{}) via crates: {}",
-                    i + 1,
-                    &team.login,
-                    org,
-                    crate_list
-                );
-            } else {
-                println!(" {}. \"{}\" via crates: {}", i + 1, &team.login, crate_list);
-            }
+        let lines: Vec<_> = map_for_display
+            .iter()
+            .map(|(team, crates)| {
+                let safe_login = team.display_safe_login();
+                let crate_list =
+                    separated_list(&crate_labels(crates, versions.as_ref()), args.separator());
+                let tier = trust_prefix(trust_tiers, crates.len());
+                let quoted = format!("\"{}\"", safe_login);
+                let login = paint(colors, &quoted, crates.len());
+                let org_link = extract_github_org(&safe_login)
+                    .map(|org| format!(" (https://github.com/{})", org))
+                    .unwrap_or_default();
+                let annotation = supplemental.annotation(&team.login);
+                let label = format!("{}{}{}{}", tier, quoted, org_link, annotation);
+                let decorated = format!("{}{}{}{}", tier, login, org_link, annotation);
+                (label, decorated, crate_list)
+            })
+            .collect();
+        print_lines(&lines, &publishers_args.align);
+        if !args.no_summary() {
+            eprintln!("\nGithub teams are black boxes. It's impossible to get the member list without explicit permission.");
+        }
+    }
+
+    if let Some(tiers) = trust_tiers {
+        if !args.diffable {
+            println!(
+                "\nTrust levels: [HIGH] {}+ crates, [MEDIUM] {}-{} crates, [LOW] fewer than {} crates",
+                tiers.high,
+                tiers.medium,
+                tiers.high.saturating_sub(1),
+                tiers.medium
+            );
+        }
+    }
+
+    if let Some(summary) = summary {
+        summary.print();
+    }
+    Ok(!(publishers_args.check && found))
+}
+
+/// Prints the numbered `(label, publisher, crate list)` lines, where `publisher` is displayed
+/// as `label`. With `--align` the crate lists start in the same column.
+fn print_lines(lines: &[(String, String, String)], align: &AlignArgs) {
+    let column = align.column(lines.iter().map(|(label, _, _)| label.as_str()));
+    let index_width = lines.len().to_string().len();
+    if let Some(column) = &column {
+        // The column is preceded by the index, e.g. ` 12. `
+        column.warn_if_too_wide(index_width + 3);
+    }
+    for (i, (label, publisher, crate_list)) in lines.iter().enumerate() {
+        match &column {
+            Some(column) => println!(
+                " {:>width$}. {} via crates: {}",
+                i + 1,
+                column.pad(label, publisher),
+                crate_list,
+                width = index_width
+            ),
+            None => println!(" {}. {} via crates: {}", i + 1, publisher, crate_list),
         }
-        eprintln!("\nGithub teams are black boxes. It's impossible to get the member list without explicit permission.");
+    }
+}
+
+fn paint(colors: Option<ColorThreshold>, login: &str, crate_count: usize) -> String {
+    match colors {
+        Some(colors) => colors.paint(login, crate_count),
+        None => login.to_owned(),
+    }
+}
+
+/// The `[HIGH] ` etc. annotation shown before a publisher with `--trust-level`
+fn trust_prefix(tiers: Option<TrustLevel>, crate_count: usize) -> String {
+    match tiers {
+        Some(tiers) => format!("{} ", tiers.tier(crate_count)),
+        None => String::new(),
+    }
+}
+
+/// The versions of the crates.io crates in the dependency graph, for `--show-versions`
+fn crate_versions(dependencies: &[SourcedPackage]) -> BTreeMap<String, Vec<String>> {
+    let mut versions: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for dependency in dependencies {
+        if dependency.source == PkgSource::CratesIo {
+            versions
+                .entry(dependency.package.name.clone())
+                .or_default()
+                .push(dependency.package.version.to_string());
+        }
+    }
+    for crate_versions in versions.values_mut() {
+        crate_versions.sort_unstable();
+        crate_versions.dedup();
+    }
+    versions
+}
+
+/// The crates as listed in text output, followed by their versions with `--show-versions`
+fn crate_labels(
+    crates: &[String],
+    versions: Option<&BTreeMap<String, Vec<String>>>,
+) -> Vec<String> {
+    crates
+        .iter()
+        .map(
+            |crate_name| match versions.and_then(|v| v.get(crate_name)) {
+                Some(crate_versions) => format!("{} ({})", crate_name, crate_versions.join(", ")),
+                None => crate_name.clone(),
+            },
+        )
+        .collect()
+}
+
+/// Lists users before teams, each sorted the same way as in the text output
+fn print_json(
+    user_to_crate_map: BTreeMap<PublisherData, Vec<String>>,
+    team_to_crate_map: BTreeMap<PublisherData, Vec<String>>,
+    versions: Option<&BTreeMap<String, Vec<String>>>,
+    diffable: bool,
+) -> Result<(), anyhow::Error> {
+    let sort = if diffable {
+        sort_transposed_map_for_diffing
+    } else {
+        sort_transposed_map_for_display
+    };
+    let publishers = sort(user_to_crate_map)
+        .into_iter()
+        .chain(sort(team_to_crate_map))
+        .map(|(publisher, crates)| {
+            let versions = versions.map(|versions| {
+                crates
+                    .iter()
+                    .filter_map(|name| Some((name.clone(), versions.get(name)?.clone())))
+                    .collect()
+            });
+            PublisherCrates {
+                publisher,
+                crates,
+                versions,
+            }
+        })
+        .collect();
+    let output = PublishersOutput { publishers };
+    let stdout = std::io::stdout();
+    let handle = stdout.lock();
+    if diffable {
+        serde_json::to_writer_pretty(handle, &output)?;
+    } else {
+        serde_json::to_writer(handle, &output)?;
     }
     Ok(())
 }
 
+/// Removes the publishers left out by `--publisher-filter-regex`, `--only-users` and the like,
+/// and with them the crates that none of the remaining publishers can publish
+fn retain_shown_publishers(
+    publisher_users: &mut BTreeMap<String, Vec<PublisherData>>,
+    publisher_teams: &mut BTreeMap<String, Vec<PublisherData>>,
+    publishers_args: &PublishersArgs,
+) {
+    if !publishers_args.filters_publishers() && !publishers_args.filters_kind() {
+        return;
+    }
+    for publishers in publisher_users
+        .values_mut()
+        .chain(publisher_teams.values_mut())
+    {
+        publishers.retain(|p| {
+            publishers_args.shows_publisher(&p.login, p.id) && publishers_args.shows_kind(p.kind)
+        });
+    }
+    let is_empty = |publishers: &BTreeMap<String, Vec<PublisherData>>, crate_name: &str| {
+        publishers.get(crate_name).map_or(true, Vec::is_empty)
+    };
+    let unpublished: Vec<String> = publisher_users
+        .keys()
+        .chain(publisher_teams.keys())
+        .filter(|name| is_empty(publisher_users, name) && is_empty(publisher_teams, name))
+        .cloned()
+        .collect();
+    for crate_name in unpublished {
+        publisher_users.remove(&crate_name);
+        publisher_teams.remove(&crate_name);
+    }
+}
+
+/// The publishers that are listed, i.e. the ones that fail `--check`,
+/// with the crates that each of them can publish
+fn listed_publishers(
+    publisher_users: &BTreeMap<String, Vec<PublisherData>>,
+    publisher_teams: &BTreeMap<String, Vec<PublisherData>>,
+) -> Vec<ViolatingPublisher> {
+    let user_to_crate_map = transpose_publishers_map(publisher_users);
+    let team_to_crate_map = transpose_publishers_map(publisher_teams);
+    let mut listed: Vec<ViolatingPublisher> = user_to_crate_map
+        .into_iter()
+        .chain(team_to_crate_map)
+        .map(|(publisher, mut crates)| {
+            crates.sort();
+            ViolatingPublisher {
+                login: publisher.login,
+                kind: publisher.kind,
+                crates,
+            }
+        })
+        .collect();
+    listed.sort_by(|a, b| (a.kind, &a.login).cmp(&(b.kind, &b.login)));
+    listed
+}
+
 /// Turns a crate-to-publishers mapping into publisher-to-crates mapping.
 /// [`BTreeMap`] is used because [`PublisherData`] doesn't implement Hash.
 fn transpose_publishers_map(
@@ -113,3 +525,183 @@ fn sort_transposed_map_for_diffing(
     result.sort_unstable_by_key(|(publisher, _crates)| publisher.login.clone());
     result
 }
+
+const JSON_SCHEMA: &str = r##"{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "PublishersOutput",
+  "description": "The output of `publishers --format json`",
+  "type": "object",
+  "required": [
+    "publishers"
+  ],
+  "properties": {
+    "publishers": {
+      "description": "Publishers along with the crates each of them can publish",
+      "type": "array",
+      "items": {
+        "$ref": "#/definitions/PublisherCrates"
+      }
+    }
+  },
+  "definitions": {
+    "PublisherCrates": {
+      "type": "object",
+      "required": [
+        "crates",
+        "publisher"
+      ],
+      "properties": {
+        "crates": {
+          "description": "Names of the crates that the publisher can publish",
+          "type": "array",
+          "items": {
+            "type": "string"
+          }
+        },
+        "publisher": {
+          "$ref": "#/definitions/PublisherData"
+        },
+        "versions": {
+          "description": "Maps the names of the crates to their versions in the dependency graph. Only present with `--show-versions`",
+          "type": [
+            "object",
+            "null"
+          ],
+          "additionalProperties": {
+            "type": "array",
+            "items": {
+              "type": "string"
+            }
+          }
+        }
+      }
+    },
+    "PublisherData": {
+      "description": "Data about a single publisher received from a crates.io API endpoint",
+      "type": "object",
+      "required": [
+        "id",
+        "kind",
+        "login"
+      ],
+      "properties": {
+        "avatar": {
+          "description": "Avatar image URL",
+          "type": [
+            "string",
+            "null"
+          ]
+        },
+        "email": {
+          "description": "Email address. Only present with `--include-email` when using the live crates.io API, never when using the cache",
+          "type": [
+            "string",
+            "null"
+          ]
+        },
+        "id": {
+          "type": "integer",
+          "format": "uint64",
+          "minimum": 0.0
+        },
+        "kind": {
+          "$ref": "#/definitions/PublisherKind"
+        },
+        "login": {
+          "type": "string"
+        },
+        "name": {
+          "description": "Display name. It is NOT guaranteed to be unique!",
+          "type": [
+            "string",
+            "null"
+          ]
+        }
+      }
+    },
+    "PublisherKind": {
+      "type": "string",
+      "enum": [
+        "team",
+        "user"
+      ]
+    }
+  }
+}"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{args_parser, CliArgs};
+    use crate::publishers::{publisher, PublisherKind};
+    use bpaf::Args;
+    use schemars::schema_for;
+
+    #[test]
+    fn test_json_schema() {
+        let schema = schema_for!(PublishersOutput);
+        let schema = serde_json::to_string_pretty(&schema).unwrap();
+        assert_eq!(schema, JSON_SCHEMA);
+    }
+
+    #[test]
+    fn test_crate_labels() {
+        let crates = ["serde".to_owned(), "syn".to_owned(), "unknown".to_owned()];
+        let versions = BTreeMap::from([
+            ("serde".to_owned(), vec!["1.0.197".to_owned()]),
+            (
+                "syn".to_owned(),
+                vec!["1.0.109".to_owned(), "2.0.48".to_owned()],
+            ),
+        ]);
+        assert_eq!(
+            crate_labels(&crates, Some(&versions)),
+            ["serde (1.0.197)", "syn (1.0.109, 2.0.48)", "unknown"]
+        );
+        assert_eq!(crate_labels(&crates, None), crates);
+    }
+
+    #[test]
+    fn filters_before_counting() {
+        let dtolnay = publisher(1, "dtolnay", PublisherKind::user);
+        let mut users = BTreeMap::from([
+            ("serde".to_owned(), vec![dtolnay.clone()]),
+            (
+                "rand".to_owned(),
+                vec![publisher(2, "dhardy", PublisherKind::user)],
+            ),
+            ("orphan".to_owned(), Vec::new()),
+        ]);
+        let mut teams = BTreeMap::from([(
+            "serde".to_owned(),
+            vec![publisher(3, "github:serde-rs:owners", PublisherKind::team)],
+        )]);
+        let parse = |args: &[&str]| match args_parser().run_inner(Args::from(args)).unwrap() {
+            CliArgs::Publishers {
+                publishers_args, ..
+            } => publishers_args,
+            _ => unreachable!(),
+        };
+
+        retain_shown_publishers(&mut users, &mut teams, &parse(&["publishers"]));
+        assert_eq!(
+            diffable_header(&users, &teams),
+            "# unique_users=2 unique_teams=1 unique_orgs=1 unique_crates=3"
+        );
+
+        let filters = parse(&[
+            "publishers",
+            "--only-users",
+            "--publisher-filter-regex",
+            "^dt",
+        ]);
+        retain_shown_publishers(&mut users, &mut teams, &filters);
+        assert_eq!(
+            diffable_header(&users, &teams),
+            "# unique_users=1 unique_teams=0 unique_orgs=0 unique_crates=1"
+        );
+        assert_eq!(users, BTreeMap::from([("serde".to_owned(), vec![dtolnay])]));
+        assert!(teams.values().all(Vec::is_empty));
+        assert_eq!(listed_publishers(&users, &teams).len(), 1);
+    }
+}
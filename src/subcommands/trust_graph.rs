@@ -0,0 +1,234 @@
+//! `trust-graph` subcommand prints the crates and their publishers as a graph,
+//! with an edge from each publisher to every crate it can publish.
+
+use crate::cli::QueryCommandArgs;
+use crate::output::GraphFormat;
+use crate::publishers::{
+    fetch_owners_of_crates, published_workspace_members, PublisherData, PublisherKind,
+};
+use crate::{common::sourced_dependencies, MetadataArgs};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+/// The values of `--format`, listed by `--help-formats`
+pub const FORMATS: &[(&str, &str)] = &[
+    (
+        "dot",
+        "Graphviz DOT, e.g. to render with `dot -Tsvg`. The default",
+    ),
+    (
+        "mermaid",
+        "Mermaid flowchart, which GitHub renders in Markdown",
+    ),
+];
+
+/// Crates with more publishers are trusting more people, and are colored accordingly
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Risk {
+    /// No publisher on record
+    Unknown,
+    /// A single publisher
+    Low,
+    /// 2 to 5 publishers
+    Medium,
+    /// 6 or more publishers
+    High,
+}
+
+impl Risk {
+    fn of(publisher_count: usize) -> Self {
+        match publisher_count {
+            0 => Risk::Unknown,
+            1 => Risk::Low,
+            2..=5 => Risk::Medium,
+            _ => Risk::High,
+        }
+    }
+
+    fn dot_color(self) -> &'static str {
+        match self {
+            Risk::Unknown => "lightgrey",
+            Risk::Low => "palegreen",
+            Risk::Medium => "yellow",
+            Risk::High => "tomato",
+        }
+    }
+
+    fn mermaid_class(self) -> &'static str {
+        match self {
+            Risk::Unknown => "unknown",
+            Risk::Low => "low",
+            Risk::Medium => "medium",
+            Risk::High => "high",
+        }
+    }
+}
+
+/// The crates and publishers, numbered so that node IDs are valid in both formats
+struct Graph<'a> {
+    crates: Vec<(&'a str, Risk)>,
+    /// Each publisher with the indices of the crates it can publish
+    publishers: Vec<(&'a PublisherData, Vec<usize>)>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(owners: &'a BTreeMap<String, Vec<PublisherData>>) -> Self {
+        let mut crates = Vec::new();
+        let mut publishers: BTreeMap<(PublisherKind, &str), (&PublisherData, Vec<usize>)> =
+            BTreeMap::new();
+        for (index, (crate_name, crate_publishers)) in owners.iter().enumerate() {
+            crates.push((crate_name.as_str(), Risk::of(crate_publishers.len())));
+            for publisher in crate_publishers {
+                publishers
+                    .entry((publisher.kind, publisher.login.as_str()))
+                    .or_insert_with(|| (publisher, Vec::new()))
+                    .1
+                    .push(index);
+            }
+        }
+        Graph {
+            crates,
+            publishers: publishers.into_values().collect(),
+        }
+    }
+
+    fn write_dot(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "digraph trust {{")?;
+        writeln!(out, "    rankdir=LR;")?;
+        writeln!(out, "    node [style=filled];")?;
+        for (index, (crate_name, risk)) in self.crates.iter().enumerate() {
+            writeln!(
+                out,
+                "    crate{} [label=\"{}\", shape=box, fillcolor={}];",
+                index,
+                crate_name,
+                risk.dot_color()
+            )?;
+        }
+        for (index, (publisher, _)) in self.publishers.iter().enumerate() {
+            let shape = match publisher.kind {
+                PublisherKind::team => "hexagon",
+                PublisherKind::user => "ellipse",
+            };
+            writeln!(
+                out,
+                "    publisher{} [label=\"{}\", shape={}, fillcolor=white];",
+                index, publisher.login, shape
+            )?;
+        }
+        for (index, (_, crates)) in self.publishers.iter().enumerate() {
+            for crate_index in crates {
+                writeln!(out, "    publisher{} -> crate{};", index, crate_index)?;
+            }
+        }
+        writeln!(out, "}}")
+    }
+
+    fn write_mermaid(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "flowchart TD")?;
+        for (index, (crate_name, risk)) in self.crates.iter().enumerate() {
+            writeln!(
+                out,
+                "    crate{}[\"{}\"]:::{}",
+                index,
+                crate_name,
+                risk.mermaid_class()
+            )?;
+        }
+        for (index, (publisher, _)) in self.publishers.iter().enumerate() {
+            match publisher.kind {
+                PublisherKind::team => {
+                    writeln!(out, "    publisher{}{{{{\"{}\"}}}}", index, publisher.login)?
+                }
+                PublisherKind::user => {
+                    writeln!(out, "    publisher{}([\"{}\"])", index, publisher.login)?
+                }
+            }
+        }
+        for (index, (_, crates)) in self.publishers.iter().enumerate() {
+            for crate_index in crates {
+                writeln!(out, "    publisher{} --> crate{}", index, crate_index)?;
+            }
+        }
+        writeln!(out, "    classDef unknown fill:#d3d3d3")?;
+        writeln!(out, "    classDef low fill:#98fb98")?;
+        writeln!(out, "    classDef medium fill:#ffff00")?;
+        writeln!(out, "    classDef high fill:#ff6347")
+    }
+}
+
+pub fn trust_graph(
+    metadata_args: MetadataArgs,
+    args: QueryCommandArgs,
+    format: GraphFormat,
+) -> Result<(), anyhow::Error> {
+    let include_members = metadata_args.include_published_members;
+    let dependencies = sourced_dependencies(metadata_args.clone())?;
+    let members = published_workspace_members(include_members, &dependencies, &args)?;
+    let (mut owners, publisher_teams) =
+        fetch_owners_of_crates(&dependencies, &members, &metadata_args, &args)?;
+    for (crate_name, publishers) in publisher_teams {
+        owners.entry(crate_name).or_default().extend(publishers);
+    }
+
+    let graph = Graph::new(&owners);
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match format {
+        GraphFormat::Dot => graph.write_dot(&mut out)?,
+        GraphFormat::Mermaid => graph.write_mermaid(&mut out)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::publishers::publisher;
+
+    fn render(format: GraphFormat) -> String {
+        let mut owners = BTreeMap::new();
+        owners.insert(
+            "serde".to_owned(),
+            vec![
+                publisher(1, "dtolnay", PublisherKind::user),
+                publisher(2, "github:serde-rs:owners", PublisherKind::team),
+            ],
+        );
+        owners.insert(
+            "syn".to_owned(),
+            vec![publisher(1, "dtolnay", PublisherKind::user)],
+        );
+        let mut out = Vec::new();
+        let graph = Graph::new(&owners);
+        match format {
+            GraphFormat::Dot => graph.write_dot(&mut out).unwrap(),
+            GraphFormat::Mermaid => graph.write_mermaid(&mut out).unwrap(),
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn dot_graph() {
+        let dot = render(GraphFormat::Dot);
+        assert!(dot.starts_with("digraph trust {\n"));
+        assert!(dot.contains("crate0 [label=\"serde\", shape=box, fillcolor=yellow];"));
+        assert!(dot.contains("crate1 [label=\"syn\", shape=box, fillcolor=palegreen];"));
+        assert!(dot.contains("[label=\"github:serde-rs:owners\", shape=hexagon"));
+        // a publisher of several crates is a single node
+        assert_eq!(dot.matches("label=\"dtolnay\"").count(), 1);
+        assert_eq!(dot.matches(" -> ").count(), 3);
+    }
+
+    #[test]
+    fn mermaid_graph() {
+        let mermaid = render(GraphFormat::Mermaid);
+        assert!(mermaid.starts_with("flowchart TD\n"));
+        assert!(mermaid.contains("crate0[\"serde\"]:::medium"));
+        assert!(mermaid.contains("{{\"github:serde-rs:owners\"}}"));
+        assert!(mermaid.contains("([\"dtolnay\"])"));
+        assert_eq!(mermaid.matches(" --> ").count(), 3);
+    }
+}
@@ -1,16 +1,117 @@
 use crate::api_client::RateLimitedClient;
-use crate::crates_cache::{CratesCache, DownloadState};
+use crate::cli::TlsArgs;
+use crate::crates_cache::{CacheTable, CratesCache, DownloadState};
+use crate::output::OutputFormat;
+use crate::publishers::PUBLISHER_TABLES;
 use anyhow::bail;
+use serde::Serialize;
+use std::io::Write;
 
-pub fn update(max_age: std::time::Duration) -> Result<(), anyhow::Error> {
+/// The values of `--format` of `--update-check`, listed by `--help-formats`
+pub const FORMATS: &[(&str, &str)] = &[
+    (
+        "text",
+        "A sentence saying whether a newer dump is available, the default",
+    ),
+    (
+        "json",
+        "Object with `up_to_date`, `cache_age_seconds` and `etag`",
+    ),
+];
+
+/// The output of `update --update-check --format json`
+#[derive(Debug, Serialize)]
+struct UpdateCheck {
+    up_to_date: bool,
+    cache_age_seconds: Option<u64>,
+    etag: Option<String>,
+}
+
+impl UpdateCheck {
+    /// Writes the result in `format`: a sentence, or a JSON object on one line
+    fn write(&self, out: &mut impl Write, format: OutputFormat) -> Result<(), anyhow::Error> {
+        match format {
+            OutputFormat::Json => {
+                serde_json::to_writer(&mut *out, self)?;
+                writeln!(out)?;
+            }
+            OutputFormat::Text => match (self.up_to_date, self.cache_age_seconds) {
+                (true, Some(age)) => writeln!(out, "Up to date (cache age: {} hours)", age / 3600)?,
+                (true, None) => writeln!(out, "Up to date")?,
+                (false, _) => writeln!(out, "Update available")?,
+            },
+            OutputFormat::GithubActions | OutputFormat::GithubMarkdown => {
+                unreachable!("rejected while parsing the arguments")
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The tables to download. The publisher tables are always needed, the others only by
+/// the features that use them, since the download ends early once all of them are stored
+fn tables_to_download(with_yanked: bool, with_categories: bool) -> Vec<CacheTable> {
+    let mut tables = PUBLISHER_TABLES.to_vec();
+    if with_yanked {
+        tables.push(CacheTable::Versions);
+    }
+    if with_categories {
+        tables.extend([CacheTable::Categories, CacheTable::CrateCategories]);
+    }
+    tables
+}
+
+pub fn update(
+    max_age: std::time::Duration,
+    offline: bool,
+    tls: &TlsArgs,
+    invalidate_crates: &[String],
+    with_yanked: bool,
+    with_categories: bool,
+    max_download_mb: u64,
+) -> Result<(), anyhow::Error> {
+    if !invalidate_crates.is_empty() {
+        // Only touches the local cache, so this works offline too
+        let mut cache = CratesCache::new();
+        let removed = match cache.invalidate_crates(invalidate_crates) {
+            Ok(removed) => removed,
+            Err(error) => bail!("Could not invalidate the cached crates!\n{}", error),
+        };
+        eprintln!(
+            "Invalidated {} crates. Their owners will be fetched from the live API.",
+            removed
+        );
+        return Ok(());
+    }
+    if offline {
+        bail!("Cannot update cache in offline mode.");
+    }
     let mut cache = CratesCache::new();
-    let mut client = RateLimitedClient::new();
+    let mut client = RateLimitedClient::with_tls(tls)?;
 
-    match cache.download(&mut client, max_age) {
+    let max_size = max_download_mb.saturating_mul(1024 * 1024);
+    let tables = tables_to_download(with_yanked, with_categories);
+    match cache.download(&mut client, max_age, &tables, max_size) {
         Ok(state) => match state {
             DownloadState::Fresh => eprintln!("No updates found"),
             DownloadState::Expired => {
                 eprintln!("Successfully updated to the newest daily data dump.");
+                // Doubles as a sanity check that the dump was stored completely
+                if let (Some(crates), Some(users), Some(teams)) = (
+                    cache.total_crate_count(),
+                    cache.total_user_count(),
+                    cache.total_team_count(),
+                ) {
+                    eprintln!(
+                        "Cache contains {} crates, {} users, {} teams.",
+                        indicatif::HumanCount(crates as u64),
+                        indicatif::HumanCount(users as u64),
+                        indicatif::HumanCount(teams as u64)
+                    );
+                }
+            }
+            DownloadState::Completed => {
+                eprintln!("The daily data dump is unchanged, downloaded the tables missing from the cache.")
             }
             DownloadState::Stale => bail!("Latest daily data dump matches the previous version, which was considered outdated."),
         },
@@ -18,3 +119,120 @@ pub fn update(max_age: std::time::Duration) -> Result<(), anyhow::Error> {
     }
     Ok(())
 }
+
+/// Returns whether the cache is up to date with the latest daily data dump
+pub fn update_check(
+    offline: bool,
+    tls: &TlsArgs,
+    format: OutputFormat,
+) -> Result<bool, anyhow::Error> {
+    if offline {
+        bail!("Cannot check for updates in offline mode.");
+    }
+    let mut cache = CratesCache::new();
+    let mut client = RateLimitedClient::with_tls(tls)?;
+    let up_to_date = match cache.is_up_to_date(&mut client) {
+        Ok(up_to_date) => up_to_date,
+        Err(error) => bail!("Could not check for a newer daily data dump!\n{}", error),
+    };
+    let check = UpdateCheck {
+        up_to_date,
+        cache_age_seconds: cache.age().map(|age| age.as_secs()),
+        etag: cache.etag(),
+    };
+    check.write(&mut std::io::stdout().lock(), format)?;
+    Ok(up_to_date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(check: &UpdateCheck, format: OutputFormat) -> String {
+        let mut out = Vec::new();
+        check.write(&mut out, format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn tables() {
+        use CacheTable::*;
+        assert_eq!(
+            tables_to_download(false, false),
+            [Crates, CrateOwners, Users, Teams]
+        );
+        assert_eq!(
+            tables_to_download(true, false),
+            [Crates, CrateOwners, Users, Teams, Versions]
+        );
+        assert_eq!(
+            tables_to_download(false, true),
+            [
+                Crates,
+                CrateOwners,
+                Users,
+                Teams,
+                Categories,
+                CrateCategories
+            ]
+        );
+        assert_eq!(
+            tables_to_download(true, true),
+            [
+                Crates,
+                CrateOwners,
+                Users,
+                Teams,
+                Versions,
+                Categories,
+                CrateCategories
+            ]
+        );
+    }
+
+    #[test]
+    fn update_check_text() {
+        let mut check = UpdateCheck {
+            up_to_date: true,
+            cache_age_seconds: Some(5 * 3600 + 59),
+            etag: Some("\"abc\"".to_owned()),
+        };
+        assert_eq!(
+            render(&check, OutputFormat::Text),
+            "Up to date (cache age: 5 hours)\n"
+        );
+        check.cache_age_seconds = None;
+        assert_eq!(render(&check, OutputFormat::Text), "Up to date\n");
+        check.up_to_date = false;
+        assert_eq!(render(&check, OutputFormat::Text), "Update available\n");
+    }
+
+    #[test]
+    fn update_check_json() {
+        let check = UpdateCheck {
+            up_to_date: false,
+            cache_age_seconds: Some(90_000),
+            etag: Some("\"abc\"".to_owned()),
+        };
+        let json = render(&check, OutputFormat::Json);
+        assert!(json.ends_with("}\n") && json.lines().count() == 1);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!({
+                "up_to_date": false,
+                "cache_age_seconds": 90_000,
+                "etag": "\"abc\"",
+            })
+        );
+        let check = UpdateCheck {
+            up_to_date: true,
+            cache_age_seconds: None,
+            etag: None,
+        };
+        let json = render(&check, OutputFormat::Json);
+        assert_eq!(
+            json,
+            "{\"up_to_date\":true,\"cache_age_seconds\":null,\"etag\":null}\n"
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! `--version-verbose` prints everything needed to reproduce an issue in a bug report
+
+use crate::crates_cache::{cache_format, CratesCache};
+use std::{env, process::Command};
+
+/// Versions of the dependencies that parse crates.io and `cargo metadata` data,
+/// as locked in Cargo.lock when this binary was built, see build.rs
+const DEPENDENCIES: &[(&str, &str)] = &[
+    ("ureq", env!("SUPPLY_CHAIN_UREQ_VERSION")),
+    (
+        "cargo_metadata",
+        env!("SUPPLY_CHAIN_CARGO_METADATA_VERSION"),
+    ),
+];
+
+pub fn version_verbose() {
+    println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    for (name, version) in DEPENDENCIES {
+        println!("{}: {}", name, version);
+    }
+    // Cargo only accepts valid package versions, so this is never empty
+    let format = cache_format(env!("CARGO_PKG_VERSION")).unwrap_or_default();
+    println!("cache format: {}", format);
+
+    // `cargo metadata` is run with the same cargo, so its version matters too
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned());
+    match Command::new(cargo).arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            print!("cargo: {}", String::from_utf8_lossy(&output.stdout))
+        }
+        _ => println!("cargo: not found"),
+    }
+
+    let mut cache = CratesCache::new();
+    match cache.location() {
+        Some(dir) => println!("cache location: {}", dir.display()),
+        None => println!("cache location: unknown"),
+    }
+    match cache.timestamp() {
+        Some(timestamp) => {
+            println!(
+                "cache timestamp: {}",
+                humantime::format_rfc3339_seconds(timestamp)
+            );
+            println!("cache etag: {}", cache.etag().as_deref().unwrap_or("none"));
+            match cache.tool_version() {
+                Some(version) => println!(
+                    "cache written by: {} (format {})",
+                    version,
+                    cache_format(&version).as_deref().unwrap_or("unknown")
+                ),
+                None => println!("cache written by: an older version"),
+            }
+        }
+        None => println!("cache: none"),
+    }
+}
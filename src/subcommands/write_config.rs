@@ -0,0 +1,18 @@
+//! `--write-config` writes a documented template of `supply-chain.toml` to the current directory
+
+use crate::config::{CONFIG_FILE_NAME, TEMPLATE};
+use anyhow::{bail, Context};
+use std::{fs, path::Path};
+
+pub fn write_config() -> Result<(), anyhow::Error> {
+    let path = Path::new(CONFIG_FILE_NAME);
+    if path.exists() {
+        bail!("{} already exists, not overwriting it", CONFIG_FILE_NAME);
+    }
+    fs::write(path, TEMPLATE).with_context(|| format!("Could not write {}", CONFIG_FILE_NAME))?;
+    eprintln!(
+        "Wrote {}. Uncomment the settings to use them.",
+        CONFIG_FILE_NAME
+    );
+    Ok(())
+}
@@ -0,0 +1,200 @@
+//! A brief pass/fail overview of the audited dependencies, printed by `--summary`
+
+use crate::publishers::{extract_github_org, PublisherData, PublisherKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Serialize, Default, Clone)]
+pub struct PublisherSummary {
+    /// Number of crates.io crates in the dependency graph
+    pub total_crates: usize,
+    /// Number of distinct users and teams that can publish the crates
+    pub unique_publishers: usize,
+    /// Names of crates.io crates for which no publisher is on record
+    pub crates_without_publisher: Vec<String>,
+    /// Names of the crates.io crates added since the git revision of `--since-commit`,
+    /// which are the only ones audited then. Only present with `--since-commit`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_since_baseline: Option<Vec<String>>,
+}
+
+impl PublisherSummary {
+    /// Computes the summary from the per-crate users and teams
+    /// as returned by [`fetch_owners_of_crates`](crate::publishers::fetch_owners_of_crates).
+    /// `since_baseline` tells whether they were narrowed down by `--since-commit`.
+    pub fn new(
+        users: &BTreeMap<String, Vec<PublisherData>>,
+        teams: &BTreeMap<String, Vec<PublisherData>>,
+        since_baseline: bool,
+    ) -> Self {
+        let crate_names: BTreeSet<&String> = users.keys().chain(teams.keys()).collect();
+        // User and team IDs are assigned independently, so the kind is part of the identity
+        let publishers: BTreeSet<(PublisherKind, u64)> = users
+            .values()
+            .chain(teams.values())
+            .flatten()
+            .map(|p| (p.kind, p.id))
+            .collect();
+        let crates_without_publisher = crate_names
+            .iter()
+            .filter(|name| {
+                users.get(**name).is_none_or(Vec::is_empty)
+                    && teams.get(**name).is_none_or(Vec::is_empty)
+            })
+            .map(|name| name.to_string())
+            .collect();
+        let new_since_baseline =
+            since_baseline.then(|| crate_names.iter().map(|name| name.to_string()).collect());
+        PublisherSummary {
+            total_crates: crate_names.len(),
+            unique_publishers: publishers.len(),
+            crates_without_publisher,
+            new_since_baseline,
+        }
+    }
+
+    pub fn print(&self) {
+        println!();
+        for crate_name in &self.crates_without_publisher {
+            println!("WARNING: crate '{}' has no publisher on record", crate_name);
+        }
+        if let Some(new_crates) = &self.new_since_baseline {
+            println!(
+                "{} crates are new since the baseline: {}",
+                new_crates.len(),
+                new_crates.join(", ")
+            );
+        }
+        let status = if self.is_ok() { "OK" } else { "WARNING" };
+        println!(
+            "{} unique publishers for {} crates: {}",
+            self.unique_publishers, self.total_crates, status
+        );
+    }
+
+    fn is_ok(&self) -> bool {
+        self.crates_without_publisher.is_empty()
+    }
+}
+
+/// Prints only the number of crates and of unique publishers, as requested by `--count`
+pub fn print_counts(
+    users: &BTreeMap<String, Vec<PublisherData>>,
+    teams: &BTreeMap<String, Vec<PublisherData>>,
+) {
+    println!(
+        "{} crates audited, {} unique user publishers, {} unique team publishers",
+        crate_count(users, teams),
+        unique_ids(users),
+        unique_ids(teams)
+    );
+}
+
+/// A comment line with the totals, printed at the top of `--diffable` text output
+/// so that scripts can extract them
+pub fn diffable_header(
+    users: &BTreeMap<String, Vec<PublisherData>>,
+    teams: &BTreeMap<String, Vec<PublisherData>>,
+) -> String {
+    let unique_orgs = teams
+        .values()
+        .flatten()
+        .filter_map(|team| extract_github_org(&team.login))
+        .collect::<BTreeSet<&str>>()
+        .len();
+    format!(
+        "# unique_users={} unique_teams={} unique_orgs={} unique_crates={}",
+        unique_ids(users),
+        unique_ids(teams),
+        unique_orgs,
+        crate_count(users, teams)
+    )
+}
+
+fn crate_count(
+    users: &BTreeMap<String, Vec<PublisherData>>,
+    teams: &BTreeMap<String, Vec<PublisherData>>,
+) -> usize {
+    users
+        .keys()
+        .chain(teams.keys())
+        .collect::<BTreeSet<_>>()
+        .len()
+}
+
+fn unique_ids(publishers: &BTreeMap<String, Vec<PublisherData>>) -> usize {
+    publishers
+        .values()
+        .flatten()
+        .map(|p| p.id)
+        .collect::<BTreeSet<u64>>()
+        .len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::publishers::publisher;
+    use serde_json::json;
+
+    fn owners() -> (
+        BTreeMap<String, Vec<PublisherData>>,
+        BTreeMap<String, Vec<PublisherData>>,
+    ) {
+        let dtolnay = publisher(1, "dtolnay", PublisherKind::user);
+        let users = BTreeMap::from([
+            ("serde".to_owned(), vec![dtolnay.clone()]),
+            ("syn".to_owned(), vec![dtolnay]),
+            ("orphan".to_owned(), Vec::new()),
+        ]);
+        let teams = BTreeMap::from([
+            (
+                "serde".to_owned(),
+                vec![publisher(1, "github:serde-rs:owners", PublisherKind::team)],
+            ),
+            ("syn".to_owned(), Vec::new()),
+            ("orphan".to_owned(), Vec::new()),
+        ]);
+        (users, teams)
+    }
+
+    #[test]
+    fn publisher_summary() {
+        let (users, teams) = owners();
+        let summary = PublisherSummary::new(&users, &teams, false);
+        assert_eq!(summary.total_crates, 3);
+        // A user and a team with the same ID are different publishers
+        assert_eq!(summary.unique_publishers, 2);
+        assert_eq!(summary.crates_without_publisher, ["orphan"]);
+        assert_eq!(summary.new_since_baseline, None);
+        assert!(!summary.is_ok());
+
+        let summary = PublisherSummary::new(&users, &BTreeMap::new(), true);
+        assert_eq!(
+            summary.new_since_baseline.unwrap(),
+            ["orphan", "serde", "syn"]
+        );
+        let summary = PublisherSummary::new(&BTreeMap::new(), &BTreeMap::new(), true);
+        assert_eq!(summary.new_since_baseline, Some(Vec::new()));
+        assert!(summary.is_ok());
+    }
+
+    #[test]
+    fn publisher_summary_json() {
+        let (users, teams) = owners();
+        let json = serde_json::to_value(PublisherSummary::new(&users, &teams, false)).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "total_crates": 3,
+                "unique_publishers": 2,
+                "crates_without_publisher": ["orphan"],
+            })
+        );
+        let json = serde_json::to_value(PublisherSummary::new(&users, &teams, true)).unwrap();
+        assert_eq!(
+            json["new_since_baseline"],
+            json!(["orphan", "serde", "syn"])
+        );
+    }
+}
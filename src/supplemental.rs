@@ -0,0 +1,123 @@
+//! Data about publishers that crates.io does not provide, such as verified identities,
+//! loaded from a local JSON file given with `--publishers-file`
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+#[cfg(test)]
+use schemars::JsonSchema;
+
+use crate::publishers::PublisherData;
+
+/// Supplemental data about a publisher, maintained by your organization
+#[cfg_attr(test, derive(JsonSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SupplementalPublisher {
+    /// Real name of the publisher, as verified by your organization
+    pub verified_name: String,
+    /// Verified email address
+    pub email: Option<String>,
+    /// Organization the publisher belongs to
+    pub organization: Option<String>,
+    /// Whether your organization trusts the publisher
+    pub trusted: bool,
+}
+
+impl SupplementalPublisher {
+    pub fn marker(&self) -> &'static str {
+        if self.trusted {
+            "[VERIFIED]"
+        } else {
+            "[UNTRUSTED]"
+        }
+    }
+
+    /// Shown after the login, e.g. ` [VERIFIED] Jane Doe <jane@example.com>, Example Corp`
+    pub fn annotation(&self) -> String {
+        let mut annotation = format!(" {} {}", self.marker(), self.verified_name);
+        if let Some(email) = &self.email {
+            annotation.push_str(&format!(" <{}>", email));
+        }
+        if let Some(organization) = &self.organization {
+            annotation.push_str(&format!(", {}", organization));
+        }
+        annotation
+    }
+}
+
+/// Maps publisher logins to the supplemental data about them
+#[derive(Debug, Default)]
+pub struct SupplementalPublishers(HashMap<String, SupplementalPublisher>);
+
+impl SupplementalPublishers {
+    /// Reads the `--publishers-file`, if one was given
+    pub fn load(path: Option<&Path>) -> Result<Self, anyhow::Error> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Could not read publishers file {}", path.display()))?;
+        let publishers = serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid publishers file {}", path.display()))?;
+        Ok(SupplementalPublishers(publishers))
+    }
+
+    /// The annotation to show after `login`, or an empty string if there is no data about it
+    pub fn annotation(&self, login: &str) -> String {
+        self.0
+            .get(login)
+            .map(SupplementalPublisher::annotation)
+            .unwrap_or_default()
+    }
+
+    /// The data about the given publishers, sorted by login
+    pub fn of<'a>(
+        &self,
+        publishers: impl IntoIterator<Item = &'a PublisherData>,
+    ) -> BTreeMap<String, SupplementalPublisher> {
+        publishers
+            .into_iter()
+            .filter_map(|p| Some((p.login.clone(), self.0.get(&p.login)?.clone())))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::publishers::{publisher, PublisherKind};
+
+    #[test]
+    fn annotations() {
+        let publishers: HashMap<String, SupplementalPublisher> = serde_json::from_str(
+            r#"{
+                "dtolnay": { "verified_name": "David Tolnay", "trusted": true },
+                "mallory": {
+                    "verified_name": "Mallory",
+                    "email": "mallory@example.com",
+                    "organization": "Example Corp",
+                    "trusted": false
+                }
+            }"#,
+        )
+        .unwrap();
+        let publishers = SupplementalPublishers(publishers);
+        assert_eq!(publishers.annotation("dtolnay"), " [VERIFIED] David Tolnay");
+        assert_eq!(
+            publishers.annotation("mallory"),
+            " [UNTRUSTED] Mallory <mallory@example.com>, Example Corp"
+        );
+        assert_eq!(publishers.annotation("someone-else"), "");
+
+        let known = publishers.of(&[
+            publisher(1, "dtolnay", PublisherKind::user),
+            publisher(2, "someone-else", PublisherKind::user),
+        ]);
+        assert_eq!(known.keys().collect::<Vec<_>>(), ["dtolnay"]);
+    }
+}
@@ -0,0 +1,207 @@
+//! Terminal UI for browsing the output of the `crates` subcommand, enabled by `--interactive`
+
+use crate::publishers::{PublisherData, PublisherKind};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+use std::{
+    io,
+    process::{Command, Stdio},
+};
+
+struct App {
+    crates: Vec<(String, Vec<PublisherData>)>,
+    /// Only crates with names containing this string are listed
+    filter: String,
+    /// Whether keystrokes currently go to the search filter
+    searching: bool,
+    /// Selection within the filtered list
+    list_state: ListState,
+}
+
+impl App {
+    fn new(crates: Vec<(String, Vec<PublisherData>)>) -> Self {
+        let mut app = App {
+            crates,
+            filter: String::new(),
+            searching: false,
+            list_state: ListState::default(),
+        };
+        app.reset_selection();
+        app
+    }
+
+    fn visible(&self) -> Vec<&(String, Vec<PublisherData>)> {
+        self.crates
+            .iter()
+            .filter(|(name, _)| name.contains(&self.filter))
+            .collect()
+    }
+
+    fn selected(&self) -> Option<&(String, Vec<PublisherData>)> {
+        let index = self.list_state.selected()?;
+        self.visible().get(index).copied()
+    }
+
+    fn reset_selection(&mut self) {
+        let selected = if self.visible().is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        self.list_state.select(selected);
+    }
+
+    fn move_selection(&mut self, up: bool) {
+        let len = self.visible().len();
+        if let Some(index) = self.list_state.selected() {
+            let index = if up {
+                index.saturating_sub(1)
+            } else {
+                (index + 1).min(len.saturating_sub(1))
+            };
+            self.list_state.select(Some(index));
+        }
+    }
+}
+
+/// Shows the crates and their publishers until the user quits
+pub fn run(crates: Vec<(String, Vec<PublisherData>)>) -> io::Result<()> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    // Restore the terminal even if drawing failed, otherwise the shell is left unusable
+    let result = event_loop(&mut terminal, App::new(crates));
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn event_loop<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, &mut app))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Up => app.move_selection(true),
+            KeyCode::Down => app.move_selection(false),
+            KeyCode::Esc if app.searching => {
+                app.searching = false;
+                app.filter.clear();
+                app.reset_selection();
+            }
+            KeyCode::Enter if app.searching => app.searching = false,
+            KeyCode::Backspace if app.searching => {
+                app.filter.pop();
+                app.reset_selection();
+            }
+            KeyCode::Char(c) if app.searching => {
+                app.filter.push(c);
+                app.reset_selection();
+            }
+            KeyCode::Char('/') => app.searching = true,
+            KeyCode::Char('o') => {
+                if let Some((crate_name, _)) = app.selected() {
+                    open_in_browser(&format!("https://crates.io/crates/{}", crate_name));
+                }
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(frame.size());
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(rows[0]);
+
+    let items: Vec<ListItem> = app
+        .visible()
+        .iter()
+        .map(|(crate_name, _)| ListItem::new(crate_name.clone()))
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("Crates"))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let details: Vec<Line> = match app.selected() {
+        Some((crate_name, publishers)) => {
+            let mut lines = vec![
+                Line::from(format!("https://crates.io/crates/{}", crate_name)),
+                Line::from(""),
+            ];
+            if publishers.is_empty() {
+                lines.push(Line::from("No publishers on record"));
+            }
+            for publisher in publishers {
+                let kind = match publisher.kind {
+                    PublisherKind::team => "team",
+                    PublisherKind::user => "user",
+                };
+                let login = publisher.display_safe_login();
+                let line = match publisher.display_safe_name() {
+                    Some(name) => format!("{} {} ({})", kind, login, name),
+                    None => format!("{} {}", kind, login),
+                };
+                lines.push(Line::from(line));
+            }
+            lines
+        }
+        None => vec![Line::from("No crate selected")],
+    };
+    let details =
+        Paragraph::new(details).block(Block::default().borders(Borders::ALL).title("Publishers"));
+
+    let status = if app.searching {
+        format!("/{}", app.filter)
+    } else {
+        "↑/↓ navigate  / search  o open on crates.io  q quit".to_owned()
+    };
+
+    frame.render_stateful_widget(list, panes[0], &mut app.list_state);
+    frame.render_widget(details, panes[1]);
+    frame.render_widget(Paragraph::new(status), rows[1]);
+}
+
+/// Failures are ignored, since there is nowhere to report them without disrupting the UI
+fn open_in_browser(url: &str) {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    let _ = command
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
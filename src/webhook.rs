@@ -0,0 +1,96 @@
+//! Notifies a webhook, e.g. of Slack or PagerDuty, when a check fails with exit code 2
+
+use crate::api_client::RateLimitedClient;
+use crate::cli::TlsArgs;
+use crate::publishers::PublisherKind;
+use serde::Serialize;
+use std::{collections::BTreeSet, path::PathBuf, time::SystemTime};
+use tracing::{info, warn};
+
+/// The JSON body POSTed to `--webhook`
+#[derive(Debug, Serialize)]
+pub struct Violation {
+    /// The subcommand whose check failed, e.g. `publishers`
+    pub subcommand: &'static str,
+    /// Root of the audited workspace
+    pub workspace: PathBuf,
+    pub audit_timestamp: String,
+    /// Names of the crates involved in the violation, sorted
+    pub crates: Vec<String>,
+    pub publishers: Vec<ViolatingPublisher>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ViolatingPublisher {
+    pub login: String,
+    pub kind: PublisherKind,
+    /// The crates that the publisher can publish
+    pub crates: Vec<String>,
+}
+
+impl Violation {
+    pub fn new(
+        subcommand: &'static str,
+        workspace: PathBuf,
+        publishers: Vec<ViolatingPublisher>,
+    ) -> Self {
+        let crates: BTreeSet<&String> = publishers.iter().flat_map(|p| &p.crates).collect();
+        Violation {
+            subcommand,
+            workspace,
+            audit_timestamp: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            crates: crates.into_iter().cloned().collect(),
+            publishers,
+        }
+    }
+}
+
+/// POSTs the violation to `url`. A failure is only reported as a warning,
+/// since the exit code signals the violation already.
+pub fn notify(url: &str, tls: &TlsArgs, violation: &Violation) {
+    // Without `with_token`, so that the crates.io token is never sent to a third party
+    let mut client = match RateLimitedClient::with_tls(tls) {
+        Ok(client) => client,
+        Err(error) => {
+            warn!("Could not notify the webhook: {}", error);
+            return;
+        }
+    };
+    match client.post(url).send_json(violation) {
+        Ok(response) if (200..300).contains(&response.status()) => {
+            info!("Notified the webhook of the violation");
+        }
+        Ok(response) => warn!("The webhook responded with status {}", response.status()),
+        Err(ureq::Error::Status(status, _)) => {
+            warn!("The webhook responded with status {}", status)
+        }
+        Err(error) => warn!("Could not notify the webhook: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn violating_crates() {
+        let publisher = |login: &str, kind, crates: &[&str]| ViolatingPublisher {
+            login: login.to_owned(),
+            kind,
+            crates: crates.iter().map(|name| (*name).to_owned()).collect(),
+        };
+        let violation = Violation::new(
+            "publishers",
+            PathBuf::from("/src/app"),
+            vec![
+                publisher("github:serde-rs:owners", PublisherKind::team, &["serde"]),
+                publisher("dtolnay", PublisherKind::user, &["serde", "anyhow"]),
+            ],
+        );
+        assert_eq!(violation.crates, ["anyhow", "serde"]);
+        let json = serde_json::to_value(&violation).unwrap();
+        assert_eq!(json["subcommand"], "publishers");
+        assert_eq!(json["publishers"][1]["kind"], "user");
+        assert_eq!(json["publishers"][1]["crates"][1], "anyhow");
+    }
+}